@@ -1,9 +1,14 @@
-use std::fmt;
+use std::{collections::BTreeSet, fmt};
 
 use crate::{
-    class_access_flags::ClassAccessFlags, class_file_field::ClassFileField,
-    class_file_method::ClassFileMethod, class_file_version::ClassFileVersion,
-    constant_pool::ConstantPool,
+    class_access_flags::ClassAccessFlags,
+    class_file_field::ClassFileField,
+    class_file_method::ClassFileMethod,
+    class_file_version::ClassFileVersion,
+    constant_pool::{ConstantPool, ConstantPoolEntry},
+    element_meta::ElementMeta,
+    enclosing_method::EnclosingMethod,
+    field_type::FieldType,
 };
 
 /// Represents the content of a .class file.
@@ -17,8 +22,46 @@ pub struct ClassFile {
     pub interfaces: Vec<String>,
     pub fields: Vec<ClassFileField>,
     pub methods: Vec<ClassFileMethod>,
-    pub deprecated: bool,
+    pub meta: ElementMeta,
     pub source_file: Option<String>,
+    /// Only present for local and anonymous classes
+    pub enclosing_method: Option<EnclosingMethod>,
+}
+
+impl ClassFile {
+    /// Returns the sorted names of all the classes referenced in the constant pool, which
+    /// include the owners of all the fields and methods used, excluding this class itself.
+    /// For arrays, the class of the elements is returned, and arrays of primitive types
+    /// are skipped. This allows to find the dependencies of a class without loading it.
+    pub fn referenced_classes(&self) -> Vec<String> {
+        let mut result: BTreeSet<String> = BTreeSet::new();
+        for entry in self.constants.iter() {
+            if let ConstantPoolEntry::ClassReference(name_index) = entry {
+                let Ok(name) = self.constants.text_of(*name_index) else {
+                    continue;
+                };
+                if let Some(class_name) = Self::referenced_class_name(name) {
+                    result.insert(class_name);
+                }
+            }
+        }
+        result.remove(&self.name);
+        result.into_iter().collect()
+    }
+
+    fn referenced_class_name(name: String) -> Option<String> {
+        if !name.starts_with('[') {
+            return Some(name);
+        }
+        let mut field_type = FieldType::parse(&name).ok()?;
+        while let FieldType::Array(element_type) = field_type {
+            field_type = *element_type;
+        }
+        match field_type {
+            FieldType::Object(class_name) => Some(class_name),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for ClassFile {
@@ -32,7 +75,7 @@ impl fmt::Display for ClassFile {
         writeln!(
             f,
             "flags: {:?}, deprecated: {}",
-            self.flags, self.deprecated
+            self.flags, self.meta.deprecated
         )?;
         writeln!(f, "interfaces: {:?}", self.interfaces)?;
         writeln!(f, "fields:")?;
@@ -1,6 +1,6 @@
 use std::{fmt, fmt::Formatter};
 
-use crate::{field_flags::FieldFlags, field_type::FieldType};
+use crate::{element_meta::ElementMeta, field_flags::FieldFlags, field_type::FieldType};
 
 /// Models a field in a class
 #[derive(Debug, PartialEq)]
@@ -8,9 +8,10 @@ pub struct ClassFileField {
     pub flags: FieldFlags,
     pub name: String,
     pub type_descriptor: FieldType,
-    /// Fields which model a constant (final) will have an attribute specifying the value
+    /// Static fields which model a constant (final) will have an attribute specifying the value.
+    /// The attribute is ignored for non-static fields, as required by the JVM specs.
     pub constant_value: Option<FieldConstantValue>,
-    pub deprecated: bool,
+    pub meta: ElementMeta,
 }
 
 impl fmt::Display for ClassFileField {
@@ -22,7 +23,11 @@ impl fmt::Display for ClassFileField {
             self.name,
             self.type_descriptor,
             self.constant_value,
-            if self.deprecated { " (deprecated)" } else { "" }
+            if self.meta.deprecated {
+                " (deprecated)"
+            } else {
+                ""
+            }
         )
     }
 }
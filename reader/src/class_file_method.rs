@@ -1,13 +1,23 @@
-use std::{fmt, fmt::Formatter};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    fmt,
+    fmt::Formatter,
+    sync::OnceLock,
+};
 
 use crate::{
     attribute::Attribute,
+    class_reader_error::ClassReaderError,
+    element_meta::ElementMeta,
     exception_table::ExceptionTable,
     field_type::{BaseType, FieldType},
-    instruction::Instruction,
+    instruction::{Instruction, SwitchTable},
     line_number_table::LineNumberTable,
     method_descriptor::MethodDescriptor,
     method_flags::MethodFlags,
+    program_counter::ProgramCounter,
+    stack_map_table::StackMapTable,
 };
 
 /// Models a method in a class
@@ -23,7 +33,7 @@ pub struct ClassFileMethod {
     // TODO: replace with some proper struct
     pub attributes: Vec<Attribute>,
     pub code: Option<ClassFileMethodCode>,
-    pub deprecated: bool,
+    pub meta: ElementMeta,
     /// List of exceptions in the `throws` clause of the method
     pub thrown_exceptions: Vec<String>,
 }
@@ -36,7 +46,11 @@ impl fmt::Display for ClassFileMethod {
             self.flags,
             self.name,
             self.parsed_type_descriptor,
-            if self.deprecated { " (deprecated)" } else { "" },
+            if self.meta.deprecated {
+                " (deprecated)"
+            } else {
+                ""
+            },
             self.thrown_exceptions,
         )?;
         if let Some(code) = &self.code {
@@ -55,6 +69,10 @@ impl ClassFileMethod {
         self.flags.contains(MethodFlags::NATIVE)
     }
 
+    pub fn is_abstract(&self) -> bool {
+        self.flags.contains(MethodFlags::ABSTRACT)
+    }
+
     pub fn is_void(&self) -> bool {
         self.parsed_type_descriptor.return_type.is_none()
     }
@@ -74,7 +92,7 @@ impl ClassFileMethod {
 }
 
 /// Code of a given method
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default)]
 pub struct ClassFileMethodCode {
     /// Maximum depth of the stack at any time
     pub max_stack: u16,
@@ -84,10 +102,81 @@ pub struct ClassFileMethodCode {
     pub code: Vec<u8>,
     pub exception_table: ExceptionTable,
     pub line_number_table: Option<LineNumberTable>,
+    /// Frames used by the type checking verifier, if the attribute is present
+    pub stack_map_table: Option<StackMapTable>,
 
     /// Generic unmapped attributes of the code
     // TODO: replace with some proper struct
     pub attributes: Vec<Attribute>,
+
+    /// Instructions decoded so far. See [instruction_at].
+    pub(crate) decoded_instructions: DecodedInstructionsCache,
+}
+
+/// The instructions of a method, decoded all at once the first time one of them is needed.
+/// Contains `None` if the code cannot be fully decoded.
+#[derive(Default)]
+pub(crate) struct DecodedInstructionsCache(OnceLock<Option<Vec<DecodedInstruction>>>);
+
+struct DecodedInstruction {
+    address: usize,
+    instruction: Instruction,
+    next_address: usize,
+    /// The jump table, for `tableswitch` and `lookupswitch`
+    switch_table: Option<SwitchTable>,
+}
+
+impl DecodedInstructionsCache {
+    fn decode(code: &[u8]) -> Option<Vec<DecodedInstruction>> {
+        let instructions = Instruction::parse_instructions(code).ok()?;
+        let next_addresses = instructions
+            .iter()
+            .skip(1)
+            .map(|(address, _)| *address)
+            .chain(std::iter::once(code.len()));
+        instructions
+            .iter()
+            .zip(next_addresses)
+            .map(|((address, instruction), next_address)| {
+                let switch_table = match instruction {
+                    Instruction::Lookupswitch | Instruction::Tableswitch => {
+                        Some(Instruction::parse_switch_table(code, *address).ok()?.0)
+                    }
+                    _ => None,
+                };
+                Some(DecodedInstruction {
+                    address: *address,
+                    instruction: *instruction,
+                    next_address,
+                    switch_table,
+                })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Debug for DecodedInstructionsCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.0.get() {
+            Some(Some(instructions)) => write!(f, "{} decoded instructions", instructions.len()),
+            Some(None) => write!(f, "undecodable instructions"),
+            None => write!(f, "instructions not decoded yet"),
+        }
+    }
+}
+
+/// The decoded instructions are derived from [ClassFileMethodCode::code], so they are
+/// not compared
+impl PartialEq for ClassFileMethodCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.max_stack == other.max_stack
+            && self.max_locals == other.max_locals
+            && self.code == other.code
+            && self.exception_table == other.exception_table
+            && self.line_number_table == other.line_number_table
+            && self.stack_map_table == other.stack_map_table
+            && self.attributes == other.attributes
+    }
 }
 
 impl fmt::Display for ClassFileMethodCode {
@@ -109,3 +198,243 @@ impl fmt::Display for ClassFileMethodCode {
         Ok(())
     }
 }
+
+impl ClassFileMethodCode {
+    /// Returns the instruction at the given address, along with the address of the next one.
+    /// All the instructions are decoded the first time one is requested and then cached,
+    /// since the same ones are generally executed many times, for example in loops.
+    /// If the code cannot be fully decoded, or the address is not the start of an instruction,
+    /// the instruction is decoded on every call.
+    /// Note that the cache is not invalidated if [code] is modified afterwards.
+    pub fn instruction_at(&self, address: usize) -> Result<(Instruction, usize), ClassReaderError> {
+        match self.decoded_instruction_at(address) {
+            Some(decoded) => Ok((decoded.instruction, decoded.next_address)),
+            None => Instruction::parse(&self.code, address),
+        }
+    }
+
+    /// Returns the jump table of the `tableswitch` or `lookupswitch` instruction at the given
+    /// address. Like instructions, the tables are decoded once and then cached.
+    pub fn switch_table_at(
+        &self,
+        address: usize,
+    ) -> Result<Cow<'_, SwitchTable>, ClassReaderError> {
+        match self
+            .decoded_instruction_at(address)
+            .and_then(|decoded| decoded.switch_table.as_ref())
+        {
+            Some(switch_table) => Ok(Cow::Borrowed(switch_table)),
+            None => Instruction::parse_switch_table(&self.code, address)
+                .map(|(switch_table, _)| Cow::Owned(switch_table)),
+        }
+    }
+
+    fn decoded_instruction_at(&self, address: usize) -> Option<&DecodedInstruction> {
+        let instructions = self
+            .decoded_instructions
+            .0
+            .get_or_init(|| DecodedInstructionsCache::decode(&self.code))
+            .as_ref()?;
+        instructions
+            .binary_search_by_key(&address, |decoded| decoded.address)
+            .ok()
+            .map(|index| &instructions[index])
+    }
+
+    /// Like [Instruction::successors], but also handles the switch instructions, whose jump
+    /// targets are stored in the code rather than in the [Instruction]
+    pub fn successors_of(
+        &self,
+        address: usize,
+        instruction: &Instruction,
+        next_address: Option<usize>,
+    ) -> Result<Vec<usize>, ClassReaderError> {
+        match instruction {
+            Instruction::Lookupswitch | Instruction::Tableswitch => {
+                let table = self.switch_table_at(address)?;
+                Ok(table.all_targets().map(|target| target as usize).collect())
+            }
+            _ => Ok(instruction.successors(next_address)),
+        }
+    }
+
+    /// Returns the addresses of the instructions that can never be executed, i.e. that cannot
+    /// be reached by any path starting from the method entry or from a reachable exception
+    /// handler. Such dead code is allowed by the JVM, but it is useful to report for tooling.
+    pub fn unreachable_instructions(&self) -> Result<Vec<ProgramCounter>, ClassReaderError> {
+        let instructions = Instruction::parse_instructions(&self.code)?;
+        let index_by_address: HashMap<usize, usize> = instructions
+            .iter()
+            .enumerate()
+            .map(|(index, (address, _))| (*address, index))
+            .collect();
+
+        let mut reachable: BTreeSet<usize> = BTreeSet::new();
+        let mut to_visit = vec![0];
+        while let Some(address) = to_visit.pop() {
+            if !reachable.insert(address) {
+                continue;
+            }
+            let index = *index_by_address.get(&address).ok_or_else(|| {
+                ClassReaderError::invalid_class_data(format!(
+                    "jump to address {address}, which is not the start of an instruction"
+                ))
+            })?;
+            let next_address = instructions.get(index + 1).map(|(address, _)| *address);
+
+            for entry in self.exception_table.lookup(ProgramCounter(address as u16)) {
+                to_visit.push(entry.handler_pc.0 as usize);
+            }
+            to_visit.extend(self.successors_of(address, &instructions[index].1, next_address)?);
+        }
+
+        Ok(instructions
+            .iter()
+            .filter(|(address, _)| !reachable.contains(address))
+            .map(|(address, _)| ProgramCounter(*address as u16))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        class_file_method::ClassFileMethodCode,
+        exception_table::{ExceptionTable, ExceptionTableEntry},
+        instruction::{Instruction, SwitchTable},
+        program_counter::ProgramCounter,
+    };
+
+    fn code_of(code: Vec<u8>) -> ClassFileMethodCode {
+        ClassFileMethodCode {
+            code,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn cached_instructions_match_the_parsed_ones() {
+        let code = code_of(vec![
+            0x1a, // 0: iload_0
+            0x99, 0x00, 0x05, // 1: ifeq 6
+            0x10, 0xff, // 4: bipush -1
+            0xac, // 6: ireturn
+        ]);
+        for address in [0, 1, 4, 6, 0, 1, 4, 6] {
+            assert_eq!(
+                Instruction::parse(&code.code, address),
+                code.instruction_at(address)
+            );
+        }
+        assert!(code.instruction_at(7).is_err());
+    }
+
+    #[test]
+    fn switch_tables_are_decoded_with_the_instructions() {
+        let code = code_of(vec![
+            0x1a, // 0: iload_0
+            0xab, // 1: lookupswitch
+            0x00, 0x00, // padding
+            0x00, 0x00, 0x00, 0x14, // default: 21
+            0x00, 0x00, 0x00, 0x01, // pairs count: 1
+            0x00, 0x00, 0x00, 0x07, // key 7
+            0x00, 0x00, 0x00, 0x13, // target 20
+            0x04, // 20: iconst_1
+            0xac, // 21: ireturn
+        ]);
+        let expected = SwitchTable {
+            default_target: 21,
+            targets: vec![(7, 20)],
+        };
+        assert_eq!(Ok((Instruction::Lookupswitch, 20)), code.instruction_at(1));
+        assert_eq!(Ok(&expected), code.switch_table_at(1).as_deref());
+        assert!(code.switch_table_at(0).is_err());
+    }
+
+    #[test]
+    fn addresses_in_the_middle_of_an_instruction_are_parsed_directly() {
+        let code = code_of(vec![
+            0x10, 0x00, // 0: bipush 0
+            0xac, // 2: ireturn
+        ]);
+        assert_eq!(Ok((Instruction::Nop, 2)), code.instruction_at(1));
+        assert_eq!(Ok((Instruction::Ireturn, 3)), code.instruction_at(2));
+    }
+
+    #[test]
+    fn undecodable_code_is_parsed_one_instruction_at_a_time() {
+        let code = code_of(vec![
+            0x04, // 0: iconst_1
+            0xac, // 1: ireturn
+            0xff, // 2: invalid
+        ]);
+        assert_eq!(Ok((Instruction::Iconst_1, 1)), code.instruction_at(0));
+        assert_eq!(Ok((Instruction::Ireturn, 2)), code.instruction_at(1));
+        assert!(code.instruction_at(2).is_err());
+    }
+
+    #[test]
+    fn code_can_be_shared_between_threads() {
+        fn assert_send_and_sync<T: Send + Sync>() {}
+        assert_send_and_sync::<ClassFileMethodCode>();
+    }
+
+    #[test]
+    fn all_instructions_reachable() {
+        let code = code_of(vec![
+            0x1a, // 0: iload_0
+            0x99, 0x00, 0x05, // 1: ifeq 6
+            0x04, // 4: iconst_1
+            0xac, // 5: ireturn
+            0x03, // 6: iconst_0
+            0xac, // 7: ireturn
+        ]);
+        assert_eq!(Ok(vec![]), code.unreachable_instructions());
+    }
+
+    #[test]
+    fn code_after_return_and_goto_is_unreachable() {
+        let code = code_of(vec![
+            0xa7, 0x00, 0x05, // 0: goto 5
+            0x00, // 3: nop
+            0xb1, // 4: return
+            0x03, // 5: iconst_0
+            0xac, // 6: ireturn
+            0x04, // 7: iconst_1
+            0xac, // 8: ireturn
+        ]);
+        assert_eq!(
+            Ok(vec![
+                ProgramCounter(3),
+                ProgramCounter(4),
+                ProgramCounter(7),
+                ProgramCounter(8)
+            ]),
+            code.unreachable_instructions()
+        );
+    }
+
+    #[test]
+    fn exception_handlers_of_reachable_code_are_reachable() {
+        let mut code = code_of(vec![
+            0x01, // 0: aconst_null
+            0xbf, // 1: athrow
+            0x57, // 2: pop
+            0xb1, // 3: return
+        ]);
+        code.exception_table = ExceptionTable::new(vec![ExceptionTableEntry {
+            range: ProgramCounter(0)..ProgramCounter(2),
+            handler_pc: ProgramCounter(2),
+            catch_class: None,
+        }]);
+        assert_eq!(Ok(vec![]), code.unreachable_instructions());
+    }
+
+    #[test]
+    fn jump_in_the_middle_of_an_instruction_is_an_error() {
+        let code = code_of(vec![
+            0xa7, 0x00, 0x01, // 0: goto 1
+        ]);
+        assert!(code.unreachable_instructions().is_err());
+    }
+}
@@ -1,7 +1,9 @@
 use crate::class_reader_error::{ClassReaderError, Result};
 
 /// Versions of the JVM class file format.
-#[derive(Debug, PartialEq, Default, strum_macros::Display)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, strum_macros::Display,
+)]
 #[allow(dead_code)]
 pub enum ClassFileVersion {
     Jdk1_1,
@@ -58,6 +60,12 @@ impl ClassFileVersion {
             _ => Err(ClassReaderError::UnsupportedVersion(major, minor)),
         }
     }
+
+    /// Returns whether this version is the given one or a later one. Useful to check
+    /// whether a class can use a feature, for example default methods require [Jdk8].
+    pub fn is_at_least(&self, version: ClassFileVersion) -> bool {
+        *self >= version
+    }
 }
 
 #[cfg(test)]
@@ -72,6 +80,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn versions_can_be_compared() {
+        assert!(ClassFileVersion::Jdk8.is_at_least(ClassFileVersion::Jdk8));
+        assert!(ClassFileVersion::Jdk11.is_at_least(ClassFileVersion::Jdk8));
+        assert!(!ClassFileVersion::Jdk7.is_at_least(ClassFileVersion::Jdk8));
+    }
+
     #[test]
     fn can_parse_future_versions() {
         assert_eq!(
@@ -10,6 +10,8 @@ use crate::{
     class_file_version::ClassFileVersion,
     class_reader_error::{ClassReaderError, Result},
     constant_pool::{ConstantPool, ConstantPoolEntry},
+    element_meta::ElementMeta,
+    enclosing_method::{EnclosingMethod, EnclosingMethodReference},
     exception_table::{ExceptionTable, ExceptionTableEntry},
     field_flags::FieldFlags,
     field_type::FieldType,
@@ -18,26 +20,62 @@ use crate::{
     method_descriptor::MethodDescriptor,
     method_flags::MethodFlags,
     program_counter::ProgramCounter,
+    stack_map_table::StackMapTable,
 };
 use crate::{buffer::Buffer, type_conversion::ToUsizeSafe};
 
+/// Limits on the size of the various parts of a class file, checked while reading it.
+/// Useful to reject early class files that are maliciously crafted to make the reader
+/// consume a lot of time or memory. The defaults are the maximum allowed by the JVM specs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassReaderLimits {
+    /// Maximum number of entries in the constant pool
+    pub max_constants: usize,
+    /// Maximum number of methods declared in the class
+    pub max_methods: usize,
+    /// Maximum length, in bytes, of the code of each method
+    pub max_code_length: usize,
+}
+
+impl Default for ClassReaderLimits {
+    fn default() -> Self {
+        Self {
+            max_constants: u16::MAX as usize - 1,
+            max_methods: u16::MAX as usize,
+            max_code_length: u16::MAX as usize,
+        }
+    }
+}
+
 /// A reader of a byte array representing a class. Supports only a subset of Java 7 class format,
 /// in particular it does not support generics.
 struct ClassFileReader<'a> {
     buffer: Buffer<'a>,
+    limits: &'a ClassReaderLimits,
     /// The class being read, created empty and updated in place
     class_file: ClassFile,
 }
 
 /// Reference: https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html
 impl<'a> ClassFileReader<'a> {
-    fn new(data: &[u8]) -> ClassFileReader {
+    fn new(data: &'a [u8], limits: &'a ClassReaderLimits) -> ClassFileReader<'a> {
         ClassFileReader {
             buffer: Buffer::new(data),
+            limits,
             class_file: Default::default(),
         }
     }
 
+    fn check_limit(what: &str, count: usize, limit: usize) -> Result<()> {
+        if count > limit {
+            Err(ClassReaderError::invalid_class_data(format!(
+                "{what} is {count}, which exceeds the limit of {limit}"
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
     fn read(mut self) -> Result<ClassFile> {
         self.check_magic_number()?;
         self.read_version()?;
@@ -72,7 +110,15 @@ impl<'a> ClassFileReader<'a> {
     }
 
     fn read_constants(&mut self) -> Result<()> {
-        let constants_count = self.buffer.read_u16()? - 1;
+        // The count in the file is one more than the actual number of entries
+        let constants_count = self.buffer.read_u16()?.checked_sub(1).ok_or_else(|| {
+            ClassReaderError::invalid_class_data("invalid constant pool count 0".to_string())
+        })?;
+        Self::check_limit(
+            "constant pool size",
+            constants_count.into_usize_safe(),
+            self.limits.max_constants,
+        )?;
         let mut i = 0;
         while i < constants_count {
             let tag = self.buffer.read_u8()?;
@@ -252,15 +298,21 @@ impl<'a> ClassFileReader<'a> {
         let type_descriptor = FieldType::parse(&type_descriptor_raw)?;
 
         let raw_attributes = self.read_raw_attributes()?;
-        let constant_value = self.extract_constant_value(&raw_attributes)?;
-        let deprecated = self.search_deprecated_attribute(&raw_attributes);
+        let mut constant_value = self.extract_constant_value(&raw_attributes)?;
+        if constant_value.is_some() && !flags.contains(FieldFlags::STATIC) {
+            // As per the JVM specs, the attribute must be silently ignored for non-static fields
+            warn!("ignoring ConstantValue attribute of non-static field {name}");
+            constant_value = None;
+        }
+        let meta =
+            self.read_element_meta(&raw_attributes, flags.contains(FieldFlags::SYNTHETIC))?;
 
         Ok(ClassFileField {
             flags,
             name,
             type_descriptor,
             constant_value,
-            deprecated,
+            meta,
         })
     }
 
@@ -312,12 +364,47 @@ impl<'a> ClassFileReader<'a> {
             .invert()
     }
 
-    fn search_deprecated_attribute(&self, raw_attributes: &[Attribute]) -> bool {
-        raw_attributes.iter().any(|attr| attr.name == "Deprecated")
+    /// Collects the compiler metadata of a class, field, or method. The synthetic flag
+    /// comes from the access flags of the element, which have a different type for each.
+    fn read_element_meta(
+        &self,
+        raw_attributes: &[Attribute],
+        has_synthetic_flag: bool,
+    ) -> Result<ElementMeta> {
+        let deprecated = raw_attributes.iter().any(|attr| attr.name == "Deprecated");
+        let synthetic =
+            has_synthetic_flag || raw_attributes.iter().any(|attr| attr.name == "Synthetic");
+        let signature = raw_attributes
+            .iter()
+            .find(|attr| attr.name == "Signature")
+            .map(|attr| {
+                let attribute_bytes: &[u8] = &attr.bytes;
+                let constant_index =
+                    attribute_bytes
+                        .try_into()
+                        .map(u16::from_be_bytes)
+                        .map_err(|_| {
+                            ClassReaderError::invalid_class_data(
+                                "invalid attribute of type Signature".to_string(),
+                            )
+                        })?;
+                self.read_string_reference(constant_index)
+            })
+            .invert()?;
+        Ok(ElementMeta {
+            deprecated,
+            synthetic,
+            signature,
+        })
     }
 
     fn read_methods(&mut self) -> Result<()> {
         let methods_count = self.buffer.read_u16()?;
+        Self::check_limit(
+            "number of methods",
+            methods_count.into_usize_safe(),
+            self.limits.max_methods,
+        )?;
         self.class_file.methods = (0..methods_count)
             .map(|_| self.read_method())
             .collect::<Result<Vec<ClassFileMethod>>>()?;
@@ -337,7 +424,8 @@ impl<'a> ClassFileReader<'a> {
         } else {
             Some(self.extract_code(&raw_attributes, &name)?)
         };
-        let deprecated = self.search_deprecated_attribute(&raw_attributes);
+        let meta =
+            self.read_element_meta(&raw_attributes, flags.contains(MethodFlags::SYNTHETIC))?;
         let thrown_exceptions = self.extract_thrown_exceptions(&raw_attributes)?;
 
         Ok(ClassFileMethod {
@@ -347,7 +435,7 @@ impl<'a> ClassFileReader<'a> {
             parsed_type_descriptor,
             attributes: raw_attributes,
             code,
-            deprecated,
+            meta,
             thrown_exceptions,
         })
     }
@@ -375,11 +463,13 @@ impl<'a> ClassFileReader<'a> {
                 let max_stack = buf.read_u16()?;
                 let max_locals = buf.read_u16()?;
                 let code_length = buf.read_u32()?.into_usize_safe();
+                Self::check_limit("code length", code_length, self.limits.max_code_length)?;
                 let code = Vec::from(buf.read_bytes(code_length)?);
                 let exception_table = self.read_exception_table(&mut buf)?;
-                let attributes =
+                let mut attributes =
                     Self::read_raw_attributes_from(&self.class_file.constants, &mut buf)?;
                 let line_number_table = self.extract_line_number_table(&attributes)?;
+                let stack_map_table = self.extract_stack_map_table(&mut attributes)?;
 
                 Result::<ClassFileMethodCode>::Ok(ClassFileMethodCode {
                     max_stack,
@@ -387,7 +477,9 @@ impl<'a> ClassFileReader<'a> {
                     code,
                     exception_table,
                     line_number_table,
+                    stack_map_table,
                     attributes,
+                    decoded_instructions: Default::default(),
                 })
             })
             .next()
@@ -445,6 +537,30 @@ impl<'a> ClassFileReader<'a> {
             .invert()
     }
 
+    /// Parses the `StackMapTable` attribute, if present, and removes it from the raw attributes
+    fn extract_stack_map_table(
+        &self,
+        raw_attributes: &mut Vec<Attribute>,
+    ) -> Result<Option<StackMapTable>> {
+        let Some(position) = raw_attributes
+            .iter()
+            .position(|attr| attr.name == "StackMapTable")
+        else {
+            return Ok(None);
+        };
+        let attribute = raw_attributes.remove(position);
+        match StackMapTable::parse(&attribute.bytes, &self.class_file.constants) {
+            Ok(stack_map_table) => Ok(Some(stack_map_table)),
+            // The table is mandatory only since Java 7; before, the JVM ignores an invalid one
+            // and falls back to the verification by type inference
+            Err(err) if !self.class_file.version.is_at_least(ClassFileVersion::Jdk7) => {
+                warn!("ignoring invalid StackMapTable: {err}");
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
     fn extract_thrown_exceptions(&self, raw_attributes: &[Attribute]) -> Result<Vec<String>> {
         raw_attributes
             .iter()
@@ -465,11 +581,50 @@ impl<'a> ClassFileReader<'a> {
 
     fn read_class_attributes(&mut self) -> Result<()> {
         let raw_attributes = self.read_raw_attributes()?;
-        self.class_file.deprecated = self.search_deprecated_attribute(&raw_attributes);
+        self.class_file.meta = self.read_element_meta(
+            &raw_attributes,
+            self.class_file.flags.contains(ClassAccessFlags::SYNTHETIC),
+        )?;
         self.class_file.source_file = self.search_source_file_attribute(&raw_attributes)?;
+        self.class_file.enclosing_method =
+            self.search_enclosing_method_attribute(&raw_attributes)?;
         Ok(())
     }
 
+    fn search_enclosing_method_attribute(
+        &self,
+        raw_attributes: &[Attribute],
+    ) -> Result<Option<EnclosingMethod>> {
+        raw_attributes
+            .iter()
+            .find(|attr| attr.name == "EnclosingMethod")
+            .map(|attr| {
+                let mut buf = Buffer::new(&attr.bytes);
+                let class_name = self.read_string_reference(buf.read_u16()?)?;
+                let method_index = buf.read_u16()?;
+                let method = if method_index == 0 {
+                    None
+                } else {
+                    match self.class_file.constants.get(method_index)? {
+                        &ConstantPoolEntry::NameAndTypeDescriptor(
+                            name_index,
+                            type_descriptor_index,
+                        ) => Some(EnclosingMethodReference {
+                            name: self.read_string_reference(name_index)?,
+                            type_descriptor: self.read_string_reference(type_descriptor_index)?,
+                        }),
+                        _ => {
+                            return Err(ClassReaderError::invalid_class_data(
+                                "invalid EnclosingMethod attribute".to_string(),
+                            ))
+                        }
+                    }
+                };
+                Ok(EnclosingMethod { class_name, method })
+            })
+            .invert()
+    }
+
     fn search_source_file_attribute(&self, raw_attributes: &[Attribute]) -> Result<Option<String>> {
         raw_attributes
             .iter()
@@ -519,12 +674,22 @@ impl<'a> ClassFileReader<'a> {
 
 /// Reads a class from a byte slice.
 pub fn read_buffer(buf: &[u8]) -> Result<ClassFile> {
-    ClassFileReader::new(buf).read()
+    read_buffer_with_limits(buf, &ClassReaderLimits::default())
+}
+
+/// Reads a class from a byte slice, failing if it exceeds the given limits.
+pub fn read_buffer_with_limits(buf: &[u8], limits: &ClassReaderLimits) -> Result<ClassFile> {
+    ClassFileReader::new(buf, limits).read()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{class_reader::read_buffer, class_reader_error::ClassReaderError};
+    use crate::{
+        class_file_field::FieldConstantValue,
+        class_reader::{read_buffer, read_buffer_with_limits, ClassReaderLimits},
+        class_reader_error::ClassReaderError,
+        element_meta::ElementMeta,
+    };
 
     #[test]
     fn magic_number_is_required() {
@@ -534,4 +699,124 @@ mod tests {
             Err(ClassReaderError::InvalidClassData(s, None)) if s == "invalid magic number"
         ));
     }
+
+    #[test]
+    fn constant_pool_larger_than_the_limit_is_rejected() {
+        let data = vec![
+            0xCA, 0xFE, 0xBA, 0xBE, // magic number
+            0x00, 0x00, 0x00, 0x33, // version 51.0
+            0xFF, 0xFF, // 65534 constants, but no data
+        ];
+        let limits = ClassReaderLimits {
+            max_constants: 1000,
+            ..Default::default()
+        };
+        assert!(matches!(
+            read_buffer_with_limits(&data, &limits),
+            Err(ClassReaderError::InvalidClassData(s, None))
+                if s == "constant pool size is 65534, which exceeds the limit of 1000"
+        ));
+    }
+
+    #[test]
+    fn constant_pool_count_of_zero_is_invalid() {
+        let data = vec![0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x33, 0x00, 0x00];
+        assert!(matches!(
+            read_buffer(&data),
+            Err(ClassReaderError::InvalidClassData(s, None)) if s == "invalid constant pool count 0"
+        ));
+    }
+
+    fn utf8_constant(value: &str) -> Vec<u8> {
+        let mut bytes = vec![0x01, 0x00, value.len() as u8];
+        bytes.extend(value.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn constant_values_can_refer_to_constants_defined_later() {
+        let mut data = vec![
+            0xCA, 0xFE, 0xBA, 0xBE, // magic number
+            0x00, 0x00, 0x00, 0x33, // version 51.0
+            0x00, 0x0B, // 10 constants
+            0x07, 0x00, 0x03, // #1: class reference to #3
+            0x08, 0x00, 0x0A, // #2: string reference to #10
+        ];
+        data.extend(utf8_constant("Forward")); // #3
+        data.extend(utf8_constant("GREETING")); // #4
+        data.extend(utf8_constant("Ljava/lang/String;")); // #5
+        data.extend(utf8_constant("ConstantValue")); // #6
+        data.extend(utf8_constant("ANSWER")); // #7
+        data.extend(utf8_constant("I")); // #8
+        data.extend([0x03, 0x00, 0x00, 0x00, 0x2A]); // #9: integer 42
+        data.extend(utf8_constant("hello")); // #10
+        data.extend([
+            0x00, 0x21, // access flags: public super
+            0x00, 0x01, // this class: #1
+            0x00, 0x00, // no superclass
+            0x00, 0x00, // no interfaces
+            0x00, 0x02, // two fields
+            // public static final String GREETING = "hello"
+            0x00, 0x19, 0x00, 0x04, 0x00, 0x05, 0x00, 0x01, //
+            0x00, 0x06, 0x00, 0x00, 0x00, 0x02, 0x00, 0x02, //
+            // public static final int ANSWER = 42
+            0x00, 0x19, 0x00, 0x07, 0x00, 0x08, 0x00, 0x01, //
+            0x00, 0x06, 0x00, 0x00, 0x00, 0x02, 0x00, 0x09, //
+            0x00, 0x00, // no methods
+            0x00, 0x00, // no attributes
+        ]);
+
+        let class = read_buffer(&data).expect("should be able to read the class");
+        assert_eq!("Forward", class.name);
+        assert_eq!(
+            Some(FieldConstantValue::String("hello".to_string())),
+            class.fields[0].constant_value
+        );
+        assert_eq!(
+            Some(FieldConstantValue::Int(42)),
+            class.fields[1].constant_value
+        );
+    }
+
+    #[test]
+    fn element_meta_is_read_from_attributes() {
+        let mut data = vec![
+            0xCA, 0xFE, 0xBA, 0xBE, // magic number
+            0x00, 0x00, 0x00, 0x2E, // version 46.0
+            0x00, 0x09, // 8 constants
+            0x07, 0x00, 0x02, // #1: class reference to #2
+        ];
+        data.extend(utf8_constant("Meta")); // #2
+        data.extend(utf8_constant("this$0")); // #3
+        data.extend(utf8_constant("Ljava/util/List;")); // #4
+        data.extend(utf8_constant("Deprecated")); // #5
+        data.extend(utf8_constant("Synthetic")); // #6
+        data.extend(utf8_constant("Signature")); // #7
+        data.extend(utf8_constant("Ljava/util/List<Ljava/lang/String;>;")); // #8
+        data.extend([
+            0x00, 0x20, // access flags: super
+            0x00, 0x01, // this class: #1
+            0x00, 0x00, // no superclass
+            0x00, 0x00, // no interfaces
+            0x00, 0x01, // one field
+            // List<String> this$0, with the attributes but no flag for synthetic
+            0x00, 0x00, 0x00, 0x03, 0x00, 0x04, 0x00, 0x03, //
+            0x00, 0x05, 0x00, 0x00, 0x00, 0x00, // Deprecated
+            0x00, 0x06, 0x00, 0x00, 0x00, 0x00, // Synthetic
+            0x00, 0x07, 0x00, 0x00, 0x00, 0x02, 0x00, 0x08, // Signature
+            0x00, 0x00, // no methods
+            0x00, 0x00, // no attributes
+        ]);
+
+        let class = read_buffer(&data).expect("should be able to read the class");
+        assert_eq!(ElementMeta::default(), class.meta);
+        assert_eq!(
+            ElementMeta {
+                deprecated: true,
+                synthetic: true,
+                signature: Some("Ljava/util/List<Ljava/lang/String;>;".to_string()),
+            },
+            class.fields[0].meta
+        );
+    }
 }
@@ -3,6 +3,10 @@ use thiserror::Error;
 
 /// Types of a constant in the constant pool of a class, following the JVM spec:
 /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.4
+///
+/// Note that equality for `Float` and `Double` follows IEEE semantics, since it is derived:
+/// two `NaN` constants are _not_ equal, whereas `0.0` and `-0.0` are. If we ever need to
+/// deduplicate entries, floating point constants should be compared by bit pattern instead.
 #[derive(Debug, PartialEq)]
 pub enum ConstantPoolEntry {
     Utf8(String),
@@ -65,6 +69,14 @@ impl ConstantPool {
         }
     }
 
+    /// Iterates over all the entries, skipping the unused slots after long and double
+    pub fn iter(&self) -> impl Iterator<Item = &ConstantPoolEntry> {
+        self.entries.iter().filter_map(|entry| match entry {
+            ConstantPoolPhysicalEntry::Entry(entry) => Some(entry),
+            ConstantPoolPhysicalEntry::MultiByteEntryTombstone() => None,
+        })
+    }
+
     /// Accesses an entry given its index. Note that it must be 1-based!
     pub fn get(
         &self,
@@ -139,6 +151,21 @@ impl ConstantPool {
     }
 
     pub fn text_of(&self, idx: u16) -> Result<String, InvalidConstantPoolIndexError> {
+        self.text_of_nested(idx, 0)
+    }
+
+    /// Since entries can refer to ones defined later in the pool, a malformed class could
+    /// contain a cycle of references; thus, we limit the depth to avoid infinite recursion.
+    /// Valid chains, such as a field reference to a class reference to a string, are short.
+    fn text_of_nested(
+        &self,
+        idx: u16,
+        depth: usize,
+    ) -> Result<String, InvalidConstantPoolIndexError> {
+        const MAX_DEPTH: usize = 3;
+        if depth > MAX_DEPTH {
+            return Err(InvalidConstantPoolIndexError::new(idx));
+        }
         let entry = self.get(idx)?;
         let text = match entry {
             ConstantPoolEntry::Utf8(ref s) => s.clone(),
@@ -146,19 +173,23 @@ impl ConstantPool {
             ConstantPoolEntry::Float(n) => n.to_string(),
             ConstantPoolEntry::Long(n) => n.to_string(),
             ConstantPoolEntry::Double(n) => n.to_string(),
-            ConstantPoolEntry::ClassReference(n) => self.text_of(*n)?,
-            ConstantPoolEntry::StringReference(n) => self.text_of(*n)?,
-            ConstantPoolEntry::FieldReference(i, j) => {
-                format!("{}.{}", self.text_of(*i)?, self.text_of(*j)?)
-            }
-            ConstantPoolEntry::MethodReference(i, j) => {
-                format!("{}.{}", self.text_of(*i)?, self.text_of(*j)?)
-            }
-            ConstantPoolEntry::InterfaceMethodReference(i, j) => {
-                format!("{}.{}", self.text_of(*i)?, self.text_of(*j)?)
+            ConstantPoolEntry::ClassReference(n) => self.text_of_nested(*n, depth + 1)?,
+            ConstantPoolEntry::StringReference(n) => self.text_of_nested(*n, depth + 1)?,
+            ConstantPoolEntry::FieldReference(i, j)
+            | ConstantPoolEntry::MethodReference(i, j)
+            | ConstantPoolEntry::InterfaceMethodReference(i, j) => {
+                format!(
+                    "{}.{}",
+                    self.text_of_nested(*i, depth + 1)?,
+                    self.text_of_nested(*j, depth + 1)?
+                )
             }
             ConstantPoolEntry::NameAndTypeDescriptor(i, j) => {
-                format!("{}: {}", self.text_of(*i)?, self.text_of(*j)?)
+                format!(
+                    "{}: {}",
+                    self.text_of_nested(*i, depth + 1)?,
+                    self.text_of_nested(*j, depth + 1)?
+                )
             }
         };
         Ok(text)
@@ -245,4 +276,52 @@ mod tests {
         assert_eq!("hey.joe", cp.text_of(13).unwrap());
         assert_eq!("hey: joe", cp.text_of(14).unwrap());
     }
+
+    #[test]
+    fn entries_can_refer_to_later_ones() {
+        let mut cp = ConstantPool::new();
+        cp.add(ConstantPoolEntry::FieldReference(2, 3));
+        cp.add(ConstantPoolEntry::ClassReference(4));
+        cp.add(ConstantPoolEntry::NameAndTypeDescriptor(5, 6));
+        cp.add(ConstantPoolEntry::Utf8("Class".to_string()));
+        cp.add(ConstantPoolEntry::Utf8("field".to_string()));
+        cp.add(ConstantPoolEntry::Utf8("I".to_string()));
+
+        assert_eq!("Class.field: I", cp.text_of(1).unwrap());
+    }
+
+    #[test]
+    fn cyclic_references_are_invalid() {
+        let mut cp = ConstantPool::new();
+        cp.add(ConstantPoolEntry::ClassReference(2));
+        cp.add(ConstantPoolEntry::StringReference(1));
+        cp.add(ConstantPoolEntry::ClassReference(3));
+
+        assert!(cp.text_of(1).is_err());
+        assert!(cp.text_of(3).is_err());
+    }
+
+    #[test]
+    fn float_constants_use_ieee_equality() {
+        assert_ne!(
+            ConstantPoolEntry::Float(f32::NAN),
+            ConstantPoolEntry::Float(f32::NAN)
+        );
+        assert_eq!(
+            ConstantPoolEntry::Float(0.0),
+            ConstantPoolEntry::Float(-0.0)
+        );
+    }
+
+    #[test]
+    fn double_constants_use_ieee_equality() {
+        assert_ne!(
+            ConstantPoolEntry::Double(f64::NAN),
+            ConstantPoolEntry::Double(f64::NAN)
+        );
+        assert_eq!(
+            ConstantPoolEntry::Double(0.0),
+            ConstantPoolEntry::Double(-0.0)
+        );
+    }
 }
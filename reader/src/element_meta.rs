@@ -0,0 +1,13 @@
+/// Metadata emitted by the compiler for a class, field, or method, collected from the
+/// `Deprecated`, `Synthetic`, and `Signature` attributes.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct ElementMeta {
+    pub deprecated: bool,
+    /// True if the element does not appear in the source code. Since Java 5 compilers mark
+    /// synthetic elements with the `ACC_SYNTHETIC` flag rather than with the attribute;
+    /// either of them is enough.
+    pub synthetic: bool,
+    /// The generic signature, only present for elements whose declaration uses type
+    /// variables or parameterized types
+    pub signature: Option<String>,
+}
@@ -0,0 +1,16 @@
+/// Models the `EnclosingMethod` attribute, which is present in local and anonymous classes
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EnclosingMethod {
+    /// The innermost class that encloses the declaration of this class
+    pub class_name: String,
+    /// The enclosing method, or `None` if the class is not declared inside a method or
+    /// constructor, for example when it is used in a field initializer
+    pub method: Option<EnclosingMethodReference>,
+}
+
+/// Name and type descriptor of the method enclosing a class
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EnclosingMethodReference {
+    pub name: String,
+    pub type_descriptor: String,
+}
@@ -13,6 +13,10 @@ impl ExceptionTable {
         Self { entries }
     }
 
+    pub fn entries(&self) -> impl Iterator<Item = &ExceptionTableEntry> {
+        self.entries.iter()
+    }
+
     pub fn lookup(&self, pc: ProgramCounter) -> Vec<&ExceptionTableEntry> {
         self.entries
             .iter()
@@ -86,6 +86,31 @@ impl FieldType {
             _ => return Err(InvalidTypeDescriptor(type_descriptor.to_string())),
         })
     }
+
+    /// Returns the type descriptor in the JVM internal form, i.e. the inverse of [parse].
+    /// Note that [fmt::Display] formats the type in a more human readable way.
+    pub fn descriptor(&self) -> String {
+        match self {
+            FieldType::Base(base_type) => base_type.descriptor().to_string(),
+            FieldType::Object(class_name) => format!("L{class_name};"),
+            FieldType::Array(component_type) => format!("[{}", component_type.descriptor()),
+        }
+    }
+}
+
+impl BaseType {
+    fn descriptor(&self) -> char {
+        match self {
+            BaseType::Byte => 'B',
+            BaseType::Char => 'C',
+            BaseType::Double => 'D',
+            BaseType::Float => 'F',
+            BaseType::Int => 'I',
+            BaseType::Long => 'J',
+            BaseType::Short => 'S',
+            BaseType::Boolean => 'Z',
+        }
+    }
 }
 
 #[cfg(test)]
@@ -188,4 +213,14 @@ mod tests {
     fn can_format_array() {
         assert_eq!("Int[]", format!("{}", FieldType::parse("[I").unwrap()));
     }
+
+    #[test]
+    fn descriptor_is_the_inverse_of_parse() {
+        for descriptor in ["B", "C", "D", "F", "I", "J", "S", "Z", "Lrjvm/Test;", "[[D"] {
+            assert_eq!(
+                descriptor,
+                FieldType::parse(descriptor).unwrap().descriptor()
+            );
+        }
+    }
 }
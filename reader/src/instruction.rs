@@ -24,7 +24,7 @@ pub enum Instruction {
     Athrow,
     Baload,
     Bastore,
-    Bipush(u8),
+    Bipush(i8),
     Caload,
     Castore,
     Checkcast(u16),
@@ -90,7 +90,7 @@ pub enum Instruction {
     Getfield(u16),
     Getstatic(u16),
     Goto(u16),
-    Goto_w,
+    Goto_w(u16),
     I2b,
     I2c,
     I2d,
@@ -153,7 +153,7 @@ pub enum Instruction {
     Iushr,
     Ixor,
     Jsr(u16),
-    Jsr_w,
+    Jsr_w(u16),
     L2d,
     L2f,
     L2i,
@@ -222,6 +222,34 @@ pub enum NewArrayType {
     Long,
 }
 
+/// The jump table of a `tableswitch` or `lookupswitch` instruction. Since its size is variable,
+/// it is not stored in the [Instruction]. See [crate::class_file_method::ClassFileMethodCode::switch_table_at].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SwitchTable {
+    /// Address to jump to when the key does not match any entry
+    pub default_target: u16,
+    /// The matched keys, sorted, each with the address to jump to
+    pub targets: Vec<(i32, u16)>,
+}
+
+impl SwitchTable {
+    /// Returns the address to jump to for the given key
+    pub fn target(&self, key: i32) -> u16 {
+        match self
+            .targets
+            .binary_search_by_key(&key, |(matched, _)| *matched)
+        {
+            Ok(index) => self.targets[index].1,
+            Err(_) => self.default_target,
+        }
+    }
+
+    /// Returns all the possible jump addresses, including the default one
+    pub fn all_targets(&self) -> impl Iterator<Item = u16> + '_ {
+        std::iter::once(self.default_target).chain(self.targets.iter().map(|(_, target)| *target))
+    }
+}
+
 impl Instruction {
     /// Reads one instruction from the bytecode, and returns it along
     /// with the address of the start of the next instruction
@@ -248,7 +276,7 @@ impl Instruction {
             0xbf => Instruction::Athrow,
             0x33 => Instruction::Baload,
             0x54 => Instruction::Bastore,
-            0x10 => Instruction::Bipush(Self::read_u8(raw_code, &mut address)?),
+            0x10 => Instruction::Bipush(Self::read_i8(raw_code, &mut address)?),
             0x34 => Instruction::Caload,
             0x55 => Instruction::Castore,
             0xc0 => Instruction::Checkcast(Self::read_u16(raw_code, &mut address)?),
@@ -314,7 +342,9 @@ impl Instruction {
             0xb4 => Instruction::Getfield(Self::read_u16(raw_code, &mut address)?),
             0xb2 => Instruction::Getstatic(Self::read_u16(raw_code, &mut address)?),
             0xa7 => Instruction::Goto(Self::read_offset(raw_code, &mut address)?),
-            0xc8 => todo!("OpCode::Goto_w"),
+            0xc8 => {
+                Instruction::Goto_w(Self::read_wide_offset(raw_code, address - 1, &mut address)?)
+            }
             0x91 => Instruction::I2b,
             0x92 => Instruction::I2c,
             0x87 => Instruction::I2d,
@@ -397,7 +427,9 @@ impl Instruction {
             0x7c => Instruction::Iushr,
             0x82 => Instruction::Ixor,
             0xa8 => Instruction::Jsr(Self::read_offset(raw_code, &mut address)?),
-            0xc9 => todo!("OpCode::Jsr_w"),
+            0xc9 => {
+                Instruction::Jsr_w(Self::read_wide_offset(raw_code, address - 1, &mut address)?)
+            }
             0x8a => Instruction::L2d,
             0x89 => Instruction::L2f,
             0x88 => Instruction::L2i,
@@ -419,7 +451,10 @@ impl Instruction {
             0x21 => Instruction::Lload_3,
             0x69 => Instruction::Lmul,
             0x75 => Instruction::Lneg,
-            0xab => todo!("OpCode::Lookupswitch"),
+            0xab => {
+                address = Self::parse_switch_table(raw_code, address - 1)?.1;
+                Instruction::Lookupswitch
+            }
             0x81 => Instruction::Lor,
             0x71 => Instruction::Lrem,
             0xad => Instruction::Lreturn,
@@ -470,8 +505,17 @@ impl Instruction {
             0x56 => Instruction::Sastore,
             0x11 => Instruction::Sipush(Self::read_i16(raw_code, &mut address)?),
             0x5f => Instruction::Swap,
-            0xaa => todo!("OpCode::Tableswitch"),
-            0xc4 => todo!("OpCode::Wide"),
+            0xaa => {
+                address = Self::parse_switch_table(raw_code, address - 1)?.1;
+                Instruction::Tableswitch
+            }
+            0xc4 => {
+                // The instructions modified by wide would need 16 bits local variable indexes
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "unsupported instruction wide at address {}",
+                    address - 1
+                )));
+            }
             _ => {
                 return Err(ClassReaderError::invalid_class_data(format!(
                     "invalid op code: {op_byte:#04x} at address {address}"
@@ -482,8 +526,8 @@ impl Instruction {
         Ok((op_code, address))
     }
 
-    /// Parses all instructions in the given raw code.
-    pub(crate) fn parse_instructions(
+    /// Parses all instructions in the given raw code, returning them along with their address
+    pub fn parse_instructions(
         raw_code: &[u8],
     ) -> Result<Vec<(usize, Instruction)>, ClassReaderError> {
         let mut instructions: Vec<(usize, Self)> = Vec::new();
@@ -498,6 +542,58 @@ impl Instruction {
         Ok(instructions)
     }
 
+    /// Returns whether the execution can continue with the next instruction in the code
+    pub fn falls_through(&self) -> bool {
+        !matches!(
+            self,
+            Instruction::Goto(_)
+                | Instruction::Goto_w(_)
+                | Instruction::Areturn
+                | Instruction::Dreturn
+                | Instruction::Freturn
+                | Instruction::Ireturn
+                | Instruction::Lreturn
+                | Instruction::Return
+                | Instruction::Athrow
+                | Instruction::Ret(_)
+                | Instruction::Lookupswitch
+                | Instruction::Tableswitch
+        )
+    }
+
+    /// Returns the addresses of the instructions that can be executed right after this one,
+    /// given the address of the next instruction in the code, if there is one
+    pub fn successors(&self, next_address: Option<usize>) -> Vec<usize> {
+        let mut successors = match self {
+            Instruction::Goto(target)
+            | Instruction::Goto_w(target)
+            | Instruction::If_acmpeq(target)
+            | Instruction::If_acmpne(target)
+            | Instruction::If_icmpeq(target)
+            | Instruction::If_icmpne(target)
+            | Instruction::If_icmplt(target)
+            | Instruction::If_icmpge(target)
+            | Instruction::If_icmpgt(target)
+            | Instruction::If_icmple(target)
+            | Instruction::Ifeq(target)
+            | Instruction::Ifne(target)
+            | Instruction::Iflt(target)
+            | Instruction::Ifge(target)
+            | Instruction::Ifgt(target)
+            | Instruction::Ifle(target)
+            | Instruction::Ifnonnull(target)
+            | Instruction::Ifnull(target)
+            // The subroutine will return to the next instruction via ret
+            | Instruction::Jsr(target)
+            | Instruction::Jsr_w(target) => vec![*target as usize],
+            _ => vec![],
+        };
+        if self.falls_through() {
+            successors.extend(next_address);
+        }
+        successors
+    }
+
     fn byte_at(raw_code: &[u8], address: usize) -> Result<u8, ClassReaderError> {
         let op_byte = *raw_code
             .get(address)
@@ -533,6 +629,91 @@ impl Instruction {
         Ok(unsafe { std::mem::transmute(value) })
     }
 
+    /// Reads the jump table of the `tableswitch` or `lookupswitch` instruction at the given
+    /// address, and returns it along with the address of the start of the next instruction
+    pub fn parse_switch_table(
+        raw_code: &[u8],
+        address: usize,
+    ) -> Result<(SwitchTable, usize), ClassReaderError> {
+        let is_table_switch = match Self::byte_at(raw_code, address)? {
+            0xaa => true,
+            0xab => false,
+            _ => {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "instruction at address {address} is not a switch"
+                )))
+            }
+        };
+        // The operands are aligned at a multiple of four bytes from the start of the code
+        let mut operands_address = (address + 4) & !3;
+
+        let default_target = Self::read_wide_offset(raw_code, address, &mut operands_address)?;
+        let targets = if is_table_switch {
+            let low = Self::read_i32(raw_code, &mut operands_address)?;
+            let high = Self::read_i32(raw_code, &mut operands_address)?;
+            if low > high {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "invalid tableswitch bounds at address {address}"
+                )));
+            }
+            (low..=high)
+                .map(|key| {
+                    let target = Self::read_wide_offset(raw_code, address, &mut operands_address)?;
+                    Ok((key, target))
+                })
+                .collect::<Result<Vec<_>, ClassReaderError>>()?
+        } else {
+            let pairs_count = Self::read_i32(raw_code, &mut operands_address)?;
+            if pairs_count < 0 {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "negative lookupswitch pairs count at address {address}"
+                )));
+            }
+            let targets = (0..pairs_count)
+                .map(|_| {
+                    let key = Self::read_i32(raw_code, &mut operands_address)?;
+                    let target = Self::read_wide_offset(raw_code, address, &mut operands_address)?;
+                    Ok((key, target))
+                })
+                .collect::<Result<Vec<_>, ClassReaderError>>()?;
+            if !targets.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "lookupswitch keys are not sorted at address {address}"
+                )));
+            }
+            targets
+        };
+
+        Ok((
+            SwitchTable {
+                default_target,
+                targets,
+            },
+            operands_address,
+        ))
+    }
+
+    fn read_i32(raw_code: &[u8], address: &mut usize) -> Result<i32, ClassReaderError> {
+        let high = Self::read_u16(raw_code, address)? as u32;
+        let low = Self::read_u16(raw_code, address)? as u32;
+        Ok(((high << 16) | low) as i32)
+    }
+
+    /// Reads a 32 bits offset, relative to the given instruction address
+    fn read_wide_offset(
+        raw_code: &[u8],
+        instruction_address: usize,
+        address: &mut usize,
+    ) -> Result<u16, ClassReaderError> {
+        let offset = Self::read_i32(raw_code, address)?;
+        let jump_address = (instruction_address as i64) + (offset as i64);
+        u16::try_from(jump_address).map_err(|_| {
+            ClassReaderError::invalid_class_data(format!(
+                "invalid jump offset at address {address}"
+            ))
+        })
+    }
+
     fn read_offset(raw_code: &[u8], address: &mut usize) -> Result<u16, ClassReaderError> {
         let instruction_address = *address - 1;
         let offset = Self::read_i16(raw_code, address)?;
@@ -544,3 +725,110 @@ impl Instruction {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::{Instruction, SwitchTable};
+
+    #[test]
+    fn bipush_is_sign_extended() {
+        assert_eq!(
+            (Instruction::Bipush(-1), 2),
+            Instruction::parse(&[0x10, 0xFF], 0).unwrap()
+        );
+        assert_eq!(
+            (Instruction::Bipush(127), 2),
+            Instruction::parse(&[0x10, 0x7F], 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn can_parse_tableswitch() {
+        let code = [
+            0x1a, // 0: iload_0
+            0xaa, // 1: tableswitch
+            0x00, 0x00, // padding
+            0x00, 0x00, 0x00, 0x1b, // default: 1 + 27 = 28
+            0x00, 0x00, 0x00, 0x01, // low: 1
+            0x00, 0x00, 0x00, 0x02, // high: 2
+            0x00, 0x00, 0x00, 0x1c, // 1: 1 + 28 = 29
+            0x00, 0x00, 0x00, 0x1d, // 2: 1 + 29 = 30
+        ];
+        assert_eq!(
+            Ok((Instruction::Tableswitch, 24)),
+            Instruction::parse(&code, 1)
+        );
+        let (table, _) = Instruction::parse_switch_table(&code, 1).unwrap();
+        assert_eq!(
+            SwitchTable {
+                default_target: 28,
+                targets: vec![(1, 29), (2, 30)],
+            },
+            table
+        );
+        assert_eq!(29, table.target(1));
+        assert_eq!(30, table.target(2));
+        assert_eq!(28, table.target(3));
+    }
+
+    #[test]
+    fn can_parse_lookupswitch() {
+        let code = [
+            0xab, // 0: lookupswitch
+            0x00, 0x00, 0x00, // padding
+            0x00, 0x00, 0x00, 0x1c, // default: 28
+            0x00, 0x00, 0x00, 0x02, // pairs count: 2
+            0xff, 0xff, 0xff, 0xf6, // key -10
+            0x00, 0x00, 0x00, 0x1d, // target 29
+            0x00, 0x00, 0x03, 0xe8, // key 1000
+            0x00, 0x00, 0x00, 0x1e, // target 30
+        ];
+        assert_eq!(
+            Ok((Instruction::Lookupswitch, 28)),
+            Instruction::parse(&code, 0)
+        );
+        let (table, _) = Instruction::parse_switch_table(&code, 0).unwrap();
+        assert_eq!(29, table.target(-10));
+        assert_eq!(30, table.target(1000));
+        assert_eq!(28, table.target(0));
+        assert_eq!(vec![28, 29, 30], table.all_targets().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn lookupswitch_with_negative_pairs_count_is_rejected() {
+        let code = [
+            0xab, // 0: lookupswitch
+            0x00, 0x00, 0x00, // padding
+            0x00, 0x00, 0x00, 0x1c, // default: 28
+            0xff, 0xff, 0xff, 0xff, // pairs count: -1
+        ];
+        assert!(Instruction::parse(&code, 0).is_err());
+    }
+
+    #[test]
+    fn can_parse_goto_w_and_jsr_w() {
+        let code = [
+            0x00, // 0: nop
+            0xc8, 0xff, 0xff, 0xff, 0xff, // 1: goto_w 0
+            0xc9, 0x00, 0x00, 0x00, 0x05, // 6: jsr_w 11
+            0xb1, // 11: return
+        ];
+        assert_eq!(
+            Ok(vec![
+                (0, Instruction::Nop),
+                (1, Instruction::Goto_w(0)),
+                (6, Instruction::Jsr_w(11)),
+                (11, Instruction::Return),
+            ]),
+            Instruction::parse_instructions(&code)
+        );
+    }
+
+    #[test]
+    fn wide_is_not_supported() {
+        let code = [
+            0xc4, 0x15, 0x01, 0x00, // 0: wide iload 256
+        ];
+        assert!(Instruction::parse(&code, 0).is_err());
+    }
+}
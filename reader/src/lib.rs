@@ -11,6 +11,8 @@ pub mod class_file_version;
 pub mod class_reader;
 pub mod class_reader_error;
 pub mod constant_pool;
+pub mod element_meta;
+pub mod enclosing_method;
 pub mod exception_table;
 pub mod field_flags;
 pub mod field_type;
@@ -20,4 +22,6 @@ pub mod line_number_table;
 pub mod method_descriptor;
 pub mod method_flags;
 pub mod program_counter;
+pub mod stack_depth;
+pub mod stack_map_table;
 pub mod type_conversion;
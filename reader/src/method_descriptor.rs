@@ -1,7 +1,5 @@
 use std::{fmt, fmt::Formatter, str::Chars};
 
-use itertools::Itertools;
-
 use crate::{
     class_reader_error::{ClassReaderError, ClassReaderError::InvalidTypeDescriptor},
     field_type::FieldType,
@@ -15,13 +13,17 @@ pub struct MethodDescriptor {
     pub return_type: Option<FieldType>,
 }
 
+/// Formats the descriptor in the JVM internal form, i.e. like the string it was parsed from
 impl fmt::Display for MethodDescriptor {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.write_str("(")?;
-        f.write_str(&self.parameters.iter().join(", "))?;
+        for parameter in self.parameters.iter() {
+            f.write_str(&parameter.descriptor())?;
+        }
+        f.write_str(")")?;
         match &self.return_type {
-            Some(field_type) => write!(f, ") -> {field_type}"),
-            None => f.write_str(") -> void"),
+            Some(field_type) => f.write_str(&field_type.descriptor()),
+            None => f.write_str("V"),
         }
     }
 }
@@ -86,6 +88,15 @@ impl MethodDescriptor {
     pub fn num_arguments(&self) -> usize {
         self.parameters.len()
     }
+
+    pub fn parameters(&self) -> &[FieldType] {
+        &self.parameters
+    }
+
+    /// Returns the type of the return value, or `None` for void methods
+    pub fn return_type(&self) -> Option<&FieldType> {
+        self.return_type.as_ref()
+    }
 }
 
 #[cfg(test)]
@@ -165,7 +176,7 @@ mod tests {
     #[test]
     fn can_format_void_to_void() {
         assert_eq!(
-            "() -> void",
+            "()V",
             format!("{}", MethodDescriptor::parse("()V").unwrap())
         );
     }
@@ -173,7 +184,7 @@ mod tests {
     #[test]
     fn can_format_parameters_to_return_type() {
         assert_eq!(
-            "(java/lang/String, Int) -> Long[]",
+            "(Ljava/lang/String;I)[J",
             format!(
                 "{}",
                 MethodDescriptor::parse("(Ljava/lang/String;I)[J").unwrap()
@@ -181,6 +192,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_access_parameters_and_return_type() {
+        let descriptor = MethodDescriptor::parse("(ILjava/lang/String;[D)J").unwrap();
+        assert_eq!(
+            &[
+                FieldType::Base(BaseType::Int),
+                FieldType::Object("java/lang/String".to_string()),
+                FieldType::Array(Box::new(FieldType::Base(BaseType::Double))),
+            ],
+            descriptor.parameters()
+        );
+        assert_eq!(
+            Some(&FieldType::Base(BaseType::Long)),
+            descriptor.return_type()
+        );
+        assert_eq!("(ILjava/lang/String;[D)J", descriptor.to_string());
+        assert_eq!(None, MethodDescriptor::parse("()V").unwrap().return_type());
+    }
+
     #[test]
     fn can_get_num_arguments() {
         assert_eq!(
@@ -0,0 +1,472 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    class_file_method::ClassFileMethodCode,
+    class_reader_error::{ClassReaderError, Result},
+    constant_pool::{ConstantPool, ConstantPoolEntry},
+    field_type::{BaseType, FieldType},
+    instruction::Instruction,
+    method_descriptor::MethodDescriptor,
+    program_counter::ProgramCounter,
+};
+
+/// Depth of the operand stack around the execution of an instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionStackDepth {
+    pub address: usize,
+    /// Depth before executing the instruction
+    pub before: usize,
+    /// Depth after executing the instruction
+    pub after: usize,
+}
+
+/// Simulates the execution of the code of a method, tracking only the depth of the operand
+/// stack, and returns it for each reachable instruction, sorted by address. Fails if the depth
+/// at some address differs between two paths, if the stack would underflow, if a jump target
+/// is not the start of an instruction, or if the execution can continue past the end of the code.
+pub fn simulate_stack_depths(
+    code: &ClassFileMethodCode,
+    constants: &ConstantPool,
+) -> Result<Vec<InstructionStackDepth>> {
+    let instructions = Instruction::parse_instructions(&code.code)?;
+    let index_by_address: HashMap<usize, usize> = instructions
+        .iter()
+        .enumerate()
+        .map(|(index, (address, _))| (*address, index))
+        .collect();
+    let stack_effects = StackEffects { constants };
+
+    let mut depths: BTreeMap<usize, InstructionStackDepth> = BTreeMap::new();
+    let mut to_visit: Vec<(usize, usize)> = vec![(0, 0)];
+    while let Some((address, depth)) = to_visit.pop() {
+        let index = *index_by_address.get(&address).ok_or_else(|| {
+            ClassReaderError::invalid_class_data(format!(
+                "jump to address {address}, which is not the start of an instruction"
+            ))
+        })?;
+        match depths.get(&address) {
+            Some(previous) if previous.before != depth => {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "inconsistent stack depth at address {address}"
+                )));
+            }
+            Some(_) => continue,
+            None => {}
+        };
+
+        // Exception handlers start with only the exception on the stack
+        for entry in code.exception_table.lookup(ProgramCounter(address as u16)) {
+            to_visit.push((entry.handler_pc.0 as usize, 1));
+        }
+
+        let instruction = &instructions[index].1;
+        let (popped, pushed) = stack_effects.stack_effect(instruction)?;
+        if depth < popped {
+            return Err(ClassReaderError::invalid_class_data(format!(
+                "stack underflow at address {address}"
+            )));
+        }
+        let new_depth = depth - popped + pushed;
+        depths.insert(
+            address,
+            InstructionStackDepth {
+                address,
+                before: depth,
+                after: new_depth,
+            },
+        );
+
+        let next_address = instructions.get(index + 1).map(|(address, _)| *address);
+        if instruction.falls_through() && next_address.is_none() {
+            return Err(ClassReaderError::invalid_class_data(
+                "execution can continue past the end of the code".to_string(),
+            ));
+        }
+        let successors = code.successors_of(address, instruction, next_address)?;
+        to_visit.extend(
+            successors
+                .into_iter()
+                .map(|successor| (successor, new_depth)),
+        );
+    }
+    Ok(depths.into_values().collect())
+}
+
+/// Returns the maximum depth that the operand stack can reach while executing the code of a
+/// method, which can be compared with the declared [ClassFileMethodCode::max_stack] to check
+/// that it is adequate. See [simulate_stack_depths] for the possible errors.
+pub fn max_stack_depth(code: &ClassFileMethodCode, constants: &ConstantPool) -> Result<usize> {
+    Ok(simulate_stack_depths(code, constants)?
+        .iter()
+        .map(|depth| depth.before.max(depth.after))
+        .max()
+        .unwrap_or(0))
+}
+
+/// Computes how many stack slots each instruction pops and pushes. Longs and doubles take
+/// two slots, like in the JVM specs.
+struct StackEffects<'a> {
+    constants: &'a ConstantPool,
+}
+
+impl<'a> StackEffects<'a> {
+    fn stack_effect(&self, instruction: &Instruction) -> Result<(usize, usize)> {
+        let effect = match instruction {
+            Instruction::Nop
+            | Instruction::Goto(_)
+            | Instruction::Goto_w(_)
+            | Instruction::Iinc(_, _)
+            | Instruction::Ret(_)
+            | Instruction::Return => (0, 0),
+
+            Instruction::Aconst_null
+            | Instruction::Iconst_m1
+            | Instruction::Iconst_0
+            | Instruction::Iconst_1
+            | Instruction::Iconst_2
+            | Instruction::Iconst_3
+            | Instruction::Iconst_4
+            | Instruction::Iconst_5
+            | Instruction::Fconst_0
+            | Instruction::Fconst_1
+            | Instruction::Fconst_2
+            | Instruction::Bipush(_)
+            | Instruction::Sipush(_)
+            | Instruction::Ldc(_)
+            | Instruction::Ldc_w(_)
+            | Instruction::Aload(_)
+            | Instruction::Aload_0
+            | Instruction::Aload_1
+            | Instruction::Aload_2
+            | Instruction::Aload_3
+            | Instruction::Fload(_)
+            | Instruction::Fload_0
+            | Instruction::Fload_1
+            | Instruction::Fload_2
+            | Instruction::Fload_3
+            | Instruction::Iload(_)
+            | Instruction::Iload_0
+            | Instruction::Iload_1
+            | Instruction::Iload_2
+            | Instruction::Iload_3
+            | Instruction::New(_)
+            | Instruction::Jsr(_)
+            | Instruction::Jsr_w(_) => (0, 1),
+
+            Instruction::Dconst_0
+            | Instruction::Dconst_1
+            | Instruction::Lconst_0
+            | Instruction::Lconst_1
+            | Instruction::Ldc2_w(_)
+            | Instruction::Dload(_)
+            | Instruction::Dload_0
+            | Instruction::Dload_1
+            | Instruction::Dload_2
+            | Instruction::Dload_3
+            | Instruction::Lload(_)
+            | Instruction::Lload_0
+            | Instruction::Lload_1
+            | Instruction::Lload_2
+            | Instruction::Lload_3 => (0, 2),
+
+            Instruction::Astore(_)
+            | Instruction::Astore_0
+            | Instruction::Astore_1
+            | Instruction::Astore_2
+            | Instruction::Astore_3
+            | Instruction::Fstore(_)
+            | Instruction::Fstore_0
+            | Instruction::Fstore_1
+            | Instruction::Fstore_2
+            | Instruction::Fstore_3
+            | Instruction::Istore(_)
+            | Instruction::Istore_0
+            | Instruction::Istore_1
+            | Instruction::Istore_2
+            | Instruction::Istore_3
+            | Instruction::Areturn
+            | Instruction::Freturn
+            | Instruction::Ireturn
+            | Instruction::Athrow
+            | Instruction::Pop
+            | Instruction::Ifeq(_)
+            | Instruction::Ifne(_)
+            | Instruction::Iflt(_)
+            | Instruction::Ifge(_)
+            | Instruction::Ifgt(_)
+            | Instruction::Ifle(_)
+            | Instruction::Ifnull(_)
+            | Instruction::Ifnonnull(_)
+            | Instruction::Lookupswitch
+            | Instruction::Tableswitch
+            | Instruction::Monitorenter
+            | Instruction::Monitorexit => (1, 0),
+
+            Instruction::Dstore(_)
+            | Instruction::Dstore_0
+            | Instruction::Dstore_1
+            | Instruction::Dstore_2
+            | Instruction::Dstore_3
+            | Instruction::Lstore(_)
+            | Instruction::Lstore_0
+            | Instruction::Lstore_1
+            | Instruction::Lstore_2
+            | Instruction::Lstore_3
+            | Instruction::Dreturn
+            | Instruction::Lreturn
+            | Instruction::Pop2
+            | Instruction::If_acmpeq(_)
+            | Instruction::If_acmpne(_)
+            | Instruction::If_icmpeq(_)
+            | Instruction::If_icmpne(_)
+            | Instruction::If_icmplt(_)
+            | Instruction::If_icmpge(_)
+            | Instruction::If_icmpgt(_)
+            | Instruction::If_icmple(_) => (2, 0),
+
+            Instruction::Aastore
+            | Instruction::Bastore
+            | Instruction::Castore
+            | Instruction::Fastore
+            | Instruction::Iastore
+            | Instruction::Sastore => (3, 0),
+            Instruction::Dastore | Instruction::Lastore => (4, 0),
+
+            Instruction::Anewarray(_)
+            | Instruction::Arraylength
+            | Instruction::Checkcast(_)
+            | Instruction::Instanceof(_)
+            | Instruction::Newarray(_)
+            | Instruction::F2i
+            | Instruction::I2b
+            | Instruction::I2c
+            | Instruction::I2f
+            | Instruction::I2s
+            | Instruction::Fneg
+            | Instruction::Ineg => (1, 1),
+            Instruction::F2d | Instruction::F2l | Instruction::I2d | Instruction::I2l => (1, 2),
+            Instruction::D2f | Instruction::D2i | Instruction::L2f | Instruction::L2i => (2, 1),
+            Instruction::D2l
+            | Instruction::L2d
+            | Instruction::Dneg
+            | Instruction::Lneg
+            | Instruction::Swap => (2, 2),
+
+            Instruction::Aaload
+            | Instruction::Baload
+            | Instruction::Caload
+            | Instruction::Faload
+            | Instruction::Iaload
+            | Instruction::Saload
+            | Instruction::Fadd
+            | Instruction::Fcmpg
+            | Instruction::Fcmpl
+            | Instruction::Fdiv
+            | Instruction::Fmul
+            | Instruction::Frem
+            | Instruction::Fsub
+            | Instruction::Iadd
+            | Instruction::Iand
+            | Instruction::Idiv
+            | Instruction::Imul
+            | Instruction::Ior
+            | Instruction::Irem
+            | Instruction::Ishl
+            | Instruction::Ishr
+            | Instruction::Isub
+            | Instruction::Iushr
+            | Instruction::Ixor => (2, 1),
+            Instruction::Daload | Instruction::Laload => (2, 2),
+            Instruction::Lshl | Instruction::Lshr | Instruction::Lushr => (3, 2),
+            Instruction::Dadd
+            | Instruction::Ddiv
+            | Instruction::Dmul
+            | Instruction::Drem
+            | Instruction::Dsub
+            | Instruction::Ladd
+            | Instruction::Land
+            | Instruction::Ldiv
+            | Instruction::Lmul
+            | Instruction::Lor
+            | Instruction::Lrem
+            | Instruction::Lsub
+            | Instruction::Lxor => (4, 2),
+            Instruction::Dcmpg | Instruction::Dcmpl | Instruction::Lcmp => (4, 1),
+
+            Instruction::Dup => (1, 2),
+            Instruction::Dup_x1 => (2, 3),
+            Instruction::Dup_x2 => (3, 4),
+            Instruction::Dup2 => (2, 4),
+            Instruction::Dup2_x1 => (3, 5),
+            Instruction::Dup2_x2 => (4, 6),
+
+            Instruction::Getstatic(index) => (0, self.field_size(*index)?),
+            Instruction::Putstatic(index) => (self.field_size(*index)?, 0),
+            Instruction::Getfield(index) => (1, self.field_size(*index)?),
+            Instruction::Putfield(index) => (1 + self.field_size(*index)?, 0),
+
+            Instruction::Invokestatic(index) => self.invocation_effect(*index, 0)?,
+            Instruction::Invokevirtual(index)
+            | Instruction::Invokespecial(index)
+            | Instruction::Invokeinterface(index, _) => self.invocation_effect(*index, 1)?,
+
+            Instruction::Multianewarray(_, dimensions) => (*dimensions as usize, 1),
+
+            Instruction::Invokedynamic(_) | Instruction::Wide => {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "unsupported instruction {instruction:?}"
+                )));
+            }
+        };
+        Ok(effect)
+    }
+
+    fn field_size(&self, constant_index: u16) -> Result<usize> {
+        let descriptor = self.referenced_type_descriptor(constant_index)?;
+        let field_type = FieldType::parse(descriptor)?;
+        Ok(Self::size_of(&field_type))
+    }
+
+    fn invocation_effect(
+        &self,
+        constant_index: u16,
+        receiver_size: usize,
+    ) -> Result<(usize, usize)> {
+        let descriptor = self.referenced_type_descriptor(constant_index)?;
+        let descriptor = MethodDescriptor::parse(descriptor)?;
+        let arguments_size: usize = descriptor.parameters.iter().map(Self::size_of).sum();
+        let return_size = descriptor.return_type.as_ref().map_or(0, Self::size_of);
+        Ok((receiver_size + arguments_size, return_size))
+    }
+
+    /// Returns the type descriptor of a field or method reference
+    fn referenced_type_descriptor(&self, constant_index: u16) -> Result<&str> {
+        let name_and_type_index = match self.constant(constant_index)? {
+            ConstantPoolEntry::FieldReference(_, name_and_type_index)
+            | ConstantPoolEntry::MethodReference(_, name_and_type_index)
+            | ConstantPoolEntry::InterfaceMethodReference(_, name_and_type_index) => {
+                *name_and_type_index
+            }
+            _ => {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "constant {constant_index} is not a reference"
+                )))
+            }
+        };
+        let type_descriptor_index = match self.constant(name_and_type_index)? {
+            ConstantPoolEntry::NameAndTypeDescriptor(_, type_descriptor_index) => {
+                *type_descriptor_index
+            }
+            _ => {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "constant {name_and_type_index} is not a name and type"
+                )))
+            }
+        };
+        match self.constant(type_descriptor_index)? {
+            ConstantPoolEntry::Utf8(descriptor) => Ok(descriptor),
+            _ => Err(ClassReaderError::invalid_class_data(format!(
+                "constant {type_descriptor_index} is not a string"
+            ))),
+        }
+    }
+
+    fn constant(&self, constant_index: u16) -> Result<&ConstantPoolEntry> {
+        self.constants.get(constant_index).map_err(|err| err.into())
+    }
+
+    fn size_of(field_type: &FieldType) -> usize {
+        match field_type {
+            FieldType::Base(BaseType::Long) | FieldType::Base(BaseType::Double) => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        class_file_method::ClassFileMethodCode,
+        constant_pool::ConstantPool,
+        stack_depth::{max_stack_depth, simulate_stack_depths, InstructionStackDepth},
+    };
+
+    fn code_of(code: Vec<u8>) -> ClassFileMethodCode {
+        ClassFileMethodCode {
+            code,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn depths_are_tracked_along_all_paths() {
+        let code = code_of(vec![
+            0x1a, // 0: iload_0
+            0x99, 0x00, 0x07, // 1: ifeq 8
+            0x09, // 4: lconst_0
+            0x0a, // 5: lconst_1
+            0x61, // 6: ladd
+            0xad, // 7: lreturn
+            0x0a, // 8: lconst_1
+            0xad, // 9: lreturn
+        ]);
+        let constants = ConstantPool::new();
+        assert_eq!(
+            Ok(vec![
+                InstructionStackDepth {
+                    address: 0,
+                    before: 0,
+                    after: 1
+                },
+                InstructionStackDepth {
+                    address: 1,
+                    before: 1,
+                    after: 0
+                },
+                InstructionStackDepth {
+                    address: 4,
+                    before: 0,
+                    after: 2
+                },
+                InstructionStackDepth {
+                    address: 5,
+                    before: 2,
+                    after: 4
+                },
+                InstructionStackDepth {
+                    address: 6,
+                    before: 4,
+                    after: 2
+                },
+                InstructionStackDepth {
+                    address: 7,
+                    before: 2,
+                    after: 0
+                },
+                InstructionStackDepth {
+                    address: 8,
+                    before: 0,
+                    after: 2
+                },
+                InstructionStackDepth {
+                    address: 9,
+                    before: 2,
+                    after: 0
+                },
+            ]),
+            simulate_stack_depths(&code, &constants)
+        );
+        assert_eq!(Ok(4), max_stack_depth(&code, &constants));
+    }
+
+    #[test]
+    fn inconsistent_depths_are_an_error() {
+        let code = code_of(vec![
+            0x1a, // 0: iload_0
+            0x99, 0x00, 0x04, // 1: ifeq 5
+            0x04, // 4: iconst_1
+            0xb1, // 5: return
+        ]);
+        assert!(max_stack_depth(&code, &ConstantPool::new()).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{
+    buffer::Buffer,
+    class_reader_error::{ClassReaderError, Result},
+    constant_pool::ConstantPool,
+    program_counter::ProgramCounter,
+};
+
+/// Models the `StackMapTable` attribute of the code of a method, used by the type checking
+/// verifier introduced in Java 6. Each frame describes the types of the local variables and
+/// of the stack entries at a given address, relative to the previous frame.
+#[derive(Debug, Default, PartialEq)]
+pub struct StackMapTable {
+    pub frames: Vec<StackMapFrame>,
+}
+
+/// A frame of a [StackMapTable]. The "extended" forms of the class file, which only differ
+/// by the size of the offset delta, are modelled with the same variant as the short ones.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StackMapFrame {
+    /// Same locals as the previous frame, and an empty stack
+    Same { offset_delta: u16 },
+    /// Same locals as the previous frame, and one entry on the stack
+    SameLocals1StackItem {
+        offset_delta: u16,
+        stack: VerificationType,
+    },
+    /// Same locals as the previous frame except the last ones, which are absent,
+    /// and an empty stack
+    Chop {
+        offset_delta: u16,
+        chopped_locals: u8,
+    },
+    /// Same locals as the previous frame plus the given ones, and an empty stack
+    Append {
+        offset_delta: u16,
+        locals: Vec<VerificationType>,
+    },
+    /// All the locals and stack entries are given explicitly
+    Full {
+        offset_delta: u16,
+        locals: Vec<VerificationType>,
+        stack: Vec<VerificationType>,
+    },
+}
+
+/// The type of a local variable or of a stack entry in a [StackMapFrame]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    /// An instance of the given class
+    Object(String),
+    /// An object created by the `new` instruction at the given address, whose constructor
+    /// has not been invoked yet
+    Uninitialized(ProgramCounter),
+}
+
+impl StackMapFrame {
+    pub fn offset_delta(&self) -> u16 {
+        match self {
+            StackMapFrame::Same { offset_delta }
+            | StackMapFrame::SameLocals1StackItem { offset_delta, .. }
+            | StackMapFrame::Chop { offset_delta, .. }
+            | StackMapFrame::Append { offset_delta, .. }
+            | StackMapFrame::Full { offset_delta, .. } => *offset_delta,
+        }
+    }
+}
+
+impl StackMapTable {
+    /// Parses the content of a `StackMapTable` attribute. The class names of the
+    /// object types are resolved with the given constant pool.
+    pub fn parse(bytes: &[u8], constants: &ConstantPool) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let number_of_entries = buffer.read_u16()?;
+        let frames = (0..number_of_entries)
+            .map(|_| Self::parse_frame(&mut buffer, constants))
+            .collect::<Result<Vec<StackMapFrame>>>()?;
+        if buffer.has_more_data() {
+            return Err(ClassReaderError::invalid_class_data(
+                "unexpected data at the end of the StackMapTable attribute".to_string(),
+            ));
+        }
+        Ok(Self { frames })
+    }
+
+    fn parse_frame(buffer: &mut Buffer, constants: &ConstantPool) -> Result<StackMapFrame> {
+        let frame_type = buffer.read_u8()?;
+        Ok(match frame_type {
+            0..=63 => StackMapFrame::Same {
+                offset_delta: frame_type as u16,
+            },
+            64..=127 => StackMapFrame::SameLocals1StackItem {
+                offset_delta: frame_type as u16 - 64,
+                stack: Self::parse_verification_type(buffer, constants)?,
+            },
+            247 => StackMapFrame::SameLocals1StackItem {
+                offset_delta: buffer.read_u16()?,
+                stack: Self::parse_verification_type(buffer, constants)?,
+            },
+            248..=250 => StackMapFrame::Chop {
+                offset_delta: buffer.read_u16()?,
+                chopped_locals: 251 - frame_type,
+            },
+            251 => StackMapFrame::Same {
+                offset_delta: buffer.read_u16()?,
+            },
+            252..=254 => StackMapFrame::Append {
+                offset_delta: buffer.read_u16()?,
+                locals: Self::parse_verification_types(
+                    buffer,
+                    constants,
+                    frame_type as usize - 251,
+                )?,
+            },
+            255 => {
+                let offset_delta = buffer.read_u16()?;
+                let number_of_locals = buffer.read_u16()?;
+                let locals =
+                    Self::parse_verification_types(buffer, constants, number_of_locals as usize)?;
+                let number_of_stack_items = buffer.read_u16()?;
+                let stack = Self::parse_verification_types(
+                    buffer,
+                    constants,
+                    number_of_stack_items as usize,
+                )?;
+                StackMapFrame::Full {
+                    offset_delta,
+                    locals,
+                    stack,
+                }
+            }
+            _ => {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "invalid stack map frame type {frame_type}"
+                )))
+            }
+        })
+    }
+
+    fn parse_verification_types(
+        buffer: &mut Buffer,
+        constants: &ConstantPool,
+        count: usize,
+    ) -> Result<Vec<VerificationType>> {
+        (0..count)
+            .map(|_| Self::parse_verification_type(buffer, constants))
+            .collect()
+    }
+
+    fn parse_verification_type(
+        buffer: &mut Buffer,
+        constants: &ConstantPool,
+    ) -> Result<VerificationType> {
+        Ok(match buffer.read_u8()? {
+            0 => VerificationType::Top,
+            1 => VerificationType::Integer,
+            2 => VerificationType::Float,
+            3 => VerificationType::Double,
+            4 => VerificationType::Long,
+            5 => VerificationType::Null,
+            6 => VerificationType::UninitializedThis,
+            7 => VerificationType::Object(constants.text_of(buffer.read_u16()?)?),
+            8 => VerificationType::Uninitialized(ProgramCounter(buffer.read_u16()?)),
+            tag => {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "invalid verification type tag {tag}"
+                )))
+            }
+        })
+    }
+
+    /// Returns the addresses described by the frames. The first frame is at its offset
+    /// delta, the following ones at offset delta + 1 from the previous, so that there
+    /// cannot be two frames at the same address.
+    pub fn frame_addresses(&self) -> Vec<usize> {
+        let mut addresses: Vec<usize> = Vec::with_capacity(self.frames.len());
+        for frame in self.frames.iter() {
+            let offset_delta = frame.offset_delta() as usize;
+            let address = match addresses.last() {
+                None => offset_delta,
+                Some(previous_address) => previous_address + offset_delta + 1,
+            };
+            addresses.push(address);
+        }
+        addresses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constant_pool::{ConstantPool, ConstantPoolEntry},
+        program_counter::ProgramCounter,
+        stack_map_table::{StackMapFrame, StackMapTable, VerificationType},
+    };
+
+    fn constants() -> ConstantPool {
+        let mut constants = ConstantPool::new();
+        constants.add(ConstantPoolEntry::Utf8("java/lang/String".to_string()));
+        constants.add(ConstantPoolEntry::ClassReference(1));
+        constants
+    }
+
+    #[test]
+    fn all_frame_types_are_parsed() {
+        let table = StackMapTable::parse(
+            &[
+                0x00, 0x07, // 7 entries
+                0x05, // same_frame
+                0x41, 0x01, // same_locals_1_stack_item_frame (integer)
+                0xf7, 0x01, 0x00, 0x05, // same_locals_1_stack_item_frame_extended (null)
+                0xf9, 0x00, 0x02, // chop_frame of 2 locals
+                0xfb, 0x01, 0x00, // same_frame_extended
+                0xfc, 0x00, 0x02, 0x07, 0x00, 0x02, // append_frame (object #2)
+                0xff, 0x00, 0x00, 0x00, 0x02, 0x04, 0x00, 0x00, 0x01, 0x08, 0x00,
+                0x03,
+                // full_frame (long, top) with an uninitialized object created at 3
+            ],
+            &constants(),
+        )
+        .expect("should be able to parse the table");
+
+        assert_eq!(
+            vec![
+                StackMapFrame::Same { offset_delta: 5 },
+                StackMapFrame::SameLocals1StackItem {
+                    offset_delta: 1,
+                    stack: VerificationType::Integer,
+                },
+                StackMapFrame::SameLocals1StackItem {
+                    offset_delta: 256,
+                    stack: VerificationType::Null,
+                },
+                StackMapFrame::Chop {
+                    offset_delta: 2,
+                    chopped_locals: 2,
+                },
+                StackMapFrame::Same { offset_delta: 256 },
+                StackMapFrame::Append {
+                    offset_delta: 2,
+                    locals: vec![VerificationType::Object("java/lang/String".to_string())],
+                },
+                StackMapFrame::Full {
+                    offset_delta: 0,
+                    locals: vec![VerificationType::Long, VerificationType::Top],
+                    stack: vec![VerificationType::Uninitialized(ProgramCounter(3))],
+                },
+            ],
+            table.frames
+        );
+        assert_eq!(vec![5, 7, 264, 267, 524, 527, 528], table.frame_addresses());
+    }
+
+    #[test]
+    fn invalid_frame_type_is_an_error() {
+        assert!(StackMapTable::parse(&[0x00, 0x01, 0x80], &constants()).is_err());
+    }
+
+    #[test]
+    fn invalid_verification_type_is_an_error() {
+        assert!(StackMapTable::parse(&[0x00, 0x01, 0x40, 0x09], &constants()).is_err());
+    }
+
+    #[test]
+    fn trailing_data_is_an_error() {
+        assert!(StackMapTable::parse(&[0x00, 0x01, 0x00, 0x00], &constants()).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+extern crate rjvm_reader;
+
+use rjvm_reader::{
+    class_reader::{read_buffer_with_limits, ClassReaderLimits},
+    class_reader_error::ClassReaderError,
+};
+
+const COMPLEX_CLASS: &[u8] = include_bytes!("../resources/rjvm/Complex.class");
+
+fn read_with_limits(limits: ClassReaderLimits) -> Result<(), String> {
+    read_buffer_with_limits(COMPLEX_CLASS, &limits)
+        .map(|_| ())
+        .map_err(|err| match err {
+            ClassReaderError::InvalidClassData(message, _) => message,
+            err => panic!("unexpected error {err:?}"),
+        })
+}
+
+#[test_log::test]
+fn class_within_the_limits_can_be_read() {
+    assert_eq!(Ok(()), read_with_limits(ClassReaderLimits::default()));
+}
+
+#[test_log::test]
+fn too_many_methods_are_rejected() {
+    let result = read_with_limits(ClassReaderLimits {
+        max_methods: 1,
+        ..Default::default()
+    });
+    assert!(result
+        .expect_err("should have failed")
+        .starts_with("number of methods is"));
+}
+
+#[test_log::test]
+fn too_long_code_is_rejected() {
+    let result = read_with_limits(ClassReaderLimits {
+        max_code_length: 2,
+        ..Default::default()
+    });
+    assert!(result
+        .expect_err("should have failed")
+        .starts_with("code length is"));
+}
@@ -6,7 +6,7 @@ use utils::read_class_from_bytes;
 #[test_log::test]
 fn can_read_deprecated_attribute() {
     let class = read_class_from_bytes(include_bytes!("../resources/rjvm/DeprecatedClass.class"));
-    assert!(class.deprecated);
+    assert!(class.meta.deprecated);
 
     class.fields.get(0).unwrap();
 
@@ -15,12 +15,12 @@ fn can_read_deprecated_attribute() {
         .into_iter()
         .find(|f| f.name == "deprecatedField")
         .expect("should find field");
-    assert!(field.deprecated);
+    assert!(field.meta.deprecated);
 
     let method = class
         .methods
         .into_iter()
         .find(|m| m.name == "deprecatedMethod")
         .expect("should find method");
-    assert!(method.deprecated);
+    assert!(method.meta.deprecated);
 }
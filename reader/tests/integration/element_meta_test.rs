@@ -0,0 +1,71 @@
+extern crate rjvm_reader;
+
+use rjvm_reader::element_meta::ElementMeta;
+use utils::read_class_from_bytes;
+
+use crate::utils;
+
+#[test_log::test]
+fn can_read_element_meta() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/ElementMetadata.class"));
+    assert_eq!(
+        ElementMeta {
+            deprecated: false,
+            synthetic: false,
+            signature: Some(
+                "<T:Ljava/lang/Object;>Ljava/lang/Object;Ljava/lang/Comparable<Lrjvm/ElementMetadata<TT;>;>;"
+                    .to_string()
+            ),
+        },
+        class.meta
+    );
+
+    let field = class
+        .fields
+        .iter()
+        .find(|f| f.name == "genericField")
+        .expect("should find field");
+    assert_eq!(
+        Some("Ljava/util/List<Ljava/lang/String;>;"),
+        field.meta.signature.as_deref()
+    );
+
+    let compare_to = class
+        .methods
+        .iter()
+        .find(|m| m.name == "compareTo" && m.type_descriptor == "(Lrjvm/ElementMetadata;)I")
+        .expect("should find method");
+    assert_eq!(
+        ElementMeta {
+            deprecated: true,
+            synthetic: false,
+            signature: Some("(Lrjvm/ElementMetadata<TT;>;)I".to_string()),
+        },
+        compare_to.meta
+    );
+
+    // The bridge method is only marked with the access flag
+    let bridge = class
+        .methods
+        .iter()
+        .find(|m| m.name == "compareTo" && m.type_descriptor == "(Ljava/lang/Object;)I")
+        .expect("should find bridge method");
+    assert_eq!(
+        ElementMeta {
+            deprecated: false,
+            synthetic: true,
+            signature: None,
+        },
+        bridge.meta
+    );
+
+    let generic_method = class
+        .methods
+        .iter()
+        .find(|m| m.name == "genericMethod")
+        .expect("should find method");
+    assert_eq!(
+        Some("<U:TT;>(Ljava/util/List<TU;>;)TU;"),
+        generic_method.meta.signature.as_deref()
+    );
+}
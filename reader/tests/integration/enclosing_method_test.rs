@@ -0,0 +1,39 @@
+extern crate rjvm_reader;
+
+use rjvm_reader::enclosing_method::{EnclosingMethod, EnclosingMethodReference};
+use utils::read_class_from_bytes;
+
+use crate::utils;
+
+#[test_log::test]
+fn top_level_class_has_no_enclosing_method() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/EnclosingMethods.class"));
+    assert_eq!(None, class.enclosing_method);
+}
+
+#[test_log::test]
+fn anonymous_class_in_field_initializer_has_no_enclosing_method() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/EnclosingMethods$1.class"));
+    assert_eq!(
+        Some(EnclosingMethod {
+            class_name: "rjvm/EnclosingMethods".to_string(),
+            method: None,
+        }),
+        class.enclosing_method
+    );
+}
+
+#[test_log::test]
+fn anonymous_class_in_method_has_enclosing_method() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/EnclosingMethods$2.class"));
+    assert_eq!(
+        Some(EnclosingMethod {
+            class_name: "rjvm/EnclosingMethods".to_string(),
+            method: Some(EnclosingMethodReference {
+                name: "method".to_string(),
+                type_descriptor: "(I)Ljava/lang/Runnable;".to_string(),
+            }),
+        }),
+        class.enclosing_method
+    );
+}
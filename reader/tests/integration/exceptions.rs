@@ -50,3 +50,18 @@ fn check_methods(class: &ClassFile) {
         class.methods[3].code.as_ref().unwrap().exception_table
     );
 }
+
+#[test_log::test]
+fn can_read_checked_exceptions_declared_by_methods() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/CheckedExceptions.class"));
+    assert_eq!(3, class.methods.len());
+
+    check_method(&class.methods[1], MethodFlags::empty(), "read", "()V");
+    assert_eq!(
+        vec!["java/io/IOException".to_string()],
+        class.methods[1].thrown_exceptions
+    );
+
+    check_method(&class.methods[2], MethodFlags::empty(), "safe", "()V");
+    assert!(class.methods[2].thrown_exceptions.is_empty());
+}
@@ -0,0 +1,22 @@
+extern crate rjvm_reader;
+
+use rjvm_reader::{class_file_version::ClassFileVersion, method_flags::MethodFlags};
+use utils::read_class_from_bytes;
+
+use crate::utils;
+
+#[test_log::test]
+fn can_read_java8_class() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/DefaultMethods.class"));
+    assert_eq!(ClassFileVersion::Jdk8, class.version);
+    assert!(class.version.is_at_least(ClassFileVersion::Jdk8));
+    assert!(!class.version.is_at_least(ClassFileVersion::Jdk9));
+
+    let default_method = class
+        .methods
+        .iter()
+        .find(|m| m.name == "doubleValue")
+        .expect("should find the default method");
+    assert!(!default_method.flags.contains(MethodFlags::ABSTRACT));
+    assert!(default_method.code.is_some());
+}
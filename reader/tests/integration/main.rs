@@ -1,6 +1,13 @@
 mod assertions;
+mod class_reader_limits_test;
 mod constants_class_test;
 mod deprecated_class_test;
+mod element_meta_test;
+mod enclosing_method_test;
 mod exceptions;
+mod java8_class_test;
 mod pojo_class_test;
+mod referenced_classes_test;
+mod stack_depth_test;
+mod stack_map_table_test;
 mod utils;
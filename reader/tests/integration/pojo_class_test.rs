@@ -5,6 +5,7 @@ use rjvm_reader::{
     class_file::ClassFile,
     class_file_field::ClassFileField,
     class_file_version::ClassFileVersion,
+    element_meta::ElementMeta,
     field_flags::FieldFlags,
     field_type::{BaseType, FieldType},
     line_number::LineNumber,
@@ -47,14 +48,14 @@ fn check_fields(class: &ClassFile) {
                 name: "real".to_string(),
                 type_descriptor: FieldType::Base(BaseType::Double),
                 constant_value: None,
-                deprecated: false,
+                meta: ElementMeta::default(),
             },
             ClassFileField {
                 flags: FieldFlags::PRIVATE | FieldFlags::FINAL,
                 name: "imag".to_string(),
                 type_descriptor: FieldType::Base(BaseType::Double),
                 constant_value: None,
-                deprecated: false,
+                meta: ElementMeta::default(),
             }
         ),
         class.fields
@@ -0,0 +1,19 @@
+extern crate rjvm_reader;
+
+use crate::utils;
+use utils::read_class_from_bytes;
+
+#[test_log::test]
+fn can_find_referenced_classes() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/ReferencedClasses.class"));
+    assert_eq!(
+        vec![
+            "java/io/PrintStream",
+            "java/lang/Object",
+            "java/lang/String",
+            "java/lang/System",
+            "rjvm/Complex",
+        ],
+        class.referenced_classes()
+    );
+}
@@ -0,0 +1,35 @@
+extern crate rjvm_reader;
+
+use rjvm_reader::{class_file::ClassFile, stack_depth::max_stack_depth};
+use utils::read_class_from_bytes;
+
+use crate::utils;
+
+fn assert_computed_depth_matches_max_stack(class: &ClassFile) {
+    for method in class.methods.iter() {
+        if let Some(code) = &method.code {
+            assert_eq!(
+                Ok(code.max_stack as usize),
+                max_stack_depth(code, &class.constants),
+                "wrong stack depth for method {}",
+                method.name
+            );
+        }
+    }
+}
+
+#[test_log::test]
+fn computed_stack_depth_matches_declared_max_stack() {
+    assert_computed_depth_matches_max_stack(&read_class_from_bytes(include_bytes!(
+        "../resources/rjvm/Complex.class"
+    )));
+    assert_computed_depth_matches_max_stack(&read_class_from_bytes(include_bytes!(
+        "../resources/rjvm/ExceptionsHandlers.class"
+    )));
+    assert_computed_depth_matches_max_stack(&read_class_from_bytes(include_bytes!(
+        "../resources/rjvm/StackMaps.class"
+    )));
+    assert_computed_depth_matches_max_stack(&read_class_from_bytes(include_bytes!(
+        "../resources/rjvm/Constants.class"
+    )));
+}
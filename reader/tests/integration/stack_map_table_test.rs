@@ -0,0 +1,135 @@
+extern crate rjvm_reader;
+
+use rjvm_reader::{
+    class_file_method::ClassFileMethod,
+    class_file_version::ClassFileVersion,
+    class_reader,
+    program_counter::ProgramCounter,
+    stack_map_table::{StackMapFrame, StackMapTable, VerificationType},
+};
+use utils::read_class_from_bytes;
+
+use crate::utils;
+
+fn stack_map_table_of(method: &ClassFileMethod) -> &StackMapTable {
+    method
+        .code
+        .as_ref()
+        .and_then(|code| code.stack_map_table.as_ref())
+        .expect("method should have a stack map table")
+}
+
+#[test_log::test]
+fn can_read_stack_map_table() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/StackMaps.class"));
+    assert_eq!(3, class.methods.len());
+
+    let constructor = &class.methods[0];
+    assert_eq!(
+        None,
+        constructor.code.as_ref().unwrap().stack_map_table.as_ref()
+    );
+
+    let branches = stack_map_table_of(&class.methods[1]);
+    assert_eq!(
+        vec![
+            StackMapFrame::Append {
+                offset_delta: 18,
+                locals: vec![VerificationType::Integer],
+            },
+            StackMapFrame::Append {
+                offset_delta: 2,
+                locals: vec![VerificationType::Integer],
+            },
+            StackMapFrame::Chop {
+                offset_delta: 18,
+                chopped_locals: 1,
+            },
+            StackMapFrame::Same { offset_delta: 16 },
+            StackMapFrame::SameLocals1StackItem {
+                offset_delta: 0,
+                stack: VerificationType::Object("java/io/Serializable".to_string()),
+            },
+        ],
+        branches.frames
+    );
+    assert_eq!(vec![18, 21, 40, 57, 58], branches.frame_addresses());
+
+    let uninitialized = stack_map_table_of(&class.methods[2]);
+    let locals = vec![
+        VerificationType::Object("rjvm/StackMaps".to_string()),
+        VerificationType::Integer,
+    ];
+    let new_object = VerificationType::Uninitialized(ProgramCounter(0));
+    assert_eq!(
+        vec![
+            StackMapFrame::Full {
+                offset_delta: 13,
+                locals: locals.clone(),
+                stack: vec![new_object.clone(), new_object.clone()],
+            },
+            StackMapFrame::Full {
+                offset_delta: 1,
+                locals,
+                stack: vec![
+                    new_object.clone(),
+                    new_object,
+                    VerificationType::Object("java/lang/String".to_string()),
+                ],
+            },
+        ],
+        uninitialized.frames
+    );
+    assert_eq!(vec![13, 15], uninitialized.frame_addresses());
+}
+
+const STACK_MAPS_CLASS: &[u8] = include_bytes!("../resources/rjvm/StackMaps.class");
+
+/// Returns a copy of StackMaps.class with the given major version, whose first stack map
+/// frame of the method `branches` has the invalid type 200
+fn stack_maps_class_with_invalid_frame(major_version: u8) -> Vec<u8> {
+    let mut bytes = STACK_MAPS_CLASS.to_vec();
+    bytes[7] = major_version;
+    // Five frames, the first being an append of one int with offset delta 18
+    let first_frame = [0, 5, 252, 0, 18, 1];
+    let position = bytes
+        .windows(first_frame.len())
+        .position(|window| window == first_frame)
+        .expect("should find the first frame of branches");
+    bytes[position + 2] = 200;
+    bytes
+}
+
+#[test_log::test]
+fn stack_map_table_is_removed_from_the_raw_attributes() {
+    let class = read_class_from_bytes(STACK_MAPS_CLASS);
+    let code = class.methods[1].code.as_ref().unwrap();
+    assert!(code.stack_map_table.is_some());
+    assert!(code
+        .attributes
+        .iter()
+        .all(|attribute| attribute.name != "StackMapTable"));
+}
+
+#[test_log::test]
+fn invalid_stack_map_table_is_rejected_since_java_7() {
+    assert_eq!(
+        ClassFileVersion::Jdk7,
+        read_class_from_bytes(STACK_MAPS_CLASS).version
+    );
+
+    let result = class_reader::read_buffer(&stack_maps_class_with_invalid_frame(51));
+    assert!(result.is_err());
+}
+
+#[test_log::test]
+fn invalid_stack_map_table_is_ignored_before_java_7() {
+    let class = read_class_from_bytes(&stack_maps_class_with_invalid_frame(50));
+    assert_eq!(ClassFileVersion::Jdk6, class.version);
+    let code = class.methods[1].code.as_ref().unwrap();
+    assert_eq!(None, code.stack_map_table);
+    assert!(code
+        .attributes
+        .iter()
+        .all(|attribute| attribute.name != "StackMapTable"));
+}
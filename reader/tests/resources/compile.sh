@@ -1,2 +1,7 @@
 #!/usr/bin/env sh
-javac -source 6 -target 6 rjvm/*.java
+set -e
+# Some tests check the version of the class files, so each group of classes is compiled
+# for the oldest version supporting its features. Compiling for Java 6 needs a JDK up to 11.
+javac -source 6 -target 6 rjvm/Complex.java rjvm/Constants.java rjvm/DeprecatedClass.java rjvm/ExceptionsHandlers.java
+javac -nowarn -source 7 -target 7 -bootclasspath ../../../vm/rt.jar rjvm/CheckedExceptions.java rjvm/ElementMetadata.java rjvm/EnclosingMethods.java rjvm/InstanceConstants.java rjvm/ReferencedClasses.java rjvm/StackMaps.java
+javac -nowarn -source 8 -target 8 -bootclasspath ../../../vm/rt.jar rjvm/DefaultMethods.java
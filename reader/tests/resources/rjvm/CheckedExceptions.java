@@ -0,0 +1,11 @@
+package rjvm;
+
+import java.io.IOException;
+
+public class CheckedExceptions {
+    void read() throws IOException {
+        throw new IOException("boom");
+    }
+
+    void safe() {}
+}
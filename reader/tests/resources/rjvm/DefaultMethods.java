@@ -0,0 +1,9 @@
+package rjvm;
+
+interface DefaultMethods {
+    int value();
+
+    default int doubleValue() {
+        return value() * 2;
+    }
+}
@@ -0,0 +1,16 @@
+package rjvm;
+
+import java.util.List;
+
+class ElementMetadata<T> implements Comparable<ElementMetadata<T>> {
+    List<String> genericField;
+
+    @Deprecated
+    public int compareTo(ElementMetadata<T> other) {
+        return 0;
+    }
+
+    <U extends T> U genericMethod(List<U> list) {
+        return list.get(0);
+    }
+}
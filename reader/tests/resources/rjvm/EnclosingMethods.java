@@ -0,0 +1,13 @@
+package rjvm;
+
+public class EnclosingMethods {
+    private final Runnable field = new Runnable() {
+        public void run() {}
+    };
+
+    Runnable method(int value) {
+        return new Runnable() {
+            public void run() {}
+        };
+    }
+}
@@ -0,0 +1,6 @@
+package rjvm;
+
+class InstanceConstants {
+    public static final int A_STATIC_INT = 2023;
+    final int anInstanceInt = 2024;
+}
@@ -0,0 +1,9 @@
+package rjvm;
+
+public class ReferencedClasses {
+    public static void main(String[] args) {
+        Complex complex = new Complex(1);
+        String[] strings = new String[] {"real: ", String.valueOf(complex.getReal())};
+        System.out.println(strings[0].concat(strings[1]));
+    }
+}
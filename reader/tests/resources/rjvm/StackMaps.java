@@ -0,0 +1,20 @@
+package rjvm;
+
+public class StackMaps {
+    int branches(int value, String text) {
+        int result = 0;
+        if (value > 0) {
+            long big = value * 2L;
+            result = (int) big;
+        }
+        for (int i = 0; i < value; ++i) {
+            result += text.length();
+        }
+        Object object = value > 10 ? new StringBuilder(text) : text;
+        return result + object.hashCode();
+    }
+
+    StringBuilder uninitialized(boolean flag) {
+        return new StringBuilder(flag ? "a" : "b");
+    }
+}
@@ -0,0 +1,67 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use rjvm_vm::{
+    value::Value,
+    vm::{Vm, DEFAULT_MAX_MEMORY},
+};
+
+// Benchmarks running the classes in ../tests/resources/rjvm. Run them with `cargo bench`.
+
+fn create_vm(class_name: &str) -> Vm<'static> {
+    let mut vm = Vm::new(DEFAULT_MAX_MEMORY);
+    let src_dir = env!("CARGO_MANIFEST_DIR");
+    vm.append_class_path(&format!("{src_dir}/rt.jar:{src_dir}/tests/resources"))
+        .expect("should be able to add entries to the classpath");
+
+    let call_stack = vm.allocate_call_stack();
+    vm.preload_classes(call_stack, &[class_name])
+        .expect("should be able to load the benchmarked class");
+    vm
+}
+
+/// Measures the execution of the main method of the given class, which should print the
+/// expected value. Creating the vm and loading the class are not measured.
+fn bench_main(c: &mut Criterion, name: &str, class_name: &str, expected: i32) {
+    c.bench_function(name, |b| {
+        b.iter_batched(
+            || create_vm(class_name),
+            |mut vm| {
+                let call_stack = vm.allocate_call_stack();
+                let main_method = vm
+                    .resolve_class_method(call_stack, class_name, "main", "([Ljava/lang/String;)V")
+                    .expect("should find main method");
+                let result = vm.invoke(call_stack, main_method, None, vec![]);
+                assert!(result.is_ok());
+                assert_eq!(vec![Value::Int(expected)], vm.printed);
+            },
+            BatchSize::PerIteration,
+        )
+    });
+}
+
+fn loops(c: &mut Criterion) {
+    bench_main(c, "loops", "rjvm/LoopBenchmark", 500_000);
+}
+
+fn virtual_calls(c: &mut Criterion) {
+    bench_main(c, "virtual_calls", "rjvm/VirtualCallsBenchmark", 1_000_000);
+}
+
+fn field_access(c: &mut Criterion) {
+    bench_main(c, "field_access", "rjvm/FieldAccessBenchmark", 500_000);
+}
+
+fn array_copy(c: &mut Criterion) {
+    bench_main(c, "array_copy", "rjvm/ArrayCopyBenchmark", 999);
+}
+
+fn instanceof(c: &mut Criterion) {
+    bench_main(c, "instanceof", "rjvm/InstanceofBenchmark", 500_000);
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = loops, virtual_calls, field_access, array_copy, instanceof
+}
+criterion_main!(benches);
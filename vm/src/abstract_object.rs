@@ -296,7 +296,7 @@ unsafe fn read_value2<'a>(ptr: *const u8, field_type: &ArrayEntryType) -> Value<
         ArrayEntryType::Base(BaseType::Long) => Value::Long(std::ptr::read(ptr as *const i64)),
         ArrayEntryType::Base(BaseType::Float) => Value::Float(std::ptr::read(ptr as *const f32)),
         ArrayEntryType::Base(BaseType::Double) => Value::Double(std::ptr::read(ptr as *const f64)),
-        ArrayEntryType::Object(_) | ArrayEntryType::Array => {
+        ArrayEntryType::Object(_) | ArrayEntryType::Array(..) => {
             match std::ptr::read(ptr as *const i64) {
                 0 => Value::Null,
                 _ => Value::Object(std::ptr::read(ptr as *const AbstractObject)),
@@ -393,10 +393,71 @@ impl<'a> Array<'a> for AbstractObject<'a> {
             }
         }
     }
+
+    fn copy_elements_from(
+        &self,
+        src: &Self,
+        src_index: usize,
+        dest_index: usize,
+        length: usize,
+    ) -> Result<(), VmError> {
+        if self.elements_type() != src.elements_type() {
+            return Err(VmError::ValidationException);
+        }
+        if src_index + length > src.len().into_usize_safe()
+            || dest_index + length > self.len().into_usize_safe()
+        {
+            return Err(VmError::ArrayIndexOutOfBoundsException);
+        }
+        unsafe {
+            // Like memmove, this handles overlapping ranges
+            std::ptr::copy(
+                src.ptr_to_array_element(src_index),
+                self.ptr_to_array_element(dest_index),
+                8 * length,
+            );
+        }
+        Ok(())
+    }
+
+    fn fill_elements(&self, start: usize, length: usize, value: Value<'a>) -> Result<(), VmError> {
+        if !can_store_in_array_of(&self.elements_type(), &value) {
+            return Err(VmError::ValidationException);
+        }
+        if start + length > self.len().into_usize_safe() {
+            return Err(VmError::ArrayIndexOutOfBoundsException);
+        }
+        unsafe {
+            // Prepare the content of one element, and then replicate it
+            let mut element: u64 = 0;
+            write_value(&mut element as *mut u64 as *mut u8, value);
+            let elements = std::slice::from_raw_parts_mut(
+                self.ptr_to_array_element(start) as *mut u64,
+                length,
+            );
+            elements.fill(element);
+        }
+        Ok(())
+    }
 }
 
-/// Expects a char[] array and returns it as a string. Must contain valid utf-16.
-pub fn string_from_char_array(array: AbstractObject) -> Result<String, VmError> {
+/// Checks that the value has the representation used by the elements of the array;
+/// the class of objects is not checked
+fn can_store_in_array_of(elements_type: &ArrayEntryType, value: &Value) -> bool {
+    match elements_type {
+        ArrayEntryType::Base(BaseType::Long) => matches!(value, Value::Long(_)),
+        ArrayEntryType::Base(BaseType::Float) => matches!(value, Value::Float(_)),
+        ArrayEntryType::Base(BaseType::Double) => matches!(value, Value::Double(_)),
+        ArrayEntryType::Base(_) => matches!(value, Value::Int(_)),
+        ArrayEntryType::Object(_) | ArrayEntryType::Array(..) => {
+            matches!(value, Value::Object(_) | Value::Null)
+        }
+    }
+}
+
+/// Expects a char[] array and returns its utf-16 code units, which can be invalid utf-16,
+/// for example if they contain lone surrogates
+pub fn utf16_from_char_array(array: AbstractObject) -> Result<Vec<u16>, VmError> {
     if array.kind() != ObjectKind::Array {
         return Err(VmError::ValidationException);
     }
@@ -416,6 +477,11 @@ pub fn string_from_char_array(array: AbstractObject) -> Result<String, VmError>
         }
     }
 
-    let string = String::from_utf16(&string_chars).expect("should have valid utf8 bytes");
-    Ok(string)
+    Ok(string_chars)
+}
+
+/// Expects a char[] array and returns it as a string. Invalid utf-16, such as a lone surrogate,
+/// is replaced by the replacement character U+FFFD.
+pub fn string_from_char_array(array: AbstractObject) -> Result<String, VmError> {
+    utf16_from_char_array(array).map(|string_chars| String::from_utf16_lossy(&string_chars))
 }
@@ -15,4 +15,31 @@ pub trait Array<'a> {
 
     /// Errors will be returned if the index is invalid
     fn get_element(&self, index: usize) -> Result<Value<'a>, VmError>;
+
+    /// Copies `length` elements of `src`, starting from `src_index`, into this array starting
+    /// from `dest_index`, correctly handling overlapping ranges of the same array. Since every
+    /// element takes the same space, regardless of its type, this copies the memory in bulk,
+    /// and it is much faster than a loop of [get_element] and [set_element].
+    /// Errors will be returned if the types of the arrays differ, or if a range is invalid
+    fn copy_elements_from(
+        &self,
+        src: &Self,
+        src_index: usize,
+        dest_index: usize,
+        length: usize,
+    ) -> Result<(), VmError>
+    where
+        Self: Sized;
+
+    /// Sets `length` elements, starting from `start`, to the given value.
+    /// Errors will be returned if the type of the given value does not match the array type, or if the range is invalid
+    fn fill_elements(&self, start: usize, length: usize, value: Value<'a>) -> Result<(), VmError>;
+}
+
+/// Returns whether the given class is a superclass or interface of all arrays
+pub(crate) fn is_implemented_by_arrays(class_name: &str) -> bool {
+    matches!(
+        class_name,
+        "java/lang/Object" | "java/lang/Cloneable" | "java/io/Serializable"
+    )
 }
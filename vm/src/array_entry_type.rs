@@ -12,12 +12,52 @@ use crate::{class::ClassId, class_resolver_by_id::ClassByIdResolver};
 pub enum ArrayEntryType {
     Base(BaseType),
     Object(ClassId),
-    // Note: here we would have to keep the sub-element type. Not doing this means that we do not
-    // correctly support arrays of arrays!
-    Array,
+    /// The entries are themselves arrays, with the given number of dimensions and innermost
+    /// elements. For instance, the entries of an `int[][][]` are `Array(2, Base(Int))`.
+    Array(u8, InnermostArrayEntryType),
+}
+
+/// The type of the innermost elements of a multidimensional array, i.e. of the entries of
+/// the arrays that do not contain other arrays
+#[derive(PartialEq, Clone, Debug)]
+#[repr(u8)]
+pub enum InnermostArrayEntryType {
+    Base(BaseType),
+    Object(ClassId),
 }
 
 impl ArrayEntryType {
+    /// Returns the type of the entries that are arrays with the given elements
+    pub fn array_of(elements_type: ArrayEntryType) -> ArrayEntryType {
+        match elements_type {
+            ArrayEntryType::Base(base_type) => {
+                ArrayEntryType::Array(1, InnermostArrayEntryType::Base(base_type))
+            }
+            ArrayEntryType::Object(class_id) => {
+                ArrayEntryType::Array(1, InnermostArrayEntryType::Object(class_id))
+            }
+            ArrayEntryType::Array(dimensions, innermost) => {
+                ArrayEntryType::Array(dimensions + 1, innermost)
+            }
+        }
+    }
+
+    /// If the entries are arrays, returns the type of their elements
+    pub fn entries_elements_type(&self) -> Option<ArrayEntryType> {
+        match self {
+            ArrayEntryType::Array(1, InnermostArrayEntryType::Base(base_type)) => {
+                Some(ArrayEntryType::Base(base_type.clone()))
+            }
+            ArrayEntryType::Array(1, InnermostArrayEntryType::Object(class_id)) => {
+                Some(ArrayEntryType::Object(*class_id))
+            }
+            ArrayEntryType::Array(dimensions, innermost) => {
+                Some(ArrayEntryType::Array(dimensions - 1, innermost.clone()))
+            }
+            _ => None,
+        }
+    }
+
     pub fn into_field_type<'a>(
         self,
         class_resolver: &impl ClassByIdResolver<'a>,
@@ -27,9 +67,10 @@ impl ArrayEntryType {
             ArrayEntryType::Object(class_id) => class_resolver
                 .find_class_by_id(class_id)
                 .map(|class| FieldType::Object(class.name.clone())),
-            ArrayEntryType::Array => {
-                todo!("Arrays of arrays are not supported at the moment")
-            }
+            ArrayEntryType::Array(..) => self
+                .entries_elements_type()
+                .and_then(|elements_type| elements_type.into_field_type(class_resolver))
+                .map(|elements_type| FieldType::Array(Box::new(elements_type))),
         }
     }
 }
@@ -1,9 +1,13 @@
+use std::cmp::Ordering;
+
 use log::{debug, warn};
 
 use rjvm_reader::{
+    class_access_flags::ClassAccessFlags,
     class_file_field::ClassFileField,
-    class_file_method::ClassFileMethod,
+    class_file_method::{ClassFileMethod, ClassFileMethodCode},
     constant_pool::ConstantPoolEntry,
+    field_flags::FieldFlags,
     field_type::{BaseType, FieldType, FieldType::Base},
     instruction::{Instruction, NewArrayType},
     line_number::LineNumber,
@@ -13,15 +17,16 @@ use rjvm_reader::{
 
 use crate::{
     abstract_object::{AbstractObject, ObjectKind},
-    array::Array,
+    array::{is_implemented_by_arrays, Array},
     array_entry_type::ArrayEntryType,
     call_frame::InstructionCompleted::{ContinueMethodExecution, ReturnFromMethod},
     call_stack::CallStack,
-    class::Class,
+    class::{Class, ClassId, ClassRef},
     class_and_method::ClassAndMethod,
     class_resolver_by_id::ClassByIdResolver,
     exceptions::{JavaException, MethodCallFailed},
-    java_objects_creation::{new_java_lang_class_object, new_java_lang_string_object},
+    instruction_hook::InstructionContext,
+    java_objects_creation::{new_java_exception_object, new_java_lang_class_object},
     object::Object,
     stack_trace_element::StackTraceElement,
     value::{
@@ -29,7 +34,7 @@ use crate::{
         Value::{Double, Float, Int, Long, Null},
     },
     value_stack::ValueStack,
-    vm::Vm,
+    vm::{DebugVerbosity, Vm},
     vm_error::VmError,
 };
 
@@ -53,6 +58,40 @@ struct FieldReference<'a> {
     type_descriptor: &'a str,
 }
 
+/// Identifies a call site, i.e. the method containing the call and the address of the
+/// invoke instruction.
+pub(crate) type CallSite = (*const ClassFileMethod, u16);
+
+/// A monomorphic inline cache for a call site of `invokevirtual` or `invokeinterface`:
+/// it stores the method referenced by the instruction, and the actual implementation
+/// that was invoked the last time, with the class of the receiver. If the next receiver
+/// has the same class, we can skip the whole resolution.
+#[derive(Debug, Clone)]
+pub(crate) struct InlineCacheEntry<'a> {
+    static_method: ClassAndMethod<'a>,
+    receiver_class_id: ClassId,
+    resolved_method: ClassAndMethod<'a>,
+}
+
+/// Identifies a field reference constant, i.e. the class whose constant pool contains it
+/// and its index.
+pub(crate) type FieldSite = (ClassId, u16);
+
+/// Identifies a class reference constant, i.e. the class whose constant pool contains it
+/// and its index.
+pub(crate) type ClassReferenceSite = (ClassId, u16);
+
+/// The result of resolving a field reference constant: the field, its index in the objects'
+/// layout, and the class on which it was looked up. For `getfield` and `putfield` the lookup
+/// is done on the class of the object, so the entry is valid only for objects of that class;
+/// for `getstatic` and `putstatic` it is the class referenced by the constant.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ResolvedField<'a> {
+    class: ClassRef<'a>,
+    index: usize,
+    field: &'a ClassFileField,
+}
+
 /// A call frame for a single method call inside a [CallStack].
 #[derive(Debug)]
 pub struct CallFrame<'a> {
@@ -62,6 +101,10 @@ pub struct CallFrame<'a> {
     /// The current program counter
     pc: ProgramCounter,
 
+    /// The address of the instruction being executed. Differs from [pc] while executing
+    /// the instruction, since [pc] is moved to the next one before the execution.
+    current_instruction_pc: ProgramCounter,
+
     /// The locals variables' map of the method
     locals: Vec<Value<'a>>,
 
@@ -69,11 +112,17 @@ pub struct CallFrame<'a> {
     stack: ValueStack<'a>,
 
     /// The bytecode to execute
-    code: &'a Vec<u8>,
+    code: &'a ClassFileMethodCode,
+
+    /// How much to log about the execution; taken from the [Vm] when the execution starts
+    debug_verbosity: DebugVerbosity,
+
+    /// The objects whose monitor was entered by this frame and not exited yet, most recent last
+    entered_monitors: Vec<AbstractObject<'a>>,
 }
 
 /// One of the possible invocation kind of methods in the JVM.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 enum InvokeKind {
     /// Special instance methods include constructors and calls to method of this class,
     /// bypassing virtual function resolution
@@ -167,19 +216,21 @@ macro_rules! generate_execute_coerce {
     };
 }
 
-/// Pops two values, compares them, and pushes the result (+1, -1, or zero)
+/// Pops two values, compares them, and pushes the result (+1, -1, or zero).
+/// If either value is NaN, the values are unordered and `result_if_unordered` is pushed;
+/// that is the only difference between the "g" and "l" variants of the instructions.
 macro_rules! generate_compare {
     ($name:ident, $pop_fn:ident) => {
-        fn $name(&mut self, sign_for_greater: i32) -> Result<(), MethodCallFailed<'a>> {
+        fn $name(&mut self, result_if_unordered: i32) -> Result<(), MethodCallFailed<'a>> {
             let val2 = self.$pop_fn()?;
             let val1 = self.$pop_fn()?;
-            if val1 > val2 {
-                self.push(Int(sign_for_greater))
-            } else if val1 < val2 {
-                self.push(Int(-sign_for_greater))
-            } else {
-                self.push(Int(0))
-            }
+            let result = match val1.partial_cmp(&val2) {
+                Some(Ordering::Greater) => 1,
+                Some(Ordering::Less) => -1,
+                Some(Ordering::Equal) => 0,
+                None => result_if_unordered,
+            };
+            self.push(Int(result))
         }
     };
 }
@@ -188,11 +239,8 @@ macro_rules! generate_compare {
 macro_rules! generate_execute_load {
     ($name:ident, $($variant:ident),+) => {
         fn $name(&mut self, index: usize) -> Result<(), MethodCallFailed<'a>> {
-            let local = self.locals.get(index).ok_or(VmError::ValidationException)?;
-            match local {
-                $($variant(..) => {
-                    self.push(local.clone())
-                }),+
+            match self.locals.get(index) {
+                $(Some($variant(value)) => self.push($variant(*value)),)+
                 _ => Err(MethodCallFailed::InternalError(VmError::ValidationException)),
             }
         }
@@ -205,10 +253,7 @@ macro_rules! generate_execute_store {
         fn $name(&mut self, index: usize) -> Result<(), MethodCallFailed<'a>> {
             let value = self.pop()?;
             match value {
-                $variant(..) => {
-                    self.locals[index] = value;
-                    Ok(())
-                }
+                $variant(..) => Ok(self.store_local(index, value)?),
                 _ => Err(MethodCallFailed::InternalError(
                     VmError::ValidationException,
                 )),
@@ -243,7 +288,12 @@ macro_rules! generate_execute_array_store {
             let array = self.pop_array()?;
             match array.elements_type() {
                 $($variant => {
-                     array.set_element(index, value)?
+                    // bastore is used for both byte and boolean arrays
+                    let value = match array.elements_type() {
+                        ArrayEntryType::Base(base_type) => value.narrowed_to(&base_type),
+                        _ => value,
+                    };
+                    array.set_element(index, value)?
                 })+
                 _ => return Err(MethodCallFailed::InternalError(VmError::ValidationException)),
             }
@@ -254,25 +304,21 @@ macro_rules! generate_execute_array_store {
 
 impl<'a> CallFrame<'a> {
     pub fn new(class_and_method: ClassAndMethod<'a>, locals: Vec<Value<'a>>) -> Self {
-        let max_stack_size = class_and_method
+        let code = class_and_method
             .method
             .code
             .as_ref()
-            .expect("method is not native")
-            .max_stack
-            .into_usize_safe();
-        let code = &class_and_method
-            .method
-            .code
-            .as_ref()
-            .expect("method is not native")
-            .code;
+            .expect("method is not native");
+        let max_stack_size = code.max_stack.into_usize_safe();
         CallFrame {
             class_and_method,
             pc: ProgramCounter(0),
+            current_instruction_pc: ProgramCounter(0),
             locals,
             stack: ValueStack::with_max_size(max_stack_size),
             code,
+            debug_verbosity: DebugVerbosity::Off,
+            entered_monitors: Vec::new(),
         }
     }
 
@@ -285,10 +331,15 @@ impl<'a> CallFrame<'a> {
         }
     }
 
+    /// Returns the address of the instruction being executed
+    pub fn current_pc(&self) -> ProgramCounter {
+        self.current_instruction_pc
+    }
+
     fn get_line_number(&self) -> Option<LineNumber> {
         if let Some(code) = self.class_and_method.method.code.as_ref() {
             if let Some(line_number_table) = &code.line_number_table {
-                return Some(line_number_table.lookup_pc(self.pc));
+                return Some(line_number_table.lookup_pc(self.current_instruction_pc));
             }
         }
         None
@@ -300,14 +351,26 @@ impl<'a> CallFrame<'a> {
         vm: &mut Vm<'a>,
         call_stack: &mut CallStack<'a>,
     ) -> MethodCallResult<'a> {
+        self.debug_verbosity = vm.debug_verbosity();
         self.debug_start_execution();
 
         loop {
             let executed_instruction_pc = self.pc;
-            let (instruction, new_address) =
-                Instruction::parse(self.code, executed_instruction_pc.0.into_usize_safe())
-                    .map_err(|_| MethodCallFailed::InternalError(VmError::ValidationException))?;
+            self.current_instruction_pc = executed_instruction_pc;
+            let (instruction, new_address) = self
+                .code
+                .instruction_at(executed_instruction_pc.0.into_usize_safe())
+                .map_err(|_| MethodCallFailed::InternalError(VmError::ValidationException))?;
             self.debug_print_status(&instruction);
+            if vm.has_instruction_hook() {
+                vm.notify_instruction_hook(&InstructionContext {
+                    class_name: &self.class_and_method.class.name,
+                    method_name: &self.class_and_method.method.name,
+                    pc: self.current_pc(),
+                    instruction: &instruction,
+                    depth: call_stack.depth(),
+                });
+            }
 
             // Move pc to the next instruction, _before_ executing it, since we want a "goto" to override this
             self.pc = ProgramCounter(new_address as u16);
@@ -317,11 +380,12 @@ impl<'a> CallFrame<'a> {
                 Ok(ReturnFromMethod(return_value)) => return Ok(return_value),
                 Ok(ContinueMethodExecution) => { /* continue the loop */ }
 
-                Err(MethodCallFailed::InternalError(err)) => {
-                    return Err(MethodCallFailed::InternalError(err))
+                Err(err @ (MethodCallFailed::InternalError(_) | MethodCallFailed::Exit(_))) => {
+                    return Err(err)
                 }
 
                 Err(MethodCallFailed::ExceptionThrown(exception)) => {
+                    vm.report_thrown_exception(&exception);
                     let exception_handler = self.find_exception_handler(
                         vm,
                         call_stack,
@@ -336,6 +400,7 @@ impl<'a> CallFrame<'a> {
                         }
                         Ok(Some(catch_handler_pc)) => {
                             // Re-push exception on the stack and continue execution of this method from the catch handler
+                            vm.report_caught_exception();
                             self.stack.push(Value::Object(exception.0))?;
                             self.pc = catch_handler_pc
                         }
@@ -546,7 +611,7 @@ impl<'a> CallFrame<'a> {
 
             Instruction::Iinc(index, constant) => {
                 let index = index.into_usize_safe();
-                let local = self.get_local_int_as_int(vm, index)?;
+                let local = self.get_local_int_as_int(index)?;
                 self.locals[index] = Int(local + constant as i32);
             }
 
@@ -564,14 +629,14 @@ impl<'a> CallFrame<'a> {
             Instruction::Land => self.execute_long_math(|a, b| Ok(a & b))?,
             Instruction::Lor => self.execute_long_math(|a, b| Ok(a | b))?,
             Instruction::Lxor => self.execute_long_math(|a, b| Ok(a ^ b))?,
-            Instruction::Lshr => self.execute_long_shift(|a, b| Ok(a >> (b & 0x1f)))?,
-            Instruction::Lshl => self.execute_long_shift(|a, b| Ok(a << (b & 0x1f)))?,
+            Instruction::Lshr => self.execute_long_shift(|a, b| Ok(a >> (b & 0x3f)))?,
+            Instruction::Lshl => self.execute_long_shift(|a, b| Ok(a << (b & 0x3f)))?,
             Instruction::Lushr => self.execute_long_shift(|a, b| {
                 Ok({
                     if a > 0 {
-                        a >> (b & 0x1f)
+                        a >> (b & 0x3f)
                     } else {
-                        ((a as u64) >> (b & 0x1f)) as i64
+                        ((a as u64) >> (b & 0x3f)) as i64
                     }
                 })
             })?,
@@ -588,15 +653,11 @@ impl<'a> CallFrame<'a> {
                     },
                 )
             })?,
-            Instruction::Frem => self.execute_float_math(|a, b| {
-                Ok(
-                    if Self::is_double_division_returning_nan(a as f64, b as f64) {
-                        f32::NAN
-                    } else {
-                        a % b
-                    },
-                )
-            })?,
+            // Rust's % on floats is the truncated remainder, like C's fmod, which is
+            // exactly what the JVM specifies: the result has the sign of the dividend,
+            // and it is NaN if either operand is NaN, the dividend is infinite,
+            // or the divisor is zero
+            Instruction::Frem => self.execute_float_math(|a, b| Ok(a % b))?,
 
             Instruction::Dadd => self.execute_double_math(|a, b| Ok(a + b))?,
             Instruction::Dsub => self.execute_double_math(|a, b| Ok(a - b))?,
@@ -608,20 +669,17 @@ impl<'a> CallFrame<'a> {
                     a / b
                 })
             })?,
-            Instruction::Drem => self.execute_double_math(|a, b| {
-                Ok(if Self::is_double_division_returning_nan(a, b) {
-                    f64::NAN
-                } else {
-                    a % b
-                })
-            })?,
+            Instruction::Drem => self.execute_double_math(|a, b| Ok(a % b))?,
 
             Instruction::Ineg => self.execute_ineg()?,
             Instruction::Lneg => self.execute_lneg()?,
             Instruction::Fneg => self.execute_fneg()?,
             Instruction::Dneg => self.execute_dneg()?,
 
-            Instruction::Goto(jump_address) => self.goto(jump_address),
+            Instruction::Goto(jump_address) | Instruction::Goto_w(jump_address) => {
+                self.goto(jump_address)
+            }
+            Instruction::Lookupswitch | Instruction::Tableswitch => self.execute_switch()?,
 
             Instruction::Ifeq(jump_address) => self.execute_if(jump_address, |v| v == 0)?,
             Instruction::Ifne(jump_address) => self.execute_if(jump_address, |v| v != 0)?,
@@ -653,11 +711,12 @@ impl<'a> CallFrame<'a> {
                 self.execute_if_icmp(jump_address, |a, b| a >= b)?
             }
 
-            Instruction::Lcmp => self.execute_long_compare(1)?,
-            Instruction::Fcmpg => self.execute_float_compare(-1)?,
-            Instruction::Fcmpl => self.execute_float_compare(1)?,
-            Instruction::Dcmpg => self.execute_double_compare(-1)?,
-            Instruction::Dcmpl => self.execute_double_compare(1)?,
+            // Longs are never unordered
+            Instruction::Lcmp => self.execute_long_compare(0)?,
+            Instruction::Fcmpg => self.execute_float_compare(1)?,
+            Instruction::Fcmpl => self.execute_float_compare(-1)?,
+            Instruction::Dcmpg => self.execute_double_compare(1)?,
+            Instruction::Dcmpl => self.execute_double_compare(-1)?,
 
             Instruction::Newarray(array_type) => {
                 self.execute_newarray(vm, array_type)?;
@@ -687,19 +746,16 @@ impl<'a> CallFrame<'a> {
             Instruction::Aastore => self.execute_aastore(vm)?,
 
             Instruction::Monitorenter => self.execute_monitorenter()?,
-            Instruction::Monitorexit => self.execute_monitorexit()?,
+            Instruction::Monitorexit => self.execute_monitorexit(vm, call_stack)?,
 
             Instruction::Athrow => self.execute_athrow()?,
 
             /* Unimplemented instructions:
-            Instruction::Goto_w => {}
             Instruction::Invokedynamic(_) => {}
             Instruction::Jsr(_) => {}
-            Instruction::Jsr_w => {}
-            Instruction::Lookupswitch => {}
+            Instruction::Jsr_w(_) => {}
             Instruction::Multianewarray(_, _) => {}
             Instruction::Ret(_) => {}
-            Instruction::Tableswitch => {}
             Instruction::Wide => {}
             */
             Instruction::Nop => {}
@@ -788,30 +844,51 @@ impl<'a> CallFrame<'a> {
         constant_index: u16,
         kind: InvokeKind,
     ) -> Result<(), MethodCallFailed<'a>> {
-        let method_reference = self.get_constant_method_reference(constant_index)?;
-        if method_reference.class_name.starts_with('[') && method_reference.method_name == "clone" {
-            // TODO:
-            //  Since we have NOT modelled arrays properly (i.e. we do not have a real class
-            //  to model them), we cannot lookup methods naturally. Thus we have a special case for
-            //  invoking "clone" on an array.
-            let array = self.pop()?;
-            let clone = vm.clone_array(array)?;
-            return self.push(clone);
-        }
-
-        let static_method_reference =
-            self.get_method_to_invoke_statically(vm, call_stack, method_reference, kind)?;
-        let (receiver, params, new_stack_len) =
-            self.get_method_receiver_and_params(&static_method_reference)?;
-        let class_and_method = match kind {
+        let call_site: CallSite = (self.class_and_method.method, self.current_instruction_pc.0);
+        let inline_cache = match kind {
             InvokeKind::Virtual | InvokeKind::Interface => {
-                Self::resolve_virtual_method(vm, receiver.clone(), static_method_reference)?
+                vm.inline_caches.get(&call_site).cloned()
             }
+            _ => None,
+        };
+
+        let static_method_reference = match &inline_cache {
+            Some(inline_cache) => inline_cache.static_method.clone(),
+            None => {
+                let method_reference = self.get_constant_method_reference(constant_index)?;
+                if method_reference.class_name.starts_with('[')
+                    && method_reference.method_name == "clone"
+                {
+                    // TODO:
+                    //  Since we have NOT modelled arrays properly (i.e. we do not have a real class
+                    //  to model them), we cannot lookup methods naturally. Thus we have a special case for
+                    //  invoking "clone" on an array.
+                    let array = self.pop()?;
+                    let clone = vm.clone_array(array)?;
+                    return self.push(clone);
+                }
+                self.get_method_to_invoke_statically(vm, call_stack, method_reference, kind)?
+            }
+        };
+        let (receiver, params, new_stack_len) =
+            self.get_method_receiver_and_params(vm, &static_method_reference)?;
+        let class_and_method = match kind {
+            InvokeKind::Virtual | InvokeKind::Interface => Self::resolve_virtual_method_cached(
+                vm,
+                call_site,
+                inline_cache,
+                receiver.clone(),
+                static_method_reference,
+            )?,
             _ => static_method_reference,
         };
         self.stack.truncate(new_stack_len)?;
 
-        let method_return_type = class_and_method.return_type();
+        let method_return_type = class_and_method
+            .method
+            .parsed_type_descriptor
+            .return_type
+            .as_ref();
         let result = vm.invoke(call_stack, class_and_method, receiver, params)?;
 
         Self::validate_type_opt(vm, method_return_type, &result)?;
@@ -821,6 +898,68 @@ impl<'a> CallFrame<'a> {
         Ok(())
     }
 
+    /// Resolves the field referenced by the given constant on the class of an object, reusing
+    /// the previous resolution if it was done for the same class.
+    fn resolve_instance_field(
+        &self,
+        vm: &mut Vm<'a>,
+        field_index: u16,
+        object_class_id: ClassId,
+    ) -> Result<ResolvedField<'a>, VmError> {
+        let field_site: FieldSite = (self.class_and_method.class.id, field_index);
+        if let Some(resolved_field) = vm.resolved_fields.get(&field_site) {
+            if resolved_field.class.id == object_class_id {
+                return Ok(*resolved_field);
+            }
+        }
+
+        let field_reference = self.get_constant_field_reference(field_index)?;
+        let class = vm.get_class_by_id(object_class_id)?;
+        let (index, field) = Self::get_field(class, field_reference)?;
+        let resolved_field = ResolvedField {
+            class,
+            index,
+            field,
+        };
+        vm.resolved_fields.insert(field_site, resolved_field);
+        Ok(resolved_field)
+    }
+
+    /// Resolves the static field referenced by the given constant, initializing its class
+    /// the first time.
+    fn resolve_static_field(
+        &self,
+        vm: &mut Vm<'a>,
+        call_stack: &mut CallStack<'a>,
+        field_index: u16,
+    ) -> Result<ResolvedField<'a>, MethodCallFailed<'a>> {
+        let field_site: FieldSite = (self.class_and_method.class.id, field_index);
+        if let Some(resolved_field) = vm.resolved_fields.get(&field_site) {
+            return Ok(*resolved_field);
+        }
+
+        let field_reference = self.get_constant_field_reference(field_index)?;
+        let class = vm.get_or_resolve_class(call_stack, field_reference.class_name)?;
+        let (index, field) = Self::get_field(class, field_reference)?;
+        let resolved_field = ResolvedField {
+            class,
+            index,
+            field,
+        };
+        vm.resolved_fields.insert(field_site, resolved_field);
+        Ok(resolved_field)
+    }
+
+    /// Values are validated when they are stored in a field, so for final fields, which are
+    /// only written during initialization, there is no need to check them again on reads
+    fn validate_field_value(vm: &Vm, field: &ClassFileField, value: &Value) -> Result<(), VmError> {
+        if field.flags.contains(FieldFlags::FINAL) {
+            Ok(())
+        } else {
+            Self::validate_type(vm, &field.type_descriptor, value)
+        }
+    }
+
     fn get_field(
         class: &'a Class,
         field_reference: FieldReference,
@@ -946,15 +1085,66 @@ impl<'a> CallFrame<'a> {
         kind: InvokeKind,
     ) -> Result<ClassAndMethod<'a>, MethodCallFailed<'a>> {
         let class = vm.get_or_resolve_class(call_stack, method_reference.class_name)?;
-        match kind {
-            InvokeKind::Special | InvokeKind::Static => {
+        let class_and_method = match kind {
+            InvokeKind::Static => Self::get_method_of_class(class, method_reference)
+                .map(|method| ClassAndMethod { class, method }),
+            // Constructors are not inherited, so they must be declared in the referenced class
+            InvokeKind::Special if method_reference.method_name == "<init>" => {
                 Self::get_method_of_class(class, method_reference)
                     .map(|method| ClassAndMethod { class, method })
             }
+            // For invokespecial, the method can be declared in a superclass, for example when
+            // calling `super.foo()` and the direct superclass inherits `foo`
+            InvokeKind::Special => {
+                let class =
+                    self.select_class_for_invoke_special(class, method_reference.method_name);
+                Self::get_method_checking_superclasses(class, method_reference)
+            }
             InvokeKind::Virtual | InvokeKind::Interface => {
                 Self::get_method_checking_superclasses(class, method_reference)
             }
+        }?;
+
+        // This can happen if the class was compiled against a different version of the callee
+        let expects_static = matches!(kind, InvokeKind::Static);
+        if class_and_method.is_static() != expects_static {
+            debug!(
+                "invoking method {}::{} {} with {:?}, but its static-ness does not match",
+                class_and_method.class.name,
+                class_and_method.method.name,
+                class_and_method.method.type_descriptor,
+                kind
+            );
+            let exception = new_java_exception_object(
+                vm,
+                call_stack,
+                "java/lang/IncompatibleClassChangeError",
+            )?;
+            return Err(MethodCallFailed::ExceptionThrown(JavaException(exception)));
+        }
+        Ok(class_and_method)
+    }
+
+    /// Implements the selection rule of invokespecial: if the current class has the
+    /// `ACC_SUPER` flag and the referenced class is one of its superclasses, the lookup
+    /// starts from the direct superclass of the current class rather than from the referenced
+    /// one, unless we are invoking a constructor. Thus `super.foo()` invokes the closest
+    /// override of `foo`, even when the class was compiled against an older hierarchy.
+    fn select_class_for_invoke_special(
+        &self,
+        referenced_class: ClassRef<'a>,
+        method_name: &str,
+    ) -> ClassRef<'a> {
+        let current_class = self.class_and_method.class;
+        if method_name == "<init>"
+            || !current_class.flags.contains(ClassAccessFlags::SUPER)
+            || referenced_class.flags.contains(ClassAccessFlags::INTERFACE)
+            || current_class.id == referenced_class.id
+            || !current_class.is_subclass_of(referenced_class)
+        {
+            return referenced_class;
         }
+        current_class.superclass.unwrap_or(referenced_class)
     }
 
     fn get_method_of_class<'b>(
@@ -975,6 +1165,9 @@ impl<'a> CallFrame<'a> {
             ))
     }
 
+    /// Looks up a method in the class and its superclasses. Note that interfaces have
+    /// `java/lang/Object` as superclass, so this also finds methods like `toString`
+    /// when they are invoked via `invokeinterface`.
     fn get_method_checking_superclasses<'b>(
         class: &'b Class<'a>,
         method_reference: MethodReference,
@@ -1005,10 +1198,40 @@ impl<'a> CallFrame<'a> {
         }
     }
 
-    fn resolve_virtual_method(
+    /// Resolves a virtual method, using and updating the inline cache of the call site
+    fn resolve_virtual_method_cached(
+        vm: &mut Vm<'a>,
+        call_site: CallSite,
+        inline_cache: Option<InlineCacheEntry<'a>>,
+        receiver: Option<AbstractObject<'a>>,
+        static_method: ClassAndMethod<'a>,
+    ) -> Result<ClassAndMethod<'a>, MethodCallFailed<'a>> {
+        let receiver_class_id = match &receiver {
+            Some(receiver) if receiver.kind() == ObjectKind::Object => receiver.class_id(),
+            _ => return Self::resolve_virtual_method(vm, receiver, static_method),
+        };
+        if let Some(inline_cache) = inline_cache {
+            if inline_cache.receiver_class_id == receiver_class_id {
+                return Ok(inline_cache.resolved_method);
+            }
+        }
+
+        let resolved_method = Self::resolve_virtual_method(vm, receiver, static_method.clone())?;
+        vm.inline_caches.insert(
+            call_site,
+            InlineCacheEntry {
+                static_method,
+                receiver_class_id,
+                resolved_method: resolved_method.clone(),
+            },
+        );
+        Ok(resolved_method)
+    }
+
+    pub(crate) fn resolve_virtual_method(
         vm: &Vm<'a>,
         receiver: Option<AbstractObject>,
-        class_and_method: ClassAndMethod,
+        class_and_method: ClassAndMethod<'a>,
     ) -> Result<ClassAndMethod<'a>, MethodCallFailed<'a>> {
         match receiver {
             Some(receiver) if receiver.kind() == ObjectKind::Object => {
@@ -1033,6 +1256,9 @@ impl<'a> CallFrame<'a> {
                 );
                 Ok(resolved_method)
             }
+            // Arrays do not override any method of java.lang.Object, except clone which
+            // is handled separately in invoke_method
+            Some(receiver) if receiver.kind() == ObjectKind::Array => Ok(class_and_method),
             _ => Err(MethodCallFailed::InternalError(
                 VmError::ValidationException,
             )),
@@ -1041,6 +1267,7 @@ impl<'a> CallFrame<'a> {
 
     fn get_method_receiver_and_params(
         &self,
+        vm: &Vm<'a>,
         class_and_method: &ClassAndMethod<'a>,
     ) -> Result<(Option<AbstractObject<'a>>, Vec<Value<'a>>, usize), VmError> {
         let cur_stack_len = self.stack.len();
@@ -1061,6 +1288,7 @@ impl<'a> CallFrame<'a> {
 
         // Faster than repeated pops :-)
         let mut params = Vec::from(&self.stack[cur_stack_len - num_params..cur_stack_len]);
+        vm.validate_argument_types(class_and_method, &params)?;
         Self::fix_long_and_double_params(&mut params)?;
 
         Ok((
@@ -1075,7 +1303,7 @@ impl<'a> CallFrame<'a> {
     // Since in our implementation we do not "split" the numbers in two 32-bits parts,
     // we can just add an empty slot in the variable after a long or a double. All the
     // bytecode instructions will refer to the "first" value anyway.
-    fn fix_long_and_double_params(params: &mut Vec<Value>) -> Result<(), VmError> {
+    pub(crate) fn fix_long_and_double_params(params: &mut Vec<Value>) -> Result<(), VmError> {
         let mut num_params = params.len();
         let mut i = 0usize;
         while i < num_params {
@@ -1109,7 +1337,7 @@ impl<'a> CallFrame<'a> {
 
     fn validate_type_opt(
         vm: &Vm,
-        expected_type: Option<FieldType>,
+        expected_type: Option<&FieldType>,
         value: &Option<Value<'a>>,
     ) -> Result<(), VmError> {
         match expected_type {
@@ -1124,7 +1352,7 @@ impl<'a> CallFrame<'a> {
         }
     }
 
-    fn validate_type(vm: &Vm, expected_type: FieldType, value: &Value) -> Result<(), VmError> {
+    fn validate_type(vm: &Vm, expected_type: &FieldType, value: &Value) -> Result<(), VmError> {
         if value.matches_type(expected_type, vm, |class_name| {
             vm.find_class_by_name(class_name)
         }) {
@@ -1145,16 +1373,9 @@ impl<'a> CallFrame<'a> {
     generate_execute_return!(execute_freturn, Float);
     generate_execute_return!(execute_dreturn, Double);
 
-    fn get_local_int(&self, vm: &Vm, index: usize) -> Result<Value<'a>, VmError> {
-        let variable = self.locals.get(index).ok_or(VmError::ValidationException)?;
-        Self::validate_type(vm, Base(BaseType::Int), variable)?;
-        Ok(variable.clone())
-    }
-
-    fn get_local_int_as_int(&self, vm: &Vm, index: usize) -> Result<i32, VmError> {
-        let value = self.get_local_int(vm, index)?;
-        match value {
-            Int(the_int) => Ok(the_int),
+    fn get_local_int_as_int(&self, index: usize) -> Result<i32, VmError> {
+        match self.locals.get(index) {
+            Some(Int(the_int)) => Ok(*the_int),
             _ => Err(VmError::ValidationException),
         }
     }
@@ -1195,6 +1416,16 @@ impl<'a> CallFrame<'a> {
         self.pc = ProgramCounter(jump_address);
     }
 
+    fn execute_switch(&mut self) -> Result<(), MethodCallFailed<'a>> {
+        let key = self.pop_int()?;
+        let table = self
+            .code
+            .switch_table_at(self.current_instruction_pc.0.into_usize_safe())
+            .map_err(|_| MethodCallFailed::InternalError(VmError::ValidationException))?;
+        self.goto(table.target(key));
+        Ok(())
+    }
+
     fn execute_if<T>(
         &mut self,
         jump_address: u16,
@@ -1295,9 +1526,9 @@ impl<'a> CallFrame<'a> {
     generate_compare!(execute_double_compare, pop_double);
 
     fn execute_aload(&mut self, index: usize) -> Result<(), MethodCallFailed<'a>> {
-        let local = self.locals.get(index).ok_or(VmError::ValidationException)?;
-        match local {
-            Value::Object(..) | Null => self.push(local.clone()),
+        match self.locals.get(index) {
+            Some(Value::Object(object)) => self.push(Value::Object(object.clone())),
+            Some(Null) => self.push(Null),
             _ => Err(MethodCallFailed::InternalError(
                 VmError::ValidationException,
             )),
@@ -1312,20 +1543,37 @@ impl<'a> CallFrame<'a> {
     fn execute_astore(&mut self, index: usize) -> Result<(), MethodCallFailed<'a>> {
         let value = self.pop()?;
         match value {
-            Value::Object(..) => {
-                self.locals[index] = value;
-                Ok(())
-            }
-            Null => {
-                self.locals[index] = value;
-                Ok(())
-            }
+            Value::Object(..) | Null => Ok(self.store_local(index, value)?),
             _ => Err(MethodCallFailed::InternalError(
                 VmError::ValidationException,
             )),
         }
     }
 
+    /// Stores a value in a local variable. Like for the method arguments (see
+    /// [fix_long_and_double_params]), longs and doubles take two slots, so the
+    /// second one does not hold a valid value anymore. Conversely, overwriting the
+    /// second slot of a long or double invalidates it, so that it cannot be loaded.
+    fn store_local(&mut self, index: usize, value: Value<'a>) -> Result<(), VmError> {
+        if let Some(previous) = index.checked_sub(1).and_then(|i| self.locals.get_mut(i)) {
+            if matches!(previous, Long(_) | Double(_)) {
+                *previous = Value::Uninitialized;
+            }
+        }
+        let takes_two_slots = matches!(value, Long(_) | Double(_));
+        *self
+            .locals
+            .get_mut(index)
+            .ok_or(VmError::ValidationException)? = value;
+        if takes_two_slots {
+            *self
+                .locals
+                .get_mut(index + 1)
+                .ok_or(VmError::ValidationException)? = Value::Uninitialized;
+        }
+        Ok(())
+    }
+
     generate_execute_store!(execute_istore, Int);
     generate_execute_store!(execute_lstore, Long);
     generate_execute_store!(execute_fstore, Float);
@@ -1345,7 +1593,7 @@ impl<'a> CallFrame<'a> {
                 let constant = self.get_constant(*string_index)?;
                 match constant {
                     ConstantPoolEntry::Utf8(string) => {
-                        let string_object = new_java_lang_string_object(vm, call_stack, string)?;
+                        let string_object = vm.get_or_create_interned_string(call_stack, string)?;
                         self.push(Value::Object(string_object))
                     }
                     _ => Err(MethodCallFailed::InternalError(
@@ -1400,7 +1648,7 @@ impl<'a> CallFrame<'a> {
             NewArrayType::Long => ArrayEntryType::Base(BaseType::Long),
         };
 
-        let array = vm.new_array(elements_type, length);
+        let array = vm.new_array(elements_type, length)?;
         self.push(Value::Object(array))
     }
 
@@ -1412,13 +1660,37 @@ impl<'a> CallFrame<'a> {
     ) -> Result<(), MethodCallFailed<'a>> {
         let length = self.pop_int()?.into_usize_safe();
         let class_name = self.get_constant_class_reference(constant_index)?;
-        let class = vm.get_or_resolve_class(call_stack, class_name)?;
-        let elements_type = ArrayEntryType::Object(class.id);
+        let elements_type = if class_name.starts_with('[') {
+            let field_type =
+                FieldType::parse(class_name).map_err(|_| VmError::ValidationException)?;
+            Self::array_entry_type_of(vm, call_stack, &field_type)?
+        } else {
+            let class = vm.get_or_resolve_class(call_stack, class_name)?;
+            ArrayEntryType::Object(class.id)
+        };
 
-        let array = vm.new_array(elements_type, length);
+        let array = vm.new_array(elements_type, length)?;
         self.push(Value::Object(array))
     }
 
+    /// Returns the type of the array entries that can hold values of the given type,
+    /// resolving the classes that it refers to
+    fn array_entry_type_of(
+        vm: &mut Vm<'a>,
+        call_stack: &mut CallStack<'a>,
+        field_type: &FieldType,
+    ) -> Result<ArrayEntryType, MethodCallFailed<'a>> {
+        Ok(match field_type {
+            Base(base_type) => ArrayEntryType::Base(base_type.clone()),
+            FieldType::Object(class_name) => {
+                ArrayEntryType::Object(vm.get_or_resolve_class(call_stack, class_name)?.id)
+            }
+            FieldType::Array(component_type) => {
+                ArrayEntryType::array_of(Self::array_entry_type_of(vm, call_stack, component_type)?)
+            }
+        })
+    }
+
     fn execute_array_length(&mut self) -> Result<(), MethodCallFailed<'a>> {
         let array = self.pop_array()?;
         let len = array.len() as i32;
@@ -1437,7 +1709,11 @@ impl<'a> CallFrame<'a> {
     generate_execute_array_load!(execute_laload, ArrayEntryType::Base(BaseType::Long));
     generate_execute_array_load!(execute_faload, ArrayEntryType::Base(BaseType::Float));
     generate_execute_array_load!(execute_daload, ArrayEntryType::Base(BaseType::Double));
-    generate_execute_array_load!(execute_aaload, ArrayEntryType::Object(..));
+    generate_execute_array_load!(
+        execute_aaload,
+        ArrayEntryType::Object(..),
+        ArrayEntryType::Array(..)
+    );
 
     generate_execute_array_store!(
         execute_bastore,
@@ -1492,11 +1768,18 @@ impl<'a> CallFrame<'a> {
                 let elements_class_name = vm.get_class_by_id(elements_class_id)?;
                 Self::validate_type(
                     vm,
-                    FieldType::Object(elements_class_name.name.clone()),
+                    &FieldType::Object(elements_class_name.name.clone()),
                     &value,
                 )?;
                 array.set_element(index, value)?
             }
+            elements_type @ ArrayEntryType::Array(..) => {
+                let elements_field_type = elements_type
+                    .into_field_type(vm)
+                    .ok_or(VmError::ValidationException)?;
+                Self::validate_type(vm, &elements_field_type, &value)?;
+                array.set_element(index, value)?
+            }
             _ => {
                 return Err(MethodCallFailed::InternalError(
                     VmError::ValidationException,
@@ -1541,40 +1824,33 @@ impl<'a> CallFrame<'a> {
         value: &Value<'a>,
     ) -> Result<bool, MethodCallFailed<'a>> {
         let class_name = self.get_constant_class_reference(constant_index)?;
-
-        // TODO: we should model classes of arrays
-        // TODO: multidimensional arrays are not supported!
-        let (is_array, expected_class) = {
-            if class_name.starts_with("[L") && class_name.ends_with(';') {
-                (
-                    true,
-                    vm.get_or_resolve_class(call_stack, &class_name[2..class_name.len() - 1])?,
-                )
-            } else {
-                (false, vm.get_or_resolve_class(call_stack, class_name)?)
-            }
-        };
-
         let is_instance_of = match &value {
             Null => false,
 
             Value::Object(object) => match object.kind() {
                 ObjectKind::Object => {
-                    if is_array {
+                    if class_name.starts_with('[') {
                         false
                     } else {
+                        let expected_class =
+                            self.resolve_class_reference(vm, call_stack, constant_index)?;
                         let object_class = vm.get_class_by_id(object.class_id())?;
                         object_class.is_subclass_of(expected_class)
                     }
                 }
-                ObjectKind::Array => match object.elements_type() {
-                    ArrayEntryType::Base(_) => false,
-                    ArrayEntryType::Object(elements_class_id) => {
-                        let components_class = vm.get_class_by_id(elements_class_id)?;
-                        components_class.is_subclass_of(expected_class)
-                    }
-                    ArrayEntryType::Array => false,
-                },
+                ObjectKind::Array => {
+                    let expected_type = if class_name.starts_with('[') {
+                        FieldType::parse(class_name).map_err(|_| VmError::ValidationException)?
+                    } else {
+                        FieldType::Object(class_name.to_string())
+                    };
+                    Self::is_array_instance_of(
+                        vm,
+                        call_stack,
+                        &object.elements_type(),
+                        &expected_type,
+                    )?
+                }
             },
 
             _ => {
@@ -1586,6 +1862,78 @@ impl<'a> CallFrame<'a> {
         Ok(is_instance_of)
     }
 
+    /// Resolves the class referenced by the given constant. Since a loaded class never
+    /// changes, the result is cached to skip the lookup by name the next time.
+    fn resolve_class_reference(
+        &self,
+        vm: &mut Vm<'a>,
+        call_stack: &mut CallStack<'a>,
+        constant_index: u16,
+    ) -> Result<ClassRef<'a>, MethodCallFailed<'a>> {
+        let site: ClassReferenceSite = (self.class_and_method.class.id, constant_index);
+        if let Some(class) = vm.resolved_classes.get(&site) {
+            return Ok(*class);
+        }
+
+        let class_name = self.get_constant_class_reference(constant_index)?;
+        let class = vm.get_or_resolve_class(call_stack, class_name)?;
+        vm.class_reference_lookups += 1;
+        vm.resolved_classes.insert(site, class);
+        Ok(class)
+    }
+
+    /// Checks whether an array with the given elements type is an instance of the expected type.
+    /// Arrays are instances of `Object`, `Cloneable` and `Serializable`, and of `T[]` when their
+    /// component type is assignable to `T`.
+    fn is_array_instance_of(
+        vm: &mut Vm<'a>,
+        call_stack: &mut CallStack<'a>,
+        elements_type: &ArrayEntryType,
+        expected_type: &FieldType,
+    ) -> Result<bool, MethodCallFailed<'a>> {
+        let expected_component_type = match expected_type {
+            FieldType::Array(expected_component_type) => expected_component_type,
+            FieldType::Object(expected_class_name) => {
+                return Ok(is_implemented_by_arrays(expected_class_name))
+            }
+            FieldType::Base(_) => return Ok(false),
+        };
+
+        Ok(match (elements_type, expected_component_type.as_ref()) {
+            (ArrayEntryType::Base(base_type), FieldType::Base(expected_base_type)) => {
+                base_type == expected_base_type
+            }
+            (ArrayEntryType::Object(elements_class_id), FieldType::Object(expected_class_name)) => {
+                let expected_class = vm.get_or_resolve_class(call_stack, expected_class_name)?;
+                let components_class = vm.get_class_by_id(*elements_class_id)?;
+                components_class.is_subclass_of(expected_class)
+            }
+            // The entries are arrays themselves, so we check them recursively
+            (ArrayEntryType::Array(..), FieldType::Array(_)) => {
+                match elements_type.entries_elements_type() {
+                    Some(entries_elements_type) => Self::is_array_instance_of(
+                        vm,
+                        call_stack,
+                        &entries_elements_type,
+                        expected_component_type,
+                    )?,
+                    None => false,
+                }
+            }
+            (ArrayEntryType::Array(..), FieldType::Object(expected_class_name)) => {
+                is_implemented_by_arrays(expected_class_name)
+            }
+            _ => false,
+        })
+    }
+
+    fn narrowed_to_field_type(field: &ClassFileField, value: Value<'a>) -> Value<'a> {
+        match &field.type_descriptor {
+            Base(base_type) => value.narrowed_to(base_type),
+            _ => value,
+        }
+    }
+
     fn execute_getfield(
         &mut self,
         vm: &mut Vm<'a>,
@@ -1594,11 +1942,10 @@ impl<'a> CallFrame<'a> {
         let object = self.pop()?;
         if let Value::Object(object_ref) = object {
             if object_ref.kind() == ObjectKind::Object {
-                let field_reference = self.get_constant_field_reference(field_index)?;
-                let object_class = vm.get_class_by_id(object_ref.class_id())?;
-                let (index, field) = Self::get_field(object_class, field_reference)?;
-                let field_value = object_ref.get_field(object_class, index);
-                Self::validate_type(vm, field.type_descriptor.clone(), &field_value)?;
+                let resolved_field =
+                    self.resolve_instance_field(vm, field_index, object_ref.class_id())?;
+                let field_value = object_ref.get_field(resolved_field.class, resolved_field.index);
+                Self::validate_field_value(vm, resolved_field.field, &field_value)?;
                 self.push(field_value)?;
                 return Ok(());
             }
@@ -1617,11 +1964,11 @@ impl<'a> CallFrame<'a> {
         let object = self.pop()?;
         if let Value::Object(object_ref) = object {
             if object_ref.kind() == ObjectKind::Object {
-                let field_reference = self.get_constant_field_reference(field_index)?;
-                let object_class = vm.get_class_by_id(object_ref.class_id())?;
-                let (index, field) = Self::get_field(object_class, field_reference)?;
-                Self::validate_type(vm, field.type_descriptor.clone(), &value)?;
-                object_ref.set_field(index, value);
+                let resolved_field =
+                    self.resolve_instance_field(vm, field_index, object_ref.class_id())?;
+                Self::validate_type(vm, &resolved_field.field.type_descriptor, &value)?;
+                let value = Self::narrowed_to_field_type(resolved_field.field, value);
+                object_ref.set_field(resolved_field.index, value);
                 return Ok(());
             }
         }
@@ -1636,14 +1983,12 @@ impl<'a> CallFrame<'a> {
         call_stack: &mut CallStack<'a>,
         field_index: u16,
     ) -> Result<(), MethodCallFailed<'a>> {
-        let field_reference = self.get_constant_field_reference(field_index)?;
-        let object_class = vm.get_or_resolve_class(call_stack, field_reference.class_name)?;
-        let (index, field) = Self::get_field(object_class, field_reference)?;
-        let object = vm.get_static_instance(self.class_and_method.class.id);
+        let resolved_field = self.resolve_static_field(vm, call_stack, field_index)?;
+        let object = vm.get_static_instance(resolved_field.class.id);
         if let Some(object_ref) = object {
             if object_ref.kind() == ObjectKind::Object {
-                let field_value = object_ref.get_field(object_class, index);
-                Self::validate_type(vm, field.type_descriptor.clone(), &field_value)?;
+                let field_value = object_ref.get_field(resolved_field.class, resolved_field.index);
+                Self::validate_field_value(vm, resolved_field.field, &field_value)?;
                 self.push(field_value)?;
                 return Ok(());
             }
@@ -1659,15 +2004,14 @@ impl<'a> CallFrame<'a> {
         call_stack: &mut CallStack<'a>,
         field_index: u16,
     ) -> Result<(), MethodCallFailed<'a>> {
-        let field_reference = self.get_constant_field_reference(field_index)?;
-        let object_class = vm.get_or_resolve_class(call_stack, field_reference.class_name)?;
-        let (index, field) = Self::get_field(object_class, field_reference)?;
+        let resolved_field = self.resolve_static_field(vm, call_stack, field_index)?;
         let value = self.pop()?;
-        Self::validate_type(vm, field.type_descriptor.clone(), &value)?;
-        let object = vm.get_static_instance(self.class_and_method.class.id);
+        Self::validate_type(vm, &resolved_field.field.type_descriptor, &value)?;
+        let value = Self::narrowed_to_field_type(resolved_field.field, value);
+        let object = vm.get_static_instance(resolved_field.class.id);
         if let Some(object_ref) = object {
             if object_ref.kind() == ObjectKind::Object {
-                object_ref.set_field(index, value);
+                object_ref.set_field(resolved_field.index, value);
                 return Ok(());
             }
         }
@@ -1679,9 +2023,11 @@ impl<'a> CallFrame<'a> {
     fn execute_monitorenter(&mut self) -> Result<(), MethodCallFailed<'a>> {
         let obj = self.pop()?;
         match obj {
-            Value::Object(_) => {
+            Value::Object(object) => {
                 // We don't really have monitors or lock, since we are single-threaded,
-                // so any monitor access will succeed!
+                // so any monitor access will succeed! We only track them to check that
+                // they are exited in the right order.
+                self.entered_monitors.push(object);
                 Ok(())
             }
             _ => Err(MethodCallFailed::InternalError(
@@ -1690,13 +2036,25 @@ impl<'a> CallFrame<'a> {
         }
     }
 
-    fn execute_monitorexit(&mut self) -> Result<(), MethodCallFailed<'a>> {
+    fn execute_monitorexit(
+        &mut self,
+        vm: &mut Vm<'a>,
+        call_stack: &mut CallStack<'a>,
+    ) -> Result<(), MethodCallFailed<'a>> {
         let obj = self.pop()?;
         match obj {
-            Value::Object(_) => {
-                // We don't really have monitors or lock, since we are single-threaded,
-                // so any monitor access will succeed!
-                Ok(())
+            Value::Object(object) => {
+                if self.entered_monitors.last() == Some(&object) {
+                    self.entered_monitors.pop();
+                    Ok(())
+                } else {
+                    let exception = new_java_exception_object(
+                        vm,
+                        call_stack,
+                        "java/lang/IllegalMonitorStateException",
+                    )?;
+                    Err(MethodCallFailed::ExceptionThrown(JavaException(exception)))
+                }
             }
             _ => Err(MethodCallFailed::InternalError(
                 VmError::ValidationException,
@@ -1752,6 +2110,9 @@ impl<'a> CallFrame<'a> {
     }
 
     fn debug_start_execution(&self) {
+        if self.debug_verbosity < DebugVerbosity::FrameEntry {
+            return;
+        }
         debug!(
             "starting execution of method {}::{} - locals are {:?}",
             self.class_and_method.class.name, self.class_and_method.method.name, self.locals
@@ -1759,12 +2120,19 @@ impl<'a> CallFrame<'a> {
     }
 
     fn debug_print_status(&self, instruction: &Instruction) {
+        if self.debug_verbosity < DebugVerbosity::Instruction {
+            return;
+        }
         debug!(
             "FRAME STATUS: executing {} signature {} pc: {}",
             self.to_stack_trace_element(),
             self.class_and_method.method.type_descriptor,
             self.pc
         );
+        if self.debug_verbosity < DebugVerbosity::Full {
+            debug!("  next instruction: {:?}", instruction);
+            return;
+        }
         debug!("  stack:");
         for stack_entry in self.stack.iter() {
             debug!("  - {:?}", stack_entry);
@@ -1777,12 +2145,29 @@ impl<'a> CallFrame<'a> {
     }
 
     fn debug_done_execution(&self, result: Option<&Value>) {
+        if self.debug_verbosity < DebugVerbosity::FrameEntry {
+            return;
+        }
         debug!(
             "completed execution of method {}::{} - result is {:?}",
             self.class_and_method.class.name, self.class_and_method.method.name, result
-        )
+        );
+        // This is valid per the JVM specs: the values are discarded along with the frame,
+        // but it is unusual for code generated by javac, so it is worth logging
+        let leftover_values = self.stack.len();
+        if leftover_values > 0 {
+            debug!(
+                "discarding {} values left on the stack of method {}::{}",
+                leftover_values,
+                self.class_and_method.class.name,
+                self.class_and_method.method.name
+            )
+        }
     }
 
+    /// Returns the objects referenced by this frame, which must be kept alive by the gc:
+    /// both the local variables and the operand stack, since a collection can happen in the
+    /// middle of an expression, for example while evaluating the arguments of a constructor.
     pub fn gc_roots(&mut self) -> impl Iterator<Item = *mut AbstractObject<'a>> {
         let mut roots = vec![];
         roots.extend(self.stack.iter_mut().filter_map(|v| match v {
@@ -1793,6 +2178,11 @@ impl<'a> CallFrame<'a> {
             Value::Object(o) => Some(o as *mut AbstractObject),
             _ => None,
         }));
+        roots.extend(
+            self.entered_monitors
+                .iter_mut()
+                .map(|o| o as *mut AbstractObject),
+        );
         roots.into_iter()
     }
 }
@@ -108,6 +108,24 @@ impl<'a> CallStack<'a> {
         locals
     }
 
+    /// Returns whether there are no frames, i.e. no method is being executed
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Returns the number of frames on the stack
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Frees all the call frames ever allocated by this stack, so that it can be reused.
+    /// Must only be invoked when no method is executing, since it invalidates all the
+    /// [CallFrameReference].
+    pub(crate) fn reset(&mut self) {
+        self.frames.clear();
+        self.allocator = Arena::new();
+    }
+
     pub fn pop_frame(&mut self) -> Result<(), VmError> {
         self.frames
             .pop()
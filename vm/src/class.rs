@@ -1,8 +1,9 @@
-use std::{fmt, fmt::Formatter};
+use std::{collections::BTreeMap, fmt, fmt::Formatter};
 
 use rjvm_reader::{
     class_access_flags::ClassAccessFlags, class_file_field::ClassFileField,
-    class_file_method::ClassFileMethod, constant_pool::ConstantPool,
+    class_file_method::ClassFileMethod, class_file_version::ClassFileVersion,
+    constant_pool::ConstantPool,
 };
 
 /// In various data structures, we store the class id of the object, i..e. a progressive
@@ -37,6 +38,7 @@ pub struct Class<'a> {
     /// Source file is stored as an attribute in the .class file, but might be missing
     /// for synthetic classes or if the compiler didn't write it.
     pub source_file: Option<String>,
+    pub version: ClassFileVersion,
     pub constants: ConstantPool,
     pub flags: ClassAccessFlags,
     pub superclass: Option<ClassRef<'a>>,
@@ -49,6 +51,12 @@ pub struct Class<'a> {
     pub first_field_index: usize,
     // The total number of fields in this class, including those in the base class.
     pub num_total_fields: usize,
+    // Indexes in [methods] of the methods with the given name; overloads are few, so we
+    // can then search linearly by descriptor. Used to avoid a linear search of all the
+    // methods in [find_method], which is executed very often.
+    pub(crate) methods_index: BTreeMap<String, Vec<usize>>,
+    // Index in [fields] of each field declared in this class, keyed by name.
+    pub(crate) fields_index: BTreeMap<String, usize>,
 }
 
 pub type ClassRef<'a> = &'a Class<'a>;
@@ -64,31 +72,45 @@ impl<'a> Class<'a> {
             || self.interfaces.iter().any(|intf| intf.is_subclass_of(base))
     }
 
+    /// Builds the value for [methods_index]
+    pub(crate) fn index_methods(methods: &[ClassFileMethod]) -> BTreeMap<String, Vec<usize>> {
+        let mut result: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (index, method) in methods.iter().enumerate() {
+            result.entry(method.name.clone()).or_default().push(index);
+        }
+        result
+    }
+
+    /// Builds the value for [fields_index]
+    pub(crate) fn index_fields(fields: &[ClassFileField]) -> BTreeMap<String, usize> {
+        // Iterate in reverse, so that the first field wins in the (invalid) case of duplicates
+        fields
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, field)| (field.name.clone(), index))
+            .collect()
+    }
+
     pub fn find_method(
         &self,
         method_name: &str,
         type_descriptor: &str,
     ) -> Option<&ClassFileMethod> {
-        // Maybe replace linear search with something faster...
-        self.methods
+        self.methods_index
+            .get(method_name)?
             .iter()
-            .find(|method| method.name == method_name && method.type_descriptor == type_descriptor)
+            .map(|index| &self.methods[*index])
+            .find(|method| method.type_descriptor == type_descriptor)
     }
 
     pub fn find_field(&self, field_name: &str) -> Option<(usize, &ClassFileField)> {
-        // Maybe replace linear search with something faster...
-        self.fields
-            .iter()
-            .enumerate()
-            .find(|entry| entry.1.name == field_name)
-            .map(|(index, field)| (index + self.first_field_index, field))
-            .or_else(|| {
-                if let Some(superclass) = &self.superclass {
-                    superclass.find_field(field_name)
-                } else {
-                    None
-                }
-            })
+        match self.fields_index.get(field_name) {
+            Some(index) => Some((index + self.first_field_index, &self.fields[*index])),
+            None => self
+                .superclass
+                .and_then(|superclass| superclass.find_field(field_name)),
+        }
     }
 
     pub fn field_at_index(&self, index: usize) -> Option<&ClassFileField> {
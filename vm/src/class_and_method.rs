@@ -26,6 +26,10 @@ impl<'a> ClassAndMethod<'a> {
         self.method.is_native()
     }
 
+    pub fn is_abstract(&self) -> bool {
+        self.method.is_abstract()
+    }
+
     pub fn is_void(&self) -> bool {
         self.method.is_void()
     }
@@ -4,13 +4,17 @@ use indexmap::IndexMap;
 use log::debug;
 use typed_arena::Arena;
 
-use rjvm_reader::{class_file::ClassFile, class_reader};
+use rjvm_reader::{
+    class_file::ClassFile,
+    class_reader::{self, ClassReaderLimits},
+};
 
 use crate::{
     class::{Class, ClassId, ClassRef},
     class_loader::ClassLoader,
     class_path::{ClassPath, ClassPathParseError},
     class_resolver_by_id::ClassByIdResolver,
+    file_system_class_path_entry::ClassFileNameMapping,
     vm_error::VmError,
 };
 
@@ -29,6 +33,13 @@ pub(crate) struct ClassManager<'a> {
     /// In a real implementation, we would have a current class loader for each thread,
     /// in a hierarchy. Currently, we only have exactly ONE global class loader.
     current_class_loader: ClassLoader<'a>,
+
+    /// Names of the classes loaded since the last call to [take_newly_loaded_classes],
+    /// along with the class path entry they were loaded from
+    newly_loaded_classes: Vec<(String, String)>,
+
+    /// Limits enforced when reading class files
+    class_reader_limits: ClassReaderLimits,
 }
 
 impl<'a> Default for ClassManager<'a> {
@@ -40,6 +51,8 @@ impl<'a> Default for ClassManager<'a> {
             arena: Arena::with_capacity(100),
             next_id: 1,
             current_class_loader: Default::default(),
+            newly_loaded_classes: Default::default(),
+            class_reader_limits: Default::default(),
         }
     }
 }
@@ -87,10 +100,38 @@ impl<'a> ClassManager<'a> {
         self.class_path.push(class_path)
     }
 
+    pub fn append_class_path_directory(
+        &mut self,
+        path: &str,
+        file_name_mapping: ClassFileNameMapping,
+    ) -> Result<(), ClassPathParseError> {
+        self.class_path
+            .push_directory_with_file_name_mapping(path, file_name_mapping)
+    }
+
+    pub fn set_class_reader_limits(&mut self, limits: ClassReaderLimits) {
+        self.class_reader_limits = limits;
+    }
+
     pub fn find_class_by_name(&self, class_name: &str) -> Option<ClassRef<'a>> {
         self.classes_by_name.get(class_name).cloned()
     }
 
+    /// Returns the ids of all the classes loaded so far
+    pub fn loaded_class_ids(&self) -> impl Iterator<Item = ClassId> + '_ {
+        self.classes_by_id.keys().copied()
+    }
+
+    /// Forgets all the loaded classes, so that they will be loaded again from the class path
+    /// when requested, with new ids. Their memory is not released, since the arena is only
+    /// dropped along with the manager, so any [ClassRef] still around stays valid.
+    pub fn unload_all_classes(&mut self) {
+        self.classes_by_id.clear();
+        self.classes_by_name.clear();
+        self.current_class_loader = Default::default();
+        self.newly_loaded_classes.clear();
+    }
+
     pub fn get_or_resolve_class(&mut self, class_name: &str) -> Result<ResolvedClass<'a>, VmError> {
         if let Some(already_loaded_class) = self.find_class_by_name(class_name) {
             Ok(ResolvedClass::AlreadyLoaded(already_loaded_class))
@@ -104,14 +145,24 @@ impl<'a> ClassManager<'a> {
         &mut self,
         class_name: &str,
     ) -> Result<ClassesToInitialize<'a>, VmError> {
-        let class_file_bytes = self
+        let (class_file_bytes, source) = self
             .class_path
             .resolve(class_name)
             .map_err(|err| VmError::ClassLoadingError(err.to_string()))?
             .ok_or(VmError::ClassNotFoundException(class_name.to_string()))?;
-        let class_file = class_reader::read_buffer(&class_file_bytes)
-            .map_err(|err| VmError::ClassLoadingError(err.to_string()))?;
-        self.load_class(class_file)
+        let source = source.to_string();
+        let class_file =
+            class_reader::read_buffer_with_limits(&class_file_bytes, &self.class_reader_limits)
+                .map_err(|err| VmError::ClassLoadingError(err.to_string()))?;
+        let loaded_class = self.load_class(class_file)?;
+        self.newly_loaded_classes
+            .push((loaded_class.resolved_class.name.clone(), source));
+        Ok(loaded_class)
+    }
+
+    /// Returns the name and source of all the classes loaded since the last call
+    pub fn take_newly_loaded_classes(&mut self) -> Vec<(String, String)> {
+        std::mem::take(&mut self.newly_loaded_classes)
     }
 
     fn load_class(&mut self, class_file: ClassFile) -> Result<ClassesToInitialize<'a>, VmError> {
@@ -218,10 +269,13 @@ impl<'a> ClassManager<'a> {
             id,
             name: class_file.name,
             source_file: class_file.source_file,
+            version: class_file.version,
             constants: class_file.constants,
             flags: class_file.flags,
             superclass,
             interfaces,
+            methods_index: Class::index_methods(&class_file.methods),
+            fields_index: Class::index_fields(&class_file.fields),
             fields: class_file.fields,
             methods: class_file.methods,
             num_total_fields: num_superclass_fields + num_this_class_fields,
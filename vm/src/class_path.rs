@@ -3,15 +3,15 @@ use thiserror::Error;
 
 use crate::{
     class_path_entry::{ClassLoadingError, ClassPathEntry},
-    file_system_class_path_entry::FileSystemClassPathEntry,
+    file_system_class_path_entry::{ClassFileNameMapping, FileSystemClassPathEntry},
     jar_file_class_path_entry::JarFileClassPathEntry,
 };
 
-/// Models a class path, i.e. a list of [ClassPathEntry]
+/// Models a class path, i.e. a list of [ClassPathEntry], each with the path it was parsed from
 #[allow(dead_code)]
 #[derive(Default, Debug)]
 pub struct ClassPath {
-    entries: Vec<Box<dyn ClassPathEntry>>,
+    entries: Vec<(String, Box<dyn ClassPathEntry>)>,
 }
 
 /// Error that models the fact that a class path entry was not valid
@@ -19,22 +19,39 @@ pub struct ClassPath {
 pub enum ClassPathParseError {
     #[error("invalid classpath entry: {0}")]
     InvalidEntry(String),
+    #[error("cannot find the Java runtime at {0}; set the RJVM_RUNTIME_JAR environment variable to the path of a rt.jar")]
+    RuntimeNotFound(String),
+    #[error("the Java runtime is not configured; set the RJVM_RUNTIME_JAR environment variable to the path of a rt.jar")]
+    RuntimeNotConfigured,
 }
 
 impl ClassPath {
     /// Parses and adds class path entries.
     /// These should be separated by a colon (:), just like in a real JVM.
     pub fn push(&mut self, string: &str) -> Result<(), ClassPathParseError> {
-        let mut entries_to_add: Vec<Box<dyn ClassPathEntry>> = Vec::new();
+        let mut entries_to_add: Vec<(String, Box<dyn ClassPathEntry>)> = Vec::new();
         for entry in string.split(':') {
             debug!("trying to parse class path entry {}", entry);
             let parsed_entry = Self::try_parse_entry(entry)?;
-            entries_to_add.push(parsed_entry);
+            entries_to_add.push((entry.to_string(), parsed_entry));
         }
         self.entries.append(&mut entries_to_add);
         Ok(())
     }
 
+    /// Adds a directory entry, whose class files are found using the given mapping
+    /// rather than the standard layout
+    pub fn push_directory_with_file_name_mapping(
+        &mut self,
+        path: &str,
+        file_name_mapping: ClassFileNameMapping,
+    ) -> Result<(), ClassPathParseError> {
+        let entry = FileSystemClassPathEntry::with_file_name_mapping(path, file_name_mapping)
+            .map_err(|_| ClassPathParseError::InvalidEntry(path.to_string()))?;
+        self.entries.push((path.to_string(), Box::new(entry)));
+        Ok(())
+    }
+
     fn try_parse_entry(path: &str) -> Result<Box<dyn ClassPathEntry>, ClassPathParseError> {
         Self::try_parse_entry_as_jar(path).or_else(|_| Self::try_parse_entry_as_directory(path))
     }
@@ -53,14 +70,15 @@ impl ClassPath {
         Ok(Box::new(entry))
     }
 
-    /// Attempts to resolve a class from the various entries.
+    /// Attempts to resolve a class from the various entries, returning its bytes
+    /// and the path of the entry where it was found.
     /// Stops at the first entry that has a match or an error.
-    pub fn resolve(&self, class_name: &str) -> Result<Option<Vec<u8>>, ClassLoadingError> {
-        for entry in self.entries.iter() {
+    pub fn resolve(&self, class_name: &str) -> Result<Option<(Vec<u8>, &str)>, ClassLoadingError> {
+        for (path, entry) in self.entries.iter() {
             debug!("looking up class {} in {:?}", class_name, entry);
             let entry_result = entry.resolve(class_name)?;
             if let Some(class_bytes) = entry_result {
-                return Ok(Some(class_bytes));
+                return Ok(Some((class_bytes, path)));
             }
         }
         Ok(None)
@@ -86,7 +104,7 @@ mod tests {
     }
 
     fn assert_can_find_class(class_path: &ClassPath, class_name: &str) {
-        let buf = class_path
+        let (buf, _) = class_path
             .resolve(class_name)
             .expect("should not have had any errors")
             .expect("should have been able to find file");
@@ -1,10 +1,16 @@
-use crate::{abstract_object::AbstractObject, value_stack::ValueStackError, vm_error::VmError};
+use crate::{
+    abstract_object::AbstractObject, java_objects_creation::extract_str_from_java_lang_string,
+    object::Object, stack_trace_element::StackTraceElement, value::Value,
+    value_stack::ValueStackError, vm::Vm, vm_error::VmError,
+};
 
 /// Models the fact that a method execution has failed
 #[derive(Debug, PartialEq)]
 pub enum MethodCallFailed<'a> {
     InternalError(VmError),
     ExceptionThrown(JavaException<'a>),
+    /// The program has requested to terminate the vm, via `System.exit`, with the given status
+    Exit(i32),
 }
 
 impl<'a> From<VmError> for MethodCallFailed<'a> {
@@ -23,3 +29,112 @@ impl<'a> From<ValueStackError> for MethodCallFailed<'a> {
 /// Newtype that wraps a java exception
 #[derive(Debug, PartialEq)]
 pub struct JavaException<'a>(pub AbstractObject<'a>);
+
+impl<'a> JavaException<'a> {
+    /// Returns the message of the exception, i.e. the `detailMessage` field of `Throwable`
+    pub fn message(&self, vm: &Vm<'a>) -> Result<Option<String>, VmError> {
+        match self.throwable_field(vm, "detailMessage")? {
+            Value::Null => Ok(None),
+            Value::Object(message) => extract_str_from_java_lang_string(vm, &message).map(Some),
+            _ => Err(VmError::ValidationException),
+        }
+    }
+
+    /// Returns the cause of the exception, like `Throwable.getCause` does. The `cause` field
+    /// contains the exception itself until a cause has been set.
+    pub fn cause(&self, vm: &Vm<'a>) -> Result<Option<JavaException<'a>>, VmError> {
+        match self.throwable_field(vm, "cause")? {
+            Value::Null => Ok(None),
+            Value::Object(cause) if cause == self.0 => Ok(None),
+            Value::Object(cause) => Ok(Some(JavaException(cause))),
+            _ => Err(VmError::ValidationException),
+        }
+    }
+
+    fn throwable_field(&self, vm: &Vm<'a>, field_name: &str) -> Result<Value<'a>, VmError> {
+        let class = vm.get_class_by_id(self.0.class_id())?;
+        let (index, _) = class
+            .find_field(field_name)
+            .ok_or(VmError::FieldNotFoundException(
+                class.name.clone(),
+                field_name.to_string(),
+            ))?;
+        Ok(self.0.get_field(class, index))
+    }
+
+    /// Returns the stack trace captured when the exception was created, innermost frame first
+    pub fn stack_trace(&self, vm: &Vm<'a>) -> Vec<StackTraceElement<'a>> {
+        vm.get_stack_trace_associated_with_throwable(self.0.clone())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Formats the exception like `Throwable.printStackTrace` does: a line with the class
+    /// and the message, followed by one line for each frame of the stack trace, and then
+    /// the same for each cause. The frames that a cause has in common with the exception
+    /// it caused are omitted and replaced by a `... n more` line.
+    pub fn format_stack_trace(&self, vm: &Vm<'a>) -> Result<Vec<String>, VmError> {
+        let mut lines: Vec<String> = Vec::new();
+        let mut enclosing_trace: Vec<StackTraceElement<'a>> = Vec::new();
+        // Identity hash codes can collide, so we compare the objects themselves
+        let mut seen: Vec<AbstractObject<'a>> = Vec::new();
+        let mut exception = Some(JavaException(self.0.clone()));
+
+        while let Some(current) = exception {
+            let description = current.description(vm)?;
+            if seen.contains(&current.0) {
+                lines.push(format!("\t[CIRCULAR REFERENCE:{description}]"));
+                break;
+            }
+            seen.push(current.0.clone());
+            if lines.is_empty() {
+                lines.push(description);
+            } else {
+                lines.push(format!("Caused by: {description}"));
+            }
+
+            let trace = current.stack_trace(vm);
+            let frames_in_common = trace
+                .iter()
+                .rev()
+                .zip(enclosing_trace.iter().rev())
+                .take_while(|(frame, enclosing_frame)| frame == enclosing_frame)
+                .count();
+            for element in trace[..trace.len() - frames_in_common].iter() {
+                lines.push(format!("\tat {}", Self::format_frame(element)));
+            }
+            if frames_in_common > 0 {
+                lines.push(format!("\t... {frames_in_common} more"));
+            }
+
+            enclosing_trace = trace;
+            exception = current.cause(vm)?;
+        }
+        Ok(lines)
+    }
+
+    /// Returns the class name and the message, like `Throwable.toString` does
+    fn description(&self, vm: &Vm<'a>) -> Result<String, VmError> {
+        let class_name = vm
+            .get_class_by_id(self.0.class_id())?
+            .name
+            .replace('/', ".");
+        Ok(match self.message(vm)? {
+            Some(message) => format!("{class_name}: {message}"),
+            None => class_name,
+        })
+    }
+
+    fn format_frame(element: &StackTraceElement<'a>) -> String {
+        let location = match (element.source_file, element.line_number) {
+            (Some(file_name), Some(line_number)) => format!("{file_name}:{line_number}"),
+            (Some(file_name), None) => file_name.clone(),
+            (None, _) => "Unknown Source".to_string(),
+        };
+        format!(
+            "{}.{}({location})",
+            element.class_name.replace('/', "."),
+            element.method_name
+        )
+    }
+}
@@ -1,20 +1,43 @@
 use std::{
     fmt,
-    fmt::Formatter,
+    fmt::{Debug, Formatter},
     path::{Path, PathBuf},
 };
 
 use crate::class_path_entry::{ClassLoadingError, ClassPathEntry};
 
+/// Maps a class name, such as `java/lang/Object`, to the path of its file,
+/// relative to the base directory of a [FileSystemClassPathEntry]
+pub type ClassFileNameMapping = Box<dyn Fn(&str) -> PathBuf>;
+
 /// Implementation of [ClassPathEntry] that searches for `.class` files,
 /// using the given directory as the root package
-#[derive(Debug)]
 pub struct FileSystemClassPathEntry {
     base_directory: PathBuf,
+    file_name_mapping: ClassFileNameMapping,
+}
+
+impl Debug for FileSystemClassPathEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "FileSystemClassPathEntry {{ base_directory: {:?} }}",
+            self.base_directory
+        )
+    }
 }
 
 impl FileSystemClassPathEntry {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, InvalidDirectoryError> {
+        Self::with_file_name_mapping(path, Box::new(Self::default_file_name))
+    }
+
+    /// Creates an entry that uses a custom mapping from class names to file names,
+    /// to support non-standard layouts of the class files
+    pub fn with_file_name_mapping<P: AsRef<Path>>(
+        path: P,
+        file_name_mapping: ClassFileNameMapping,
+    ) -> Result<Self, InvalidDirectoryError> {
         let mut base_directory = PathBuf::new();
         base_directory.push(path);
 
@@ -23,16 +46,26 @@ impl FileSystemClassPathEntry {
                 path: base_directory.to_string_lossy().to_string(),
             })
         } else {
-            Ok(Self { base_directory })
+            Ok(Self {
+                base_directory,
+                file_name_mapping,
+            })
         }
     }
+
+    /// The standard layout, i.e. one directory per package and the `.class` extension
+    fn default_file_name(class_name: &str) -> PathBuf {
+        let mut file_name = PathBuf::from(class_name);
+        file_name.set_extension("class");
+        file_name
+    }
 }
 
 impl ClassPathEntry for FileSystemClassPathEntry {
     fn resolve(&self, class_name: &str) -> Result<Option<Vec<u8>>, ClassLoadingError> {
-        let mut candidate = self.base_directory.clone();
-        candidate.push(class_name);
-        candidate.set_extension("class");
+        let candidate = self
+            .base_directory
+            .join((self.file_name_mapping)(class_name));
         if candidate.exists() {
             std::fs::read(candidate)
                 .map(Some)
@@ -88,4 +121,21 @@ mod tests {
         assert_can_find_class(&entry, "rjvm/ControlFlow");
         assert_cannot_find_class(&entry, "rjvm/Foo");
     }
+
+    #[test]
+    fn file_system_class_path_entry_with_custom_file_name_mapping() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("tests");
+        let entry = FileSystemClassPathEntry::with_file_name_mapping(
+            &path,
+            Box::new(|class_name| PathBuf::from(format!("resources/{class_name}.class"))),
+        )
+        .expect("should find directory");
+
+        assert_can_find_class(&entry, "rjvm/NumericTypes");
+        assert_cannot_find_class(&entry, "rjvm/Foo");
+
+        let entry = FileSystemClassPathEntry::new(&path).expect("should find directory");
+        assert_cannot_find_class(&entry, "rjvm/NumericTypes");
+    }
 }
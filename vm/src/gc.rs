@@ -1,4 +1,11 @@
-use std::{alloc::Layout, fmt, fmt::Formatter, marker::PhantomData, ptr::null};
+use std::{
+    alloc::Layout,
+    fmt,
+    fmt::Formatter,
+    marker::PhantomData,
+    ptr::null,
+    time::{Duration, Instant},
+};
 
 use log::{debug, info};
 
@@ -49,6 +56,15 @@ impl MemoryChunk {
         }
     }
 
+    /// A chunk without any memory, which cannot be used for allocations
+    fn empty() -> Self {
+        MemoryChunk {
+            memory: std::ptr::NonNull::<u64>::dangling().as_ptr() as *mut u8,
+            capacity: 0,
+            used: 0,
+        }
+    }
+
     /// Allocates from the chunk, or returns None if there is not enough space
     fn alloc(&mut self, required_size: usize) -> Option<AllocEntry> {
         if self.used + required_size > self.capacity {
@@ -81,6 +97,45 @@ impl MemoryChunk {
     }
 }
 
+/// Statistics about the garbage collections executed so far
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GcStats {
+    /// Number of collections executed
+    pub collections: u64,
+    /// Total number of bytes copied to the other semi-space, across all collections
+    pub bytes_copied: u64,
+    /// Duration of the last collection
+    pub last_pause: Duration,
+    /// Bytes in use after the last collection
+    pub live_bytes: usize,
+}
+
+/// The result of a single garbage collection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GcOutcome {
+    /// Bytes in use before the collection
+    pub used_before: usize,
+    /// Bytes in use after the collection
+    pub used_after: usize,
+}
+
+impl GcOutcome {
+    /// Number of bytes that were freed by the collection
+    pub fn reclaimed_bytes(&self) -> usize {
+        self.used_before - self.used_after
+    }
+}
+
+/// Memory usage of the heap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapUsage {
+    /// Bytes currently allocated, including unreachable objects not yet collected
+    pub used: usize,
+    /// Bytes that can be allocated, i.e. the size of one semi-space, or of the whole
+    /// memory if garbage collection is disabled
+    pub capacity: usize,
+}
+
 /// Models the object allocator and the garbage collector!
 ///
 /// For the garbage collection, we use a very simple semi-space copying collector. We split the
@@ -91,22 +146,48 @@ impl MemoryChunk {
 /// Obviously, this wastes half the memory, which is why nobody uses this algorithm
 /// in any real implementation. However, it is quite simple, and handles reference cycles,
 /// so it is the one I have chosen here.
+///
+/// Alternatively, the allocator can be created with garbage collection disabled, in which case
+/// all the memory is used as a single region, and allocations fail once it is full. This is
+/// useful for short-lived executions, that fit in memory, without halving the heap.
 pub struct ObjectAllocator<'a> {
     current: MemoryChunk,
     other: MemoryChunk,
+    collection_enabled: bool,
+    stats: GcStats,
     marker: PhantomData<&'a AbstractObject<'a>>,
 }
 
 impl<'a> ObjectAllocator<'a> {
+    /// Creates a new allocator. Note that only half of the given memory will be usable
+    /// for allocations, since the other half is reserved for the copying collector.
     pub fn with_maximum_memory(max_size: usize) -> Self {
         let semi_space_capacity = max_size / 2;
         Self {
             current: MemoryChunk::new(semi_space_capacity),
             other: MemoryChunk::new(semi_space_capacity),
+            collection_enabled: true,
+            stats: Default::default(),
             marker: Default::default(),
         }
     }
 
+    /// Creates a new allocator that never collects garbage, and thus can use all the
+    /// given memory for allocations
+    pub fn bump_only(max_size: usize) -> Self {
+        Self {
+            current: MemoryChunk::new(max_size),
+            other: MemoryChunk::empty(),
+            collection_enabled: false,
+            stats: Default::default(),
+            marker: Default::default(),
+        }
+    }
+
+    pub fn is_collection_enabled(&self) -> bool {
+        self.collection_enabled
+    }
+
     /// Allocates a new object, or returns None if the memory is full
     pub fn allocate_object(&mut self, class: &Class<'a>) -> Option<AbstractObject<'a>> {
         let size = AbstractObject::size_of_object(class);
@@ -127,17 +208,40 @@ impl<'a> ObjectAllocator<'a> {
             .map(|alloc_entry| AbstractObject::new_array(elements_type, length, &alloc_entry))
     }
 
+    pub fn heap_usage(&self) -> HeapUsage {
+        HeapUsage {
+            used: self.current.used,
+            capacity: self.current.capacity,
+        }
+    }
+
+    pub fn stats(&self) -> &GcStats {
+        &self.stats
+    }
+
+    /// Discards all the allocated objects, making the whole heap available again.
+    /// Any object allocated before becomes invalid!
+    pub fn reset(&mut self) {
+        self.current.reset();
+        self.other.reset();
+        self.stats = Default::default();
+    }
+
     /// Runs the garbage collection! Will update the roots with the new addresses of the objects.
     pub unsafe fn do_garbage_collection(
         &mut self,
         roots: Vec<*mut AbstractObject<'a>>,
         class_resolver: &impl ClassByIdResolver<'a>,
     ) -> Result<(), VmError> {
+        if !self.collection_enabled {
+            return Err(VmError::GarbageCollectionDisabled);
+        }
         info!(
             "running gc; currently allocated memory = {}, gc roots count: {}",
             self.current.used,
             roots.len()
         );
+        let start = Instant::now();
 
         // Copy all reachable objects to the other region
         for root in roots.iter() {
@@ -156,6 +260,11 @@ impl<'a> ObjectAllocator<'a> {
         );
         self.other.reset();
 
+        self.stats.collections += 1;
+        self.stats.bytes_copied += self.current.used as u64;
+        self.stats.last_pause = start.elapsed();
+        self.stats.live_bytes = self.current.used;
+
         Ok(())
     }
 
@@ -257,7 +366,7 @@ impl<'a> ObjectAllocator<'a> {
                 // No objects are kept alive by this GC-reachable array!
                 Ok(())
             }
-            ArrayEntryType::Object(_) => {
+            ArrayEntryType::Object(_) | ArrayEntryType::Array(..) => {
                 for i in 0..array.len().into_usize_safe() {
                     let value = array.get_element(i);
                     match value {
@@ -273,9 +382,6 @@ impl<'a> ObjectAllocator<'a> {
                 }
                 Ok(())
             }
-            ArrayEntryType::Array => {
-                todo!("arrays of arrays are not supported yet")
-            }
         }
     }
 
@@ -343,8 +449,8 @@ impl<'a> ObjectAllocator<'a> {
                 // No objects are kept alive by this GC-reachable array!
                 Ok(())
             }
-            ArrayEntryType::Object(class_id) => {
-                debug!("fixing entries of array {array:?} of type {class_id}");
+            ArrayEntryType::Object(_) | ArrayEntryType::Array(..) => {
+                debug!("fixing entries of array {array:?}");
                 for i in 0..array.len().into_usize_safe() {
                     let element_ptr = array.ptr_to_array_element(i);
                     debug!(
@@ -360,9 +466,6 @@ impl<'a> ObjectAllocator<'a> {
                 }
                 Ok(())
             }
-            ArrayEntryType::Array => {
-                todo!("arrays of arrays are not supported yet")
-            }
         }
     }
 
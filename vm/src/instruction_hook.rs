@@ -0,0 +1,27 @@
+use rjvm_reader::{instruction::Instruction, program_counter::ProgramCounter};
+
+/// Describes the instruction that the [crate::vm::Vm] is about to execute, and is passed
+/// to the hook registered via [crate::vm::Vm::set_instruction_hook].
+#[derive(Debug)]
+pub struct InstructionContext<'i> {
+    pub class_name: &'i str,
+    pub method_name: &'i str,
+    pub pc: ProgramCounter,
+    pub instruction: &'i Instruction,
+    /// Number of frames on the call stack, including the one executing the instruction
+    pub depth: usize,
+}
+
+/// Returned by the instruction hook to control how the execution proceeds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepAction {
+    /// Invoke the hook again before the next instruction, wherever it is executed
+    StepInto,
+    /// Do not invoke the hook for the instructions executed by any method called from the
+    /// current one, i.e. resume at the next instruction of the current frame. If the
+    /// current frame returns or unwinds, the hook is invoked again in the caller.
+    StepOver,
+}
+
+/// A callback invoked before executing each instruction
+pub type InstructionHook<'a> = Box<dyn FnMut(&InstructionContext) -> StepAction + 'a>;
@@ -1,10 +1,14 @@
-use rjvm_reader::{field_type::BaseType, line_number::LineNumber};
+use rjvm_reader::{
+    field_type::{BaseType, FieldType},
+    line_number::LineNumber,
+};
 
 use crate::{
-    abstract_object::{string_from_char_array, AbstractObject},
+    abstract_object::{utf16_from_char_array, AbstractObject},
     array::Array,
     array_entry_type::ArrayEntryType,
     call_stack::CallStack,
+    class::Class,
     exceptions::MethodCallFailed,
     object::Object,
     stack_trace_element::StackTraceElement,
@@ -19,60 +23,199 @@ pub fn new_java_lang_string_object<'a>(
     call_stack: &mut CallStack<'a>,
     content: &str,
 ) -> Result<AbstractObject<'a>, MethodCallFailed<'a>> {
+    let java_array = new_char_array(vm, content)?;
+    let (java_array, string_object) = vm.with_temporary_root(java_array, |vm| {
+        vm.new_object(call_stack, "java/lang/String")
+    });
+    let string_object = string_object?;
+    let string_class = vm.get_class_by_id(string_object.class_id())?;
+    let value_field_index = string_value_field_index(string_class)?;
+    // All the other fields, i.e. the cached hash codes, are left to their default value of zero
+    string_object.set_field(value_field_index, Value::Object(java_array));
+    Ok(string_object)
+}
+
+/// Creates a new `char[]` with the UTF-16 encoding of the given string
+fn new_char_array<'a>(vm: &mut Vm<'a>, content: &str) -> Result<AbstractObject<'a>, VmError> {
     let char_array: Vec<Value<'a>> = content
         .encode_utf16()
         .map(|c| Value::Int(c as i32))
         .collect();
 
-    let java_array = vm.new_array(ArrayEntryType::Base(BaseType::Char), char_array.len());
+    let java_array = vm.new_array(ArrayEntryType::Base(BaseType::Char), char_array.len())?;
     char_array
         .into_iter()
         .enumerate()
         .for_each(|(index, value)| java_array.set_element(index, value).unwrap());
-
-    // In our JRE's rt.jar, the fields for String are:
-    //    private final char[] value;
-    //    private int hash;
-    //    private static final long serialVersionUID = -6849794470754667710L;
-    //    private static final ObjectStreamField[] serialPersistentFields = new ObjectStreamField[0];
-    //    public static final Comparator<String> CASE_INSENSITIVE_ORDER = new CaseInsensitiveComparator();
-    //    private static final int HASHING_SEED;
-    //    private transient int hash32;
-    let string_object = vm.new_object(call_stack, "java/lang/String")?;
-    string_object.set_field(0, Value::Object(java_array));
-    string_object.set_field(1, Value::Int(0));
-    string_object.set_field(6, Value::Int(0));
-    Ok(string_object)
+    Ok(java_array)
 }
 
-/// Given an instance of `java.lang.String`, extracts the content as a Rust `String`
+/// Given an instance of `java.lang.String`, extracts the content as a Rust `String`.
+/// Invalid utf-16, such as a lone surrogate, is replaced by the replacement character.
 pub fn extract_str_from_java_lang_string<'a>(
     vm: &Vm<'a>,
     object: &impl Object<'a>,
 ) -> Result<String, VmError> {
+    extract_utf16_from_java_lang_string(vm, object)
+        .map(|string_chars| String::from_utf16_lossy(&string_chars))
+}
+
+/// Given an instance of `java.lang.String`, extracts its exact content as utf-16 code units
+pub fn extract_utf16_from_java_lang_string<'a>(
+    vm: &Vm<'a>,
+    object: &impl Object<'a>,
+) -> Result<Vec<u16>, VmError> {
     let class = vm.get_class_by_id(object.class_id())?;
     if class.name == "java/lang/String" {
-        // In our JRE's rt.jar, the first fields of String is
-        //    private final char[] value;
-        if let Value::Object(array) = object.get_field(class, 0) {
-            return string_from_char_array(array);
+        let value_field_index = string_value_field_index(class)?;
+        if let Value::Object(array) = object.get_field(class, value_field_index) {
+            return utf16_from_char_array(array);
         }
     }
     Err(VmError::ValidationException)
 }
 
+/// Returns the index of the field that holds the content of a `java.lang.String`.
+/// In the rt.jar of Java 7 and 8 it is `private final char[] value`; newer versions use
+/// a `byte[]` along with a `coder` field (JEP 254, compact strings), which we do not support.
+fn string_value_field_index(string_class: &Class) -> Result<usize, VmError> {
+    match string_class.find_field("value") {
+        Some((index, field))
+            if field.type_descriptor
+                == FieldType::Array(Box::new(FieldType::Base(BaseType::Char))) =>
+        {
+            Ok(index)
+        }
+        Some(_) => Err(VmError::NotImplemented),
+        None => Err(VmError::FieldNotFoundException(
+            string_class.name.clone(),
+            "value".to_string(),
+        )),
+    }
+}
+
+/// Given an instance of `java.lang.Class`, extracts the internal name of the class it represents
+pub fn extract_class_name_from_java_lang_class<'a>(
+    vm: &Vm<'a>,
+    object: &impl Object<'a>,
+) -> Result<String, VmError> {
+    let class = vm.get_class_by_id(object.class_id())?;
+    if class.name == "java/lang/Class" {
+        if let Value::Object(name) = object.get_field(class, class_name_field_index(class)?) {
+            return extract_str_from_java_lang_string(vm, &name);
+        }
+    }
+    Err(VmError::ValidationException)
+}
+
+/// Returns the index of the field `private transient String name;` of `java.lang.Class`,
+/// in which we store the name of the class
+fn class_name_field_index(class_class: &Class) -> Result<usize, VmError> {
+    class_class
+        .find_field("name")
+        .map(|(index, _)| index)
+        .ok_or_else(|| {
+            VmError::FieldNotFoundException(class_class.name.clone(), "name".to_string())
+        })
+}
+
+/// Returns the instance of `java.lang.Class` for the given class name. Since code can compare
+/// them by reference, there is only one instance per class, which gets created on first use.
+/// The class is loaded if needed, but not initialized.
 pub fn new_java_lang_class_object<'a>(
     vm: &mut Vm<'a>,
     call_stack: &mut CallStack<'a>,
     class_name: &str,
+) -> Result<AbstractObject<'a>, MethodCallFailed<'a>> {
+    if class_name.starts_with('[') {
+        return vm.get_or_create_array_or_primitive_class_object(call_stack, class_name);
+    }
+    let class = vm.get_or_load_class(class_name)?;
+    vm.get_or_create_class_object(call_stack, class)
+}
+
+/// Allocates a new instance of `java.lang.Class`, without checking whether one already exists
+pub(crate) fn allocate_java_lang_class_object<'a>(
+    vm: &mut Vm<'a>,
+    call_stack: &mut CallStack<'a>,
+    class_name: &str,
 ) -> Result<AbstractObject<'a>, MethodCallFailed<'a>> {
     let class_object = vm.new_object(call_stack, "java/lang/Class")?;
     // TODO: build a proper instance of Class object
-    let string_object = new_java_lang_string_object(vm, call_stack, class_name)?;
-    class_object.set_field(5, Value::Object(string_object));
+    let (class_object, string_object) = vm.with_temporary_root(class_object, |vm| {
+        new_java_lang_string_object(vm, call_stack, class_name)
+    });
+    let string_object = string_object?;
+    let class_class = vm.get_class_by_id(class_object.class_id())?;
+    class_object.set_field(
+        class_name_field_index(class_class)?,
+        Value::Object(string_object),
+    );
     Ok(class_object)
 }
 
+/// Allocates the instance of `java.lang.Thread` representing the main thread. Its constructor
+/// would require a thread group, so we only set the fields read by the simple accessors.
+pub(crate) fn allocate_main_thread_object<'a>(
+    vm: &mut Vm<'a>,
+    call_stack: &mut CallStack<'a>,
+) -> Result<AbstractObject<'a>, MethodCallFailed<'a>> {
+    let name = new_char_array(vm, "main")?;
+    let (name, thread_object) =
+        vm.with_temporary_root(name, |vm| vm.new_object(call_stack, "java/lang/Thread"));
+    let thread_object = thread_object?;
+    let thread_class = vm.get_class_by_id(thread_object.class_id())?;
+    let field_index = |field_name: &str| {
+        thread_class
+            .find_field(field_name)
+            .map(|(index, _)| index)
+            .ok_or_else(|| {
+                VmError::FieldNotFoundException(thread_class.name.clone(), field_name.to_string())
+            })
+    };
+    thread_object.set_field(field_index("name")?, Value::Object(name));
+    // Thread.NORM_PRIORITY
+    thread_object.set_field(field_index("priority")?, Value::Int(5));
+    thread_object.set_field(field_index("tid")?, Value::Long(1));
+    Ok(thread_object)
+}
+
+/// Creates a new instance of the given exception class, invoking its no-arguments constructor
+pub fn new_java_exception_object<'a>(
+    vm: &mut Vm<'a>,
+    call_stack: &mut CallStack<'a>,
+    class_name: &str,
+) -> Result<AbstractObject<'a>, MethodCallFailed<'a>> {
+    vm.new_instance(call_stack, class_name, "()V", Vec::new())
+}
+
+/// Creates a new instance of the given exception class, invoking its constructor
+/// that takes the message
+pub fn new_java_exception_object_with_message<'a>(
+    vm: &mut Vm<'a>,
+    call_stack: &mut CallStack<'a>,
+    class_name: &str,
+    message: &str,
+) -> Result<AbstractObject<'a>, MethodCallFailed<'a>> {
+    let constructor =
+        vm.resolve_class_method(call_stack, class_name, "<init>", "(Ljava/lang/String;)V")?;
+    let exception = vm.new_object_of_class(constructor.class)?;
+    let (exception, message) = vm.with_temporary_root(exception, |vm| {
+        new_java_lang_string_object(vm, call_stack, message)
+    });
+    let message = message?;
+    let (exception, result) = vm.with_temporary_root(exception.clone(), |vm| {
+        vm.invoke(
+            call_stack,
+            constructor,
+            Some(exception),
+            vec![Value::Object(message)],
+        )
+    });
+    result?;
+    Ok(exception)
+}
+
 pub fn new_java_lang_stack_trace_element_object<'a>(
     vm: &mut Vm<'a>,
     call_stack: &mut CallStack<'a>,
@@ -14,6 +14,7 @@ mod class_resolver_by_id;
 pub mod exceptions;
 mod file_system_class_path_entry;
 mod gc;
+pub mod instruction_hook;
 mod jar_file_class_path_entry;
 pub mod java_objects_creation;
 mod native_methods_impl;
@@ -23,5 +24,7 @@ pub mod stack_trace_element;
 mod time;
 pub mod value;
 mod value_stack;
+mod verifier;
 pub mod vm;
 pub mod vm_error;
+pub mod vm_event;
@@ -1,23 +1,30 @@
-use log::{debug, info};
+use std::io::Write;
 
-use rjvm_reader::type_conversion::ToUsizeSafe;
+use log::{debug, info, warn};
+
+use rjvm_reader::{
+    field_type::{BaseType, FieldType},
+    type_conversion::ToUsizeSafe,
+};
 
 use crate::{
-    abstract_object::{AbstractObject, ObjectKind},
+    abstract_object::{string_from_char_array, AbstractObject, ObjectKind},
     array::Array,
+    array_entry_type::ArrayEntryType,
     call_frame::MethodCallResult,
     call_stack::CallStack,
-    exceptions::MethodCallFailed,
+    exceptions::{JavaException, MethodCallFailed},
     java_objects_creation::{
-        extract_str_from_java_lang_string, new_java_lang_class_object,
-        new_java_lang_stack_trace_element_object,
+        extract_class_name_from_java_lang_class, extract_str_from_java_lang_string,
+        new_java_exception_object, new_java_lang_stack_trace_element_object,
+        new_java_lang_string_object,
     },
     native_methods_registry::NativeMethodsRegistry,
     object::Object,
     time::{get_current_time_millis, get_nano_time},
     value::{
-        expect_abstract_object_at, expect_array_at, expect_concrete_object_at, expect_double_at,
-        expect_float_at, expect_int_at, Value,
+        expect_abstract_object_at, expect_concrete_object_at, expect_double_at, expect_float_at,
+        expect_int_at, expect_long_at, Value,
     },
     vm::Vm,
     vm_error::VmError,
@@ -30,8 +37,16 @@ pub(crate) fn register_natives(registry: &mut NativeMethodsRegistry) {
     register_time_methods(registry);
     register_gc_methods(registry);
     register_native_repr_methods(registry);
+    register_math_methods(registry);
     register_reflection_methods(registry);
     register_throwable_methods(registry);
+    register_string_methods(registry);
+    register_reference_methods(registry);
+    register_saved_properties_methods(registry);
+    register_exit_methods(registry);
+    register_thread_methods(registry);
+    register_byte_array_output_stream_methods(registry);
+    register_print_stream_methods(registry);
 }
 
 /// These various methods are noop, i.e. they do not do anything
@@ -59,6 +74,64 @@ fn register_noops(registry: &mut NativeMethodsRegistry) {
     );
 }
 
+/// Our garbage collector treats all references as strong ones, and we never run finalizers.
+/// Thus, references never get cleared or enqueued, and there is nothing to register.
+/// Note that `Object::finalize` needs no stub, since in our rt.jar it is already empty.
+fn register_reference_methods(registry: &mut NativeMethodsRegistry) {
+    // The static initializer of Reference starts the thread that enqueues the cleared
+    // references, but we do not support threads and we would not need it anyway
+    registry.register_override(
+        "java/lang/ref/Reference",
+        "<clinit>",
+        "()V",
+        |_, _, _, _| Ok(None),
+    );
+}
+
+/// We never run `System.initializeSystemClass`, so there are no saved system properties.
+/// They are looked up, for example, by the cache of boxed integers to find its size.
+fn register_saved_properties_methods(registry: &mut NativeMethodsRegistry) {
+    registry.register("sun/misc/VM", "initialize", "()V", |_, _, _, _| Ok(None));
+    registry.register_override(
+        "sun/misc/VM",
+        "getSavedProperty",
+        "(Ljava/lang/String;)Ljava/lang/String;",
+        |_, _, _, _| Ok(Some(Value::Null)),
+    );
+}
+
+/// We do not run the shutdown hooks, so exiting just unwinds the whole execution
+/// with the requested status code, without running any `catch` or `finally` block
+fn register_exit_methods(registry: &mut NativeMethodsRegistry) {
+    registry.register_override("java/lang/System", "exit", "(I)V", |_, _, _, args| {
+        exit(&args)
+    });
+    registry.register_override("java/lang/Runtime", "exit", "(I)V", |_, _, _, args| {
+        exit(&args)
+    });
+}
+
+fn exit<'a>(args: &[Value<'a>]) -> MethodCallResult<'a> {
+    let status = expect_int_at(args, 0)?;
+    Err(MethodCallFailed::Exit(status))
+}
+
+/// We support only one thread, the one running the program
+fn register_thread_methods(registry: &mut NativeMethodsRegistry) {
+    registry.register(
+        "java/lang/Thread",
+        "registerNatives",
+        "()V",
+        |_, _, _, _| Ok(None),
+    );
+    registry.register(
+        "java/lang/Thread",
+        "currentThread",
+        "()Ljava/lang/Thread;",
+        |vm, stack, _, _| Ok(Some(Value::Object(vm.get_or_create_main_thread(stack)?))),
+    );
+}
+
 /// Methods to access the system clock
 fn register_time_methods(registry: &mut NativeMethodsRegistry) {
     registry.register("java/lang/System", "nanoTime", "()J", |_, _, _, _| {
@@ -80,8 +153,17 @@ fn register_gc_methods(registry: &mut NativeMethodsRegistry) {
         "(Ljava/lang/Object;)I",
         |_, _, _, args| identity_hash_code(args),
     );
+    registry.register(
+        "java/lang/Object",
+        "hashCode",
+        "()I",
+        |_, _, receiver, _| object_hash_code(receiver),
+    );
+    // Without the collector, System.gc() is just a hint that we ignore
     registry.register("java/lang/System", "gc", "()V", |vm, _, _, _| {
-        vm.run_garbage_collection()?;
+        if vm.is_garbage_collection_enabled() {
+            vm.run_garbage_collection()?;
+        }
         Ok(None)
     });
 }
@@ -92,8 +174,55 @@ fn register_native_repr_methods(registry: &mut NativeMethodsRegistry) {
         "java/lang/System",
         "arraycopy",
         "(Ljava/lang/Object;ILjava/lang/Object;II)V",
-        |_, _, _, args| native_array_copy(args),
+        |vm, call_stack, _, args| native_array_copy(vm, call_stack, &args),
     );
+    for type_descriptor in [
+        "([ZZ)V",
+        "([BB)V",
+        "([CC)V",
+        "([SS)V",
+        "([II)V",
+        "([JJ)V",
+        "([FF)V",
+        "([DD)V",
+        "([Ljava/lang/Object;Ljava/lang/Object;)V",
+    ] {
+        registry.register_override(
+            "java/util/Arrays",
+            "fill",
+            type_descriptor,
+            |vm, call_stack, _, args| arrays_fill(vm, call_stack, &args, false),
+        );
+    }
+    for type_descriptor in [
+        "([ZIIZ)V",
+        "([BIIB)V",
+        "([CIIC)V",
+        "([SIIS)V",
+        "([IIII)V",
+        "([JIIJ)V",
+        "([FIIF)V",
+        "([DIID)V",
+        "([Ljava/lang/Object;IILjava/lang/Object;)V",
+    ] {
+        registry.register_override(
+            "java/util/Arrays",
+            "fill",
+            type_descriptor,
+            |vm, call_stack, _, args| arrays_fill(vm, call_stack, &args, true),
+        );
+    }
+    // The version for arrays of objects is not overridden, since it invokes `equals`
+    for type_descriptor in [
+        "([Z[Z)Z", "([B[B)Z", "([C[C)Z", "([S[S)Z", "([I[I)Z", "([J[J)Z", "([F[F)Z", "([D[D)Z",
+    ] {
+        registry.register_override(
+            "java/util/Arrays",
+            "equals",
+            type_descriptor,
+            |_, _, _, args| arrays_equals(&args),
+        );
+    }
     registry.register(
         "java/lang/Float",
         "floatToRawIntBits",
@@ -106,10 +235,109 @@ fn register_native_repr_methods(registry: &mut NativeMethodsRegistry) {
         "(D)J",
         |_, _, _, args| double_to_raw_long_bits(&args),
     );
+    registry.register(
+        "java/lang/Float",
+        "intBitsToFloat",
+        "(I)F",
+        |_, _, _, args| int_bits_to_float(&args),
+    );
+    registry.register(
+        "java/lang/Double",
+        "longBitsToDouble",
+        "(J)D",
+        |_, _, _, args| long_bits_to_double(&args),
+    );
+    registry.register_override(
+        "java/lang/Float",
+        "floatToIntBits",
+        "(F)I",
+        |_, _, _, args| float_to_int_bits(&args),
+    );
+    registry.register_override(
+        "java/lang/Double",
+        "doubleToLongBits",
+        "(D)J",
+        |_, _, _, args| double_to_long_bits(&args),
+    );
+    registry.register(
+        "java/lang/Object",
+        "clone",
+        "()Ljava/lang/Object;",
+        |vm, call_stack, receiver, _| object_clone(vm, call_stack, receiver),
+    );
+}
+
+/// The math functions of `java.lang.StrictMath`, which `java.lang.Math` delegates to.
+/// Note that we use Rust's implementation rather than fdlibm, so results might differ from
+/// the ones of a real JVM in the last bit.
+fn register_math_methods(registry: &mut NativeMethodsRegistry) {
+    registry.register("java/lang/StrictMath", "sin", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::sin)
+    });
+    registry.register("java/lang/StrictMath", "cos", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::cos)
+    });
+    registry.register("java/lang/StrictMath", "tan", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::tan)
+    });
+    registry.register("java/lang/StrictMath", "asin", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::asin)
+    });
+    registry.register("java/lang/StrictMath", "acos", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::acos)
+    });
+    registry.register("java/lang/StrictMath", "atan", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::atan)
+    });
+    registry.register("java/lang/StrictMath", "exp", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::exp)
+    });
+    registry.register("java/lang/StrictMath", "log", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::ln)
+    });
+    registry.register("java/lang/StrictMath", "log10", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::log10)
+    });
+    registry.register("java/lang/StrictMath", "sqrt", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::sqrt)
+    });
+    registry.register("java/lang/StrictMath", "cbrt", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::cbrt)
+    });
+    registry.register("java/lang/StrictMath", "sinh", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::sinh)
+    });
+    registry.register("java/lang/StrictMath", "cosh", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::cosh)
+    });
+    registry.register("java/lang/StrictMath", "tanh", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::tanh)
+    });
+    registry.register("java/lang/StrictMath", "expm1", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::exp_m1)
+    });
+    registry.register("java/lang/StrictMath", "log1p", "(D)D", |_, _, _, args| {
+        unary_math_function(&args, f64::ln_1p)
+    });
+    registry.register("java/lang/StrictMath", "atan2", "(DD)D", |_, _, _, args| {
+        binary_math_function(&args, f64::atan2)
+    });
+    registry.register("java/lang/StrictMath", "pow", "(DD)D", |_, _, _, args| {
+        binary_math_function(&args, java_pow)
+    });
+    registry.register("java/lang/StrictMath", "hypot", "(DD)D", |_, _, _, args| {
+        binary_math_function(&args, f64::hypot)
+    });
 }
 
 /// Methods related to reflection
 fn register_reflection_methods(registry: &mut NativeMethodsRegistry) {
+    registry.register(
+        "java/lang/Object",
+        "getClass",
+        "()Ljava/lang/Class;",
+        |vm, stack, receiver, _| get_class(vm, stack, receiver),
+    );
     registry.register(
         "java/lang/Class",
         "getClassLoader0",
@@ -128,6 +356,27 @@ fn register_reflection_methods(registry: &mut NativeMethodsRegistry) {
         "(Ljava/lang/String;)Ljava/lang/Class;",
         |vm, stack, _, args| get_primitive_class(vm, stack, &args),
     );
+    registry.register("java/lang/Class", "isArray", "()Z", |vm, _, receiver, _| {
+        is_array_class(vm, receiver)
+    });
+    registry.register(
+        "java/lang/Class",
+        "isPrimitive",
+        "()Z",
+        |vm, _, receiver, _| is_primitive_class(vm, receiver),
+    );
+    registry.register_override(
+        "java/lang/Class",
+        "getName",
+        "()Ljava/lang/String;",
+        |vm, stack, receiver, _| get_class_name(vm, stack, receiver),
+    );
+    registry.register_override(
+        "java/lang/Class",
+        "getSimpleName",
+        "()Ljava/lang/String;",
+        |vm, stack, receiver, _| get_class_simple_name(vm, stack, receiver),
+    );
 }
 
 /// Methods of java.lang.Throwable
@@ -150,6 +399,108 @@ fn register_throwable_methods(registry: &mut NativeMethodsRegistry) {
         "(I)Ljava/lang/StackTraceElement;",
         get_stack_trace_element,
     );
+    registry.register_override(
+        "java/lang/Throwable",
+        "printStackTrace",
+        "()V",
+        |vm, _, receiver, _| print_stack_trace(vm, receiver),
+    );
+}
+
+/// Methods of java.lang.String, implemented natively for performance
+fn register_string_methods(registry: &mut NativeMethodsRegistry) {
+    registry.register_override(
+        "java/lang/String",
+        "hashCode",
+        "()I",
+        |vm, _, receiver, _| string_hash_code(vm, receiver),
+    );
+    registry.register_override(
+        "java/lang/String",
+        "equals",
+        "(Ljava/lang/Object;)Z",
+        |vm, _, receiver, args| string_equals(vm, receiver, &args),
+    );
+    registry.register(
+        "java/lang/String",
+        "intern",
+        "()Ljava/lang/String;",
+        |vm, _, receiver, _| string_intern(vm, receiver),
+    );
+}
+
+/// We implement the writes of ByteArrayOutputStream natively, operating on its `buf` and
+/// `count` fields. `toString` is also native, since the java implementation requires the
+/// charset machinery, which we do not support; we always use UTF-8.
+fn register_byte_array_output_stream_methods(registry: &mut NativeMethodsRegistry) {
+    registry.register_override(
+        "java/io/ByteArrayOutputStream",
+        "write",
+        "(I)V",
+        |vm, _, receiver, args| byte_array_output_stream_write_byte(vm, receiver, &args),
+    );
+    registry.register_override(
+        "java/io/ByteArrayOutputStream",
+        "write",
+        "([BII)V",
+        |vm, stack, receiver, args| {
+            byte_array_output_stream_write_bytes(vm, stack, receiver, &args)
+        },
+    );
+    registry.register_override(
+        "java/io/ByteArrayOutputStream",
+        "toByteArray",
+        "()[B",
+        |vm, _, receiver, _| byte_array_output_stream_to_byte_array(vm, receiver),
+    );
+    registry.register_override(
+        "java/io/ByteArrayOutputStream",
+        "toString",
+        "()Ljava/lang/String;",
+        |vm, stack, receiver, _| byte_array_output_stream_to_string(vm, stack, receiver),
+    );
+}
+
+/// PrintStream encodes the text it prints with an OutputStreamWriter, which requires the
+/// charset machinery. We skip creating it, and replace the private methods that use it
+/// with native ones writing the UTF-8 encoding of the text directly to the wrapped stream.
+fn register_print_stream_methods(registry: &mut NativeMethodsRegistry) {
+    registry.register_override(
+        "java/io/PrintStream",
+        "<init>",
+        "(ZLjava/io/OutputStream;)V",
+        |vm, _, receiver, args| print_stream_init(vm, receiver, &args),
+    );
+    registry.register_override(
+        "java/io/PrintStream",
+        "write",
+        "(Ljava/lang/String;)V",
+        |vm, stack, receiver, args| {
+            let receiver = expect_some_receiver(receiver)?;
+            let string = expect_concrete_object_at(&args, 0)?;
+            let content = extract_str_from_java_lang_string(vm, &string)?;
+            print_stream_write_str(vm, stack, receiver, &content)
+        },
+    );
+    registry.register_override(
+        "java/io/PrintStream",
+        "write",
+        "([C)V",
+        |vm, stack, receiver, args| {
+            let receiver = expect_some_receiver(receiver)?;
+            let content = string_from_char_array(expect_abstract_object_at(&args, 0)?)?;
+            print_stream_write_str(vm, stack, receiver, &content)
+        },
+    );
+    registry.register_override(
+        "java/io/PrintStream",
+        "newLine",
+        "()V",
+        |vm, stack, receiver, _| {
+            let receiver = expect_some_receiver(receiver)?;
+            print_stream_write_str(vm, stack, receiver, "\n")
+        },
+    );
 }
 
 /// Debug method that does a "println", useful since we do not have real I/O
@@ -180,39 +531,255 @@ fn identity_hash_code(args: Vec<Value<'_>>) -> MethodCallResult<'_> {
     Ok(Some(Value::Int(object.identity_hash_code())))
 }
 
-fn native_array_copy(args: Vec<Value>) -> MethodCallResult {
-    // TODO: handle NullPointerException with the correct error
+fn object_hash_code(receiver: Option<AbstractObject>) -> MethodCallResult {
+    let receiver = expect_some_receiver(receiver)?;
+    Ok(Some(Value::Int(receiver.identity_hash_code())))
+}
+
+/// Creates a shallow copy of the receiver, i.e. a new object of the same class with the same
+/// field values. Arrays can always be cloned, other objects only if they implement `Cloneable`.
+fn object_clone<'a>(
+    vm: &mut Vm<'a>,
+    call_stack: &mut CallStack<'a>,
+    receiver: Option<AbstractObject<'a>>,
+) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    if receiver.kind() == ObjectKind::Array {
+        return vm
+            .clone_array(Value::Object(receiver))
+            .map(Some)
+            .map_err(Into::into);
+    }
+
+    let class = vm.get_class_by_id(receiver.class_id())?;
+    let (receiver, cloneable) = vm.with_temporary_root(receiver, |vm| {
+        vm.get_or_resolve_class(call_stack, "java/lang/Cloneable")
+    });
+    if !class.is_subclass_of(cloneable?) {
+        return throw_java_exception(vm, call_stack, "java/lang/CloneNotSupportedException");
+    }
+
+    let (receiver, clone) = vm.with_temporary_root(receiver, |vm| vm.new_object_of_class(class));
+    let clone = clone?;
+    for index in 0..class.num_total_fields {
+        clone.set_field(index, receiver.get_field(class, index));
+    }
+    Ok(Some(Value::Object(clone)))
+}
+
+fn native_array_copy<'a>(
+    vm: &mut Vm<'a>,
+    call_stack: &mut CallStack<'a>,
+    args: &[Value<'a>],
+) -> MethodCallResult<'a> {
+    if let (Some(Value::Null), _) | (_, Some(Value::Null)) = (args.first(), args.get(2)) {
+        return throw_java_exception(vm, call_stack, "java/lang/NullPointerException");
+    }
+
+    let src = expect_abstract_object_at(args, 0)?;
+    let src_pos = expect_int_at(args, 1)?;
+    let dest = expect_abstract_object_at(args, 2)?;
+    let dest_pos = expect_int_at(args, 3)?;
+    let length = expect_int_at(args, 4)?;
+    if src.kind() != ObjectKind::Array
+        || dest.kind() != ObjectKind::Array
+        || !are_array_types_compatible(&src.elements_type(), &dest.elements_type())
+    {
+        return throw_java_exception(vm, call_stack, "java/lang/ArrayStoreException");
+    }
+
+    // Bounds are checked before copying anything, so that the destination is left untouched
+    if src_pos < 0
+        || dest_pos < 0
+        || length < 0
+        || src_pos as i64 + length as i64 > src.len() as i64
+        || dest_pos as i64 + length as i64 > dest.len() as i64
+    {
+        return throw_java_exception(vm, call_stack, "java/lang/ArrayIndexOutOfBoundsException");
+    }
+
+    if src.elements_type() == dest.elements_type() {
+        array_copy(&src, src_pos, &dest, dest_pos, length.into_usize_safe())?;
+        return Ok(None);
+    }
 
-    let src = expect_array_at(&args, 0)?;
-    let src_pos = expect_int_at(&args, 1)?;
-    let dest = expect_array_at(&args, 2)?;
-    let dest_pos = expect_int_at(&args, 3)?;
-    let length = expect_int_at(&args, 4)?;
-    array_copy(&src, src_pos, &dest, dest_pos, length.into_usize_safe())?;
+    // Arrays of different reference types: each element has to be checked, and the ones
+    // preceding the first incompatible element are copied anyway
+    let dest_elements_type = dest.elements_type();
+    for i in 0..length.into_usize_safe() {
+        let value = src.get_element(src_pos.into_usize_safe() + i)?;
+        if !can_be_stored_in_array(vm, &dest_elements_type, &value)? {
+            return throw_java_exception(vm, call_stack, "java/lang/ArrayStoreException");
+        }
+        dest.set_element(dest_pos.into_usize_safe() + i, value)?;
+    }
     Ok(None)
 }
 
-pub fn array_copy<'a>(
-    src: &impl Array<'a>,
+/// Primitive arrays can only be copied into arrays of the same type, while arrays of
+/// references can be copied into any other array of references, checking each element
+fn are_array_types_compatible(src: &ArrayEntryType, dest: &ArrayEntryType) -> bool {
+    match (src, dest) {
+        (ArrayEntryType::Base(src_type), ArrayEntryType::Base(dest_type)) => src_type == dest_type,
+        (ArrayEntryType::Base(_), _) | (_, ArrayEntryType::Base(_)) => false,
+        _ => true,
+    }
+}
+
+fn can_be_stored_in_array<'a>(
+    vm: &Vm<'a>,
+    elements_type: &ArrayEntryType,
+    value: &Value<'a>,
+) -> Result<bool, VmError> {
+    match (elements_type, value) {
+        (_, Value::Null) => Ok(true),
+        (ArrayEntryType::Object(_) | ArrayEntryType::Array(..), _) => {
+            let field_type = elements_type
+                .clone()
+                .into_field_type(vm)
+                .ok_or(VmError::ValidationException)?;
+            Ok(value.matches_type(&field_type, vm, |class_name| {
+                vm.find_class_by_name(class_name)
+            }))
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Copies `length` elements between two arrays of the same type. Like `System.arraycopy`,
+/// it works correctly even if the source and destination are overlapping parts of the same array.
+pub fn array_copy<'a, A: Array<'a>>(
+    src: &A,
     src_pos: i32,
-    dest: &impl Array<'a>,
+    dest: &A,
     dest_pos: i32,
     length: usize,
 ) -> Result<(), VmError> {
-    if dest.elements_type() != src.elements_type() {
-        // TODO: we should throw an instance of ArrayStoreException
-        return Err(VmError::ValidationException);
+    dest.copy_elements_from(
+        src,
+        src_pos.into_usize_safe(),
+        dest_pos.into_usize_safe(),
+        length,
+    )
+}
+
+/// Implements the versions of `Arrays.fill`, which would otherwise set one element at a time.
+/// The versions with a range take the start (inclusive) and end (exclusive) before the value.
+fn arrays_fill<'a>(
+    vm: &mut Vm<'a>,
+    call_stack: &mut CallStack<'a>,
+    args: &[Value<'a>],
+    with_range: bool,
+) -> MethodCallResult<'a> {
+    if let Some(Value::Null) = args.first() {
+        return throw_java_exception(vm, call_stack, "java/lang/NullPointerException");
+    }
+    let array = expect_abstract_object_at(args, 0)?;
+    let (start, end, value) = if with_range {
+        (
+            expect_int_at(args, 1)?,
+            expect_int_at(args, 2)?,
+            args.get(3),
+        )
+    } else {
+        (0, array.len() as i32, args.get(1))
+    };
+    let value = value.cloned().ok_or(VmError::ValidationException)?;
+
+    if start > end {
+        return throw_java_exception(vm, call_stack, "java/lang/IllegalArgumentException");
     }
+    if start < 0 || end as i64 > array.len() as i64 {
+        return throw_java_exception(vm, call_stack, "java/lang/ArrayIndexOutOfBoundsException");
+    }
+    let elements_type = array.elements_type();
+    if !matches!(elements_type, ArrayEntryType::Base(_))
+        && !can_be_stored_in_array(vm, &elements_type, &value)?
+    {
+        return throw_java_exception(vm, call_stack, "java/lang/ArrayStoreException");
+    }
+    array.fill_elements(
+        start.into_usize_safe(),
+        (end - start).into_usize_safe(),
+        value,
+    )?;
+    Ok(None)
+}
 
-    for i in 0..length {
-        let src_index = src_pos.into_usize_safe() + i;
-        let src_item = src.get_element(src_index)?;
+/// Implements the versions of `Arrays.equals` for arrays of primitive types
+fn arrays_equals<'a>(args: &[Value<'a>]) -> MethodCallResult<'a> {
+    let equal = match (args.first(), args.get(1)) {
+        (Some(Value::Null), Some(Value::Null)) => true,
+        (Some(Value::Null), _) | (_, Some(Value::Null)) => false,
+        _ => {
+            let first = expect_abstract_object_at(args, 0)?;
+            let second = expect_abstract_object_at(args, 1)?;
+            if first.len() != second.len() {
+                false
+            } else {
+                let mut equal = true;
+                for index in 0..first.len().into_usize_safe() {
+                    if !are_array_elements_equal(
+                        &first.get_element(index)?,
+                        &second.get_element(index)?,
+                    ) {
+                        equal = false;
+                        break;
+                    }
+                }
+                equal
+            }
+        }
+    };
+    Ok(Some(Value::Int(equal as i32)))
+}
 
-        let dest_index = dest_pos.into_usize_safe() + i;
-        dest.set_element(dest_index, src_item)?;
+/// Like `Arrays.equals`, floating point values are compared by their bits, with all the
+/// NaN values considered equal, so that NaN equals NaN but 0.0 does not equal -0.0
+fn are_array_elements_equal(first: &Value, second: &Value) -> bool {
+    match (first, second) {
+        (Value::Float(first), Value::Float(second)) => {
+            first.to_bits() == second.to_bits() || (first.is_nan() && second.is_nan())
+        }
+        (Value::Double(first), Value::Double(second)) => {
+            first.to_bits() == second.to_bits() || (first.is_nan() && second.is_nan())
+        }
+        (first, second) => first == second,
     }
+}
+
+fn throw_java_exception<'a>(
+    vm: &mut Vm<'a>,
+    call_stack: &mut CallStack<'a>,
+    exception_class_name: &str,
+) -> MethodCallResult<'a> {
+    let exception = new_java_exception_object(vm, call_stack, exception_class_name)?;
+    Err(MethodCallFailed::ExceptionThrown(JavaException(exception)))
+}
+
+fn unary_math_function<'a>(args: &[Value<'a>], function: fn(f64) -> f64) -> MethodCallResult<'a> {
+    let arg = expect_double_at(args, 0)?;
+    Ok(Some(Value::Double(function(arg))))
+}
+
+fn binary_math_function<'a>(
+    args: &[Value<'a>],
+    function: fn(f64, f64) -> f64,
+) -> MethodCallResult<'a> {
+    // Doubles take two slots in the arguments
+    let first = expect_double_at(args, 0)?;
+    let second = expect_double_at(args, 2)?;
+    Ok(Some(Value::Double(function(first, second))))
+}
 
-    Ok(())
+/// Unlike `f64::powf`, which follows C's `pow`, Java returns NaN when the exponent is NaN,
+/// or when the base is ±1 and the exponent is infinite
+fn java_pow(base: f64, exponent: f64) -> f64 {
+    if exponent.is_nan() || (base.abs() == 1.0 && exponent.is_infinite()) {
+        f64::NAN
+    } else {
+        base.powf(exponent)
+    }
 }
 
 fn float_to_raw_int_bits<'a>(args: &[Value<'a>]) -> MethodCallResult<'a> {
@@ -227,6 +794,38 @@ fn double_to_raw_long_bits<'a>(args: &[Value<'a>]) -> MethodCallResult<'a> {
     Ok(Some(Value::Long(long_bits)))
 }
 
+/// Like [float_to_raw_int_bits], but all NaN values are collapsed to the canonical one
+fn float_to_int_bits<'a>(args: &[Value<'a>]) -> MethodCallResult<'a> {
+    let arg = expect_float_at(args, 0)?;
+    let int_bits: i32 = if arg.is_nan() {
+        0x7fc00000
+    } else {
+        arg.to_bits() as i32
+    };
+    Ok(Some(Value::Int(int_bits)))
+}
+
+/// Like [double_to_raw_long_bits], but all NaN values are collapsed to the canonical one
+fn double_to_long_bits<'a>(args: &[Value<'a>]) -> MethodCallResult<'a> {
+    let arg = expect_double_at(args, 0)?;
+    let long_bits: i64 = if arg.is_nan() {
+        0x7ff8000000000000
+    } else {
+        arg.to_bits() as i64
+    };
+    Ok(Some(Value::Long(long_bits)))
+}
+
+fn int_bits_to_float<'a>(args: &[Value<'a>]) -> MethodCallResult<'a> {
+    let arg = expect_int_at(args, 0)?;
+    Ok(Some(Value::Float(f32::from_bits(arg as u32))))
+}
+
+fn long_bits_to_double<'a>(args: &[Value<'a>]) -> MethodCallResult<'a> {
+    let arg = expect_long_at(args, 0)?;
+    Ok(Some(Value::Double(f64::from_bits(arg as u64))))
+}
+
 fn get_class_loader(receiver: Option<AbstractObject>) -> MethodCallResult {
     debug!("invoked get class loader for object {:?}", receiver);
 
@@ -234,6 +833,27 @@ fn get_class_loader(receiver: Option<AbstractObject>) -> MethodCallResult {
     Ok(Some(Value::Null))
 }
 
+fn get_class<'a>(
+    vm: &mut Vm<'a>,
+    stack: &mut CallStack<'a>,
+    receiver: Option<AbstractObject<'a>>,
+) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    let class_object = match receiver.kind() {
+        ObjectKind::Object => {
+            let class = vm.get_class_by_id(receiver.class_id())?;
+            vm.get_or_create_class_object(stack, class)?
+        }
+        ObjectKind::Array => {
+            let class_name = vm
+                .array_class_name(&receiver.elements_type())
+                .ok_or(VmError::ValidationException)?;
+            vm.get_or_create_array_or_primitive_class_object(stack, &class_name)?
+        }
+    };
+    Ok(Some(Value::Object(class_object)))
+}
+
 fn get_primitive_class<'a>(
     vm: &mut Vm<'a>,
     stack: &mut CallStack<'a>,
@@ -241,10 +861,81 @@ fn get_primitive_class<'a>(
 ) -> MethodCallResult<'a> {
     let arg = expect_concrete_object_at(args, 0)?;
     let class_name = extract_str_from_java_lang_string(vm, &arg)?;
-    let java_lang_class_instance = new_java_lang_class_object(vm, stack, &class_name)?;
+    let java_lang_class_instance =
+        vm.get_or_create_array_or_primitive_class_object(stack, &class_name)?;
     Ok(Some(Value::Object(java_lang_class_instance)))
 }
 
+fn is_array_class<'a>(vm: &Vm<'a>, receiver: Option<AbstractObject<'a>>) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    let class_name = extract_class_name_from_java_lang_class(vm, &receiver)?;
+    Ok(Some(Value::Int(class_name.starts_with('[') as i32)))
+}
+
+fn is_primitive_class<'a>(
+    vm: &Vm<'a>,
+    receiver: Option<AbstractObject<'a>>,
+) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    let class_name = extract_class_name_from_java_lang_class(vm, &receiver)?;
+    let is_primitive = matches!(
+        class_name.as_str(),
+        "boolean" | "byte" | "char" | "short" | "int" | "long" | "float" | "double" | "void"
+    );
+    Ok(Some(Value::Int(is_primitive as i32)))
+}
+
+fn get_class_name<'a>(
+    vm: &mut Vm<'a>,
+    stack: &mut CallStack<'a>,
+    receiver: Option<AbstractObject<'a>>,
+) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    let class_name = extract_class_name_from_java_lang_class(vm, &receiver)?;
+    let name = new_java_lang_string_object(vm, stack, &class_name.replace('/', "."))?;
+    Ok(Some(Value::Object(name)))
+}
+
+fn get_class_simple_name<'a>(
+    vm: &mut Vm<'a>,
+    stack: &mut CallStack<'a>,
+    receiver: Option<AbstractObject<'a>>,
+) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    let class_name = extract_class_name_from_java_lang_class(vm, &receiver)?;
+    let simple_name = if class_name.starts_with('[') {
+        let array_type = FieldType::parse(&class_name).map_err(|_| VmError::ValidationException)?;
+        simple_name_of_type(&array_type)
+    } else {
+        simple_name_of_class(&class_name).to_string()
+    };
+    let simple_name = new_java_lang_string_object(vm, stack, &simple_name)?;
+    Ok(Some(Value::Object(simple_name)))
+}
+
+fn simple_name_of_type(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Base(base_type) => base_type.to_string().to_lowercase(),
+        FieldType::Object(class_name) => simple_name_of_class(class_name).to_string(),
+        FieldType::Array(component_type) => format!("{}[]", simple_name_of_type(component_type)),
+    }
+}
+
+/// Strips the package and the enclosing classes from the name. Anonymous classes, which are
+/// named like `Outer$1`, have an empty simple name.
+fn simple_name_of_class(class_name: &str) -> &str {
+    let without_package = class_name.rsplit('/').next().unwrap_or(class_name);
+    let simple_name = without_package
+        .rsplit('$')
+        .next()
+        .unwrap_or(without_package);
+    if simple_name.chars().all(|c| c.is_ascii_digit()) {
+        ""
+    } else {
+        simple_name
+    }
+}
+
 fn fill_in_stack_trace<'a>(
     vm: &mut Vm<'a>,
     call_stack: &mut CallStack<'a>,
@@ -290,9 +981,323 @@ fn get_stack_trace_element<'a>(
     }
 }
 
+/// Writes the stack trace to the error output of the [Vm]
+fn print_stack_trace<'a>(
+    vm: &mut Vm<'a>,
+    receiver: Option<AbstractObject<'a>>,
+) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    let lines = JavaException(receiver).format_stack_trace(vm)?;
+    for line in lines {
+        // Like the java implementation, we ignore any error while writing
+        if writeln!(vm.error_output, "{line}").is_err() {
+            warn!("cannot write the stack trace to the error output");
+            break;
+        }
+    }
+    Ok(None)
+}
+
+fn string_hash_code<'a>(vm: &Vm<'a>, receiver: Option<AbstractObject<'a>>) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    let string = extract_str_from_java_lang_string(vm, &receiver)?;
+
+    // Same algorithm as the java implementation: s[0]*31^(n-1) + s[1]*31^(n-2) + ... + s[n-1]
+    let hash_code = string
+        .encode_utf16()
+        .fold(0i32, |hash, c| hash.wrapping_mul(31).wrapping_add(c as i32));
+    Ok(Some(Value::Int(hash_code)))
+}
+
+fn string_intern<'a>(
+    vm: &mut Vm<'a>,
+    receiver: Option<AbstractObject<'a>>,
+) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    Ok(Some(Value::Object(vm.intern_string(receiver)?)))
+}
+
+fn string_equals<'a>(
+    vm: &Vm<'a>,
+    receiver: Option<AbstractObject<'a>>,
+    args: &[Value<'a>],
+) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    let equal = match args.first() {
+        Some(Value::Object(other)) if other.is_same_as(&receiver) => true,
+        Some(Value::Object(other)) if other.kind() == ObjectKind::Object => {
+            let other_class = vm.get_class_by_id(other.class_id())?;
+            other_class.name == "java/lang/String"
+                && extract_str_from_java_lang_string(vm, &receiver)?
+                    == extract_str_from_java_lang_string(vm, other)?
+        }
+        Some(Value::Object(_)) | Some(Value::Null) => false,
+        _ => {
+            return Err(MethodCallFailed::InternalError(
+                VmError::ValidationException,
+            ))
+        }
+    };
+    Ok(Some(Value::Int(equal as i32)))
+}
+
 fn expect_some_receiver(receiver: Option<AbstractObject>) -> Result<AbstractObject, VmError> {
     match receiver {
         Some(v) => Ok(v),
         None => Err(VmError::ValidationException),
     }
 }
+
+/// Returns the `buf` and `count` fields of a ByteArrayOutputStream
+fn byte_array_output_stream_fields<'a>(
+    vm: &Vm<'a>,
+    stream: &AbstractObject<'a>,
+) -> Result<(AbstractObject<'a>, usize), VmError> {
+    match (
+        vm.get_object_field(stream, "buf"),
+        vm.get_object_field(stream, "count"),
+    ) {
+        (Some(Value::Object(buf)), Some(Value::Int(count))) => Ok((buf, count.into_usize_safe())),
+        _ => Err(VmError::ValidationException),
+    }
+}
+
+/// Grows the `buf` of the given ByteArrayOutputStream, if needed, so that it can hold
+/// `additional` more bytes. Returns the stream, which might have been moved by the
+/// garbage collector, its buffer and its count.
+fn byte_array_output_stream_ensure_capacity<'a>(
+    vm: &mut Vm<'a>,
+    stream: AbstractObject<'a>,
+    additional: usize,
+) -> Result<(AbstractObject<'a>, AbstractObject<'a>, usize), VmError> {
+    let (buf, count) = byte_array_output_stream_fields(vm, &stream)?;
+    let required_capacity = count + additional;
+    let capacity = buf.len().into_usize_safe();
+    if required_capacity <= capacity {
+        return Ok((stream, buf, count));
+    }
+
+    // Same growth policy as the java implementation
+    let new_capacity = required_capacity.max(capacity * 2);
+    let (stream, new_buf) = vm.with_temporary_root(stream, |vm| {
+        vm.new_array(ArrayEntryType::Base(BaseType::Byte), new_capacity)
+    });
+    let new_buf = new_buf?;
+    let (buf, count) = byte_array_output_stream_fields(vm, &stream)?;
+    array_copy(&buf, 0, &new_buf, 0, count)?;
+    vm.set_object_field(&stream, "buf", Value::Object(new_buf.clone()))?;
+    Ok((stream, new_buf, count))
+}
+
+fn byte_array_output_stream_write_byte<'a>(
+    vm: &mut Vm<'a>,
+    receiver: Option<AbstractObject<'a>>,
+    args: &[Value<'a>],
+) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    let byte = expect_int_at(args, 0)?;
+    let (receiver, buf, count) = byte_array_output_stream_ensure_capacity(vm, receiver, 1)?;
+    buf.set_element(count, Value::Int(byte as i8 as i32))?;
+    vm.set_object_field(&receiver, "count", Value::Int(count as i32 + 1))?;
+    Ok(None)
+}
+
+fn byte_array_output_stream_write_bytes<'a>(
+    vm: &mut Vm<'a>,
+    call_stack: &mut CallStack<'a>,
+    receiver: Option<AbstractObject<'a>>,
+    args: &[Value<'a>],
+) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    if let Some(Value::Null) = args.first() {
+        return throw_java_exception(vm, call_stack, "java/lang/NullPointerException");
+    }
+    let bytes = expect_abstract_object_at(args, 0)?;
+    let offset = expect_int_at(args, 1)?;
+    let length = expect_int_at(args, 2)?;
+    if bytes.kind() != ObjectKind::Array {
+        return Err(VmError::ValidationException.into());
+    }
+    if offset < 0 || length < 0 || offset as i64 + length as i64 > bytes.len() as i64 {
+        return throw_java_exception(vm, call_stack, "java/lang/IndexOutOfBoundsException");
+    }
+
+    let length = length.into_usize_safe();
+    let (bytes, result) = vm.with_temporary_root(bytes, |vm| {
+        byte_array_output_stream_ensure_capacity(vm, receiver, length)
+    });
+    let (receiver, buf, count) = result?;
+    array_copy(&bytes, offset, &buf, count as i32, length)?;
+    vm.set_object_field(&receiver, "count", Value::Int((count + length) as i32))?;
+    Ok(None)
+}
+
+fn byte_array_output_stream_to_byte_array<'a>(
+    vm: &mut Vm<'a>,
+    receiver: Option<AbstractObject<'a>>,
+) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    let (_, count) = byte_array_output_stream_fields(vm, &receiver)?;
+    let (receiver, array) = vm.with_temporary_root(receiver, |vm| {
+        vm.new_array(ArrayEntryType::Base(BaseType::Byte), count)
+    });
+    let array = array?;
+    let (buf, _) = byte_array_output_stream_fields(vm, &receiver)?;
+    array_copy(&buf, 0, &array, 0, count)?;
+    Ok(Some(Value::Object(array)))
+}
+
+fn byte_array_output_stream_to_string<'a>(
+    vm: &mut Vm<'a>,
+    call_stack: &mut CallStack<'a>,
+    receiver: Option<AbstractObject<'a>>,
+) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    let (buf, count) = byte_array_output_stream_fields(vm, &receiver)?;
+    let content = (0..count)
+        .map(|index| match buf.get_element(index)? {
+            Value::Int(byte) => Ok(byte as u8),
+            _ => Err(VmError::ValidationException),
+        })
+        .collect::<Result<Vec<u8>, VmError>>()?;
+    let string = new_java_lang_string_object(vm, call_stack, &String::from_utf8_lossy(&content))?;
+    Ok(Some(Value::Object(string)))
+}
+
+fn print_stream_init<'a>(
+    vm: &mut Vm<'a>,
+    receiver: Option<AbstractObject<'a>>,
+    args: &[Value<'a>],
+) -> MethodCallResult<'a> {
+    let receiver = expect_some_receiver(receiver)?;
+    let auto_flush = expect_int_at(args, 0)?;
+    let out = expect_abstract_object_at(args, 1)?;
+    vm.set_object_field(&receiver, "autoFlush", Value::Int(auto_flush))?;
+    vm.set_object_field(&receiver, "out", Value::Object(out))?;
+    Ok(None)
+}
+
+/// Writes the UTF-8 encoding of the given text to the stream wrapped by a PrintStream.
+/// Since we do not buffer anything, there is never a need to flush.
+fn print_stream_write_str<'a>(
+    vm: &mut Vm<'a>,
+    call_stack: &mut CallStack<'a>,
+    receiver: AbstractObject<'a>,
+    content: &str,
+) -> MethodCallResult<'a> {
+    let write = vm.resolve_class_method(call_stack, "java/io/OutputStream", "write", "([BII)V")?;
+    let (receiver, bytes) = vm.with_temporary_root(receiver, |vm| {
+        let bytes = vm.new_array(ArrayEntryType::Base(BaseType::Byte), content.len())?;
+        for (index, byte) in content.bytes().enumerate() {
+            bytes.set_element(index, Value::Int(byte as i8 as i32))?;
+        }
+        Ok::<_, VmError>(bytes)
+    });
+    let bytes = bytes?;
+    match vm.get_object_field(&receiver, "out") {
+        Some(Value::Object(out)) => vm.invoke_virtual(
+            call_stack,
+            write,
+            out,
+            vec![
+                Value::Object(bytes),
+                Value::Int(0),
+                Value::Int(content.len() as i32),
+            ],
+        ),
+        // The stream was closed: like the java implementation, we only record the error
+        _ => {
+            vm.set_object_field(&receiver, "trouble", Value::Int(1))?;
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rjvm_reader::field_type::BaseType;
+
+    use crate::{
+        abstract_object::AbstractObject,
+        array::Array,
+        array_entry_type::ArrayEntryType,
+        native_methods_impl::{array_copy, java_pow},
+        value::Value,
+        vm::{Vm, ONE_MEGABYTE},
+        vm_error::VmError,
+    };
+
+    fn new_int_array<'a>(vm: &mut Vm<'a>, values: &[i32]) -> AbstractObject<'a> {
+        let array = vm
+            .new_array(ArrayEntryType::Base(BaseType::Int), values.len())
+            .unwrap();
+        for (index, value) in values.iter().enumerate() {
+            array.set_element(index, Value::Int(*value)).unwrap();
+        }
+        array
+    }
+
+    fn int_array_content(array: &AbstractObject) -> Vec<i32> {
+        (0..array.len() as usize)
+            .map(|index| array.get_element(index).unwrap().as_int().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn array_copy_shifting_right_in_place() {
+        let mut vm = Vm::new(ONE_MEGABYTE);
+        let array = new_int_array(&mut vm, &[1, 2, 3, 4, 5]);
+        array_copy(&array, 0, &array, 1, 4).unwrap();
+        assert_eq!(vec![1, 1, 2, 3, 4], int_array_content(&array));
+    }
+
+    #[test]
+    fn array_copy_shifting_left_in_place() {
+        let mut vm = Vm::new(ONE_MEGABYTE);
+        let array = new_int_array(&mut vm, &[1, 2, 3, 4, 5]);
+        array_copy(&array, 1, &array, 0, 4).unwrap();
+        assert_eq!(vec![2, 3, 4, 5, 5], int_array_content(&array));
+    }
+
+    #[test]
+    fn array_copy_between_different_arrays() {
+        let mut vm = Vm::new(ONE_MEGABYTE);
+        let src = new_int_array(&mut vm, &[1, 2, 3]);
+        let dest = new_int_array(&mut vm, &[0, 0, 0, 0]);
+        array_copy(&src, 0, &dest, 1, 3).unwrap();
+        assert_eq!(vec![0, 1, 2, 3], int_array_content(&dest));
+    }
+
+    #[test]
+    fn fill_elements_sets_the_range() {
+        let mut vm = Vm::new(ONE_MEGABYTE);
+        let array = new_int_array(&mut vm, &[1, 2, 3, 4, 5]);
+        array.fill_elements(1, 3, Value::Int(9)).unwrap();
+        assert_eq!(vec![1, 9, 9, 9, 5], int_array_content(&array));
+    }
+
+    #[test]
+    fn fill_elements_checks_the_type_and_the_range() {
+        let mut vm = Vm::new(ONE_MEGABYTE);
+        let array = new_int_array(&mut vm, &[1, 2, 3]);
+        assert_eq!(
+            Err(VmError::ValidationException),
+            array.fill_elements(0, 3, Value::Long(1))
+        );
+        assert_eq!(
+            Err(VmError::ArrayIndexOutOfBoundsException),
+            array.fill_elements(1, 3, Value::Int(0))
+        );
+        assert_eq!(vec![1, 2, 3], int_array_content(&array));
+    }
+
+    #[test]
+    fn pow_follows_the_java_special_cases() {
+        assert_eq!(1024.0, java_pow(2.0, 10.0));
+        assert_eq!(1.0, java_pow(f64::NAN, 0.0));
+        assert!(java_pow(1.0, f64::NAN).is_nan());
+        assert!(java_pow(1.0, f64::INFINITY).is_nan());
+        assert!(java_pow(-1.0, f64::NEG_INFINITY).is_nan());
+        assert_eq!(f64::INFINITY, java_pow(2.0, f64::INFINITY));
+    }
+}
@@ -1,4 +1,8 @@
-use std::{collections::HashMap, fmt, fmt::Formatter};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    fmt::Formatter,
+};
 
 use crate::{
     abstract_object::AbstractObject, call_frame::MethodCallResult, call_stack::CallStack,
@@ -22,6 +26,11 @@ pub struct NativeMethodsRegistry<'a> {
     // this just stores the values printed by a method named `tempPrint` into an array
     // in the Vm object. This method is used for all classes whose name starts with rjvm.
     temp_print_callback: Option<NativeCallback<'a>>,
+
+    // Names of the classes that have at least one method whose bytecode implementation
+    // we replace with a native one. Used to avoid an expensive lookup in [methods]
+    // for every single method invocation.
+    classes_with_overrides: HashSet<String>,
 }
 
 impl<'a> fmt::Debug for NativeMethodsRegistry<'a> {
@@ -48,6 +57,20 @@ impl<'a> NativeMethodsRegistry<'a> {
         );
     }
 
+    /// Registers a native implementation for a method that is _not_ marked as native, which
+    /// will be used instead of the bytecode. Useful for methods that are very hot, or whose
+    /// implementation requires features that our VM does not support.
+    pub fn register_override(
+        &mut self,
+        class_name: &str,
+        method_name: &str,
+        type_descriptor: &str,
+        callback: NativeCallback<'a>,
+    ) {
+        self.classes_with_overrides.insert(class_name.to_string());
+        self.register(class_name, method_name, type_descriptor, callback);
+    }
+
     pub(crate) fn register_temp_print(&mut self, callback: NativeCallback<'a>) {
         self.temp_print_callback = Some(callback);
     }
@@ -60,6 +83,18 @@ impl<'a> NativeMethodsRegistry<'a> {
         )
     }
 
+    /// Returns the native override of a bytecode method, if any was registered
+    pub fn get_override(&self, class_and_method: &ClassAndMethod) -> Option<NativeCallback<'a>> {
+        if self
+            .classes_with_overrides
+            .contains(&class_and_method.class.name)
+        {
+            self.get_method(class_and_method)
+        } else {
+            None
+        }
+    }
+
     pub fn get(
         &self,
         class_name: &str,
@@ -5,7 +5,7 @@ use std::{
 };
 
 /// One element of the stack trace information. Models java.lang.StackTraceElement
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct StackTraceElement<'a> {
     pub class_name: &'a str,
     pub method_name: &'a str,
@@ -1,10 +1,11 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, LowerExp};
 
 use rjvm_reader::field_type::{BaseType, FieldType};
 
 use crate::{
     abstract_object::{AbstractObject, ObjectKind},
-    array::Array,
+    array::{is_implemented_by_arrays, Array},
+    array_entry_type::ArrayEntryType,
     class::ClassRef,
     class_resolver_by_id::ClassByIdResolver,
     object::Object,
@@ -20,7 +21,8 @@ pub enum Value<'a> {
     Uninitialized,
 
     /// Models all the 32-or-lower-bits types in the jvm: `boolean`, `byte`, `char`, `short`,
-    /// and `int`.
+    /// and `int`. On the stack and in local variables the narrower types can hold any
+    /// value, but they are normalized when stored in fields and arrays, see [narrowed_to].
     Int(i32),
 
     /// Models a `long` value.
@@ -41,12 +43,25 @@ pub enum Value<'a> {
 }
 
 impl<'a> Value<'a> {
+    /// Truncates an `Int` to the range of the given type, so that fields and array elements
+    /// of type `boolean` are always 0 or 1, `char` are in 0..65535, and `byte` and `short`
+    /// are sign-extended from their bits. Other values are returned unchanged.
+    pub(crate) fn narrowed_to(self, base_type: &BaseType) -> Self {
+        match (self, base_type) {
+            (Value::Int(value), BaseType::Boolean) => Value::Int(value & 1),
+            (Value::Int(value), BaseType::Byte) => Value::Int(value as i8 as i32),
+            (Value::Int(value), BaseType::Char) => Value::Int(value as u16 as i32),
+            (Value::Int(value), BaseType::Short) => Value::Int(value as i16 as i32),
+            (value, _) => value,
+        }
+    }
+
     /// Used for runtime validations that the value matches the given type.
     /// Overly complex; these things, according to the JVM spec, should be checked
     /// at class linkage time, but we have not implemented that phase... :-)
     pub fn matches_type<'b, 'c, ResByName>(
         &self,
-        expected_type: FieldType,
+        expected_type: &FieldType,
         class_resolver_by_id: &impl ClassByIdResolver<'c>,
         class_resolver_by_name: ResByName,
     ) -> bool
@@ -67,29 +82,29 @@ impl<'a> Value<'a> {
                 _ => false,
             },
             Value::Long(_) => match expected_type {
-                FieldType::Base(base_type) => base_type == BaseType::Long,
+                FieldType::Base(base_type) => *base_type == BaseType::Long,
                 _ => false,
             },
             Value::Float(_) => match expected_type {
-                FieldType::Base(base_type) => base_type == BaseType::Float,
+                FieldType::Base(base_type) => *base_type == BaseType::Float,
                 _ => false,
             },
             Value::Double(_) => match expected_type {
-                FieldType::Base(base_type) => base_type == BaseType::Double,
+                FieldType::Base(base_type) => *base_type == BaseType::Double,
                 _ => false,
             },
 
             Value::Object(object) => {
                 if object.kind() == ObjectKind::Array {
                     match expected_type {
-                        FieldType::Array(expected_field_type) => {
-                            let array_entry_type =
-                                object.elements_type().into_field_type(class_resolver_by_id);
-                            if let Some(array_entry_type) = array_entry_type {
-                                array_entry_type == *expected_field_type
-                            } else {
-                                false
-                            }
+                        FieldType::Array(expected_field_type) => array_elements_match_type(
+                            object.elements_type(),
+                            expected_field_type,
+                            class_resolver_by_id,
+                            class_resolver_by_name,
+                        ),
+                        FieldType::Object(expected_class_name) => {
+                            is_implemented_by_arrays(expected_class_name)
                         }
                         _ => false,
                     }
@@ -101,7 +116,7 @@ impl<'a> Value<'a> {
                             let value_class =
                                 class_resolver_by_id.find_class_by_id(object.class_id());
                             if let Some(object_class) = value_class {
-                                let expected_class = class_resolver_by_name(&expected_class_name);
+                                let expected_class = class_resolver_by_name(expected_class_name);
                                 expected_class.map_or(false, |expected_class| {
                                     object_class.is_subclass_of(expected_class)
                                 })
@@ -121,6 +136,139 @@ impl<'a> Value<'a> {
             },
         }
     }
+
+    /// Returns the value if it is an `Int`, or `None` otherwise
+    #[inline]
+    pub fn as_int(&self) -> Option<i32> {
+        match self {
+            Value::Int(int) => Some(*int),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a `Long`, or `None` otherwise
+    #[inline]
+    pub fn as_long(&self) -> Option<i64> {
+        match self {
+            Value::Long(long) => Some(*long),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a `Float`, or `None` otherwise
+    #[inline]
+    pub fn as_float(&self) -> Option<f32> {
+        match self {
+            Value::Float(float) => Some(*float),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a `Double`, or `None` otherwise
+    #[inline]
+    pub fn as_double(&self) -> Option<f64> {
+        match self {
+            Value::Double(double) => Some(*double),
+            _ => None,
+        }
+    }
+
+    /// Returns the object or array, or `None` if the value is `Null` or not an object
+    #[inline]
+    pub fn as_object(&self) -> Option<&AbstractObject<'a>> {
+        match self {
+            Value::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// Booleans are modelled as an `Int` with value 0 or 1; returns `None` for anything else
+    #[inline]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Int(0) => Some(false),
+            Value::Int(1) => Some(true),
+            _ => None,
+        }
+    }
+}
+
+/// Checks whether the elements of an array match the expected component type of an array type
+fn array_elements_match_type<'b, 'c, ResByName>(
+    elements_type: ArrayEntryType,
+    expected_component_type: &FieldType,
+    class_resolver_by_id: &impl ClassByIdResolver<'c>,
+    class_resolver_by_name: ResByName,
+) -> bool
+where
+    ResByName: FnOnce(&str) -> Option<ClassRef<'b>>,
+{
+    match (elements_type, expected_component_type) {
+        // The entries are arrays themselves, so we check them recursively
+        (elements_type @ ArrayEntryType::Array(..), FieldType::Array(expected_component_type)) => {
+            elements_type
+                .entries_elements_type()
+                .map_or(false, |entries_elements_type| {
+                    array_elements_match_type(
+                        entries_elements_type,
+                        expected_component_type,
+                        class_resolver_by_id,
+                        class_resolver_by_name,
+                    )
+                })
+        }
+        (ArrayEntryType::Array(..), FieldType::Object(expected_class_name)) => {
+            is_implemented_by_arrays(expected_class_name)
+        }
+        (ArrayEntryType::Array(..), _) => false,
+        // Arrays of objects are covariant
+        (ArrayEntryType::Object(class_id), FieldType::Object(expected_class_name)) => {
+            let elements_class = class_resolver_by_id.find_class_by_id(class_id);
+            let expected_class = class_resolver_by_name(expected_class_name);
+            match (elements_class, expected_class) {
+                (Some(elements_class), Some(expected_class)) => {
+                    elements_class.is_subclass_of(expected_class)
+                }
+                _ => false,
+            }
+        }
+        (elements_type, expected_component_type) => elements_type
+            .into_field_type(class_resolver_by_id)
+            .map_or(false, |array_entry_type| {
+                array_entry_type == *expected_component_type
+            }),
+    }
+}
+
+impl<'a> From<i32> for Value<'a> {
+    fn from(value: i32) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl<'a> From<i64> for Value<'a> {
+    fn from(value: i64) -> Self {
+        Value::Long(value)
+    }
+}
+
+impl<'a> From<f32> for Value<'a> {
+    fn from(value: f32) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl<'a> From<f64> for Value<'a> {
+    fn from(value: f64) -> Self {
+        Value::Double(value)
+    }
+}
+
+/// Booleans are modelled as an `Int` with value 0 or 1
+impl<'a> From<bool> for Value<'a> {
+    fn from(value: bool) -> Self {
+        Value::Int(value as i32)
+    }
 }
 
 /// Checks that the element at the given index is an abstract object and returns it, or an error.
@@ -169,6 +317,16 @@ pub fn expect_int_at(vec: &[Value], index: usize) -> Result<i32, VmError> {
     }
 }
 
+/// Checks that the element at the given index is a Long and returns it, or an error.
+pub fn expect_long_at(vec: &[Value], index: usize) -> Result<i64, VmError> {
+    let value = vec.get(index);
+    if let Some(Value::Long(long)) = value {
+        Ok(*long)
+    } else {
+        Err(VmError::ValidationException)
+    }
+}
+
 /// Checks that the element at the given index is a Float and returns it, or an error.
 pub fn expect_float_at(vec: &[Value], index: usize) -> Result<f32, VmError> {
     let value = vec.get(index);
@@ -188,3 +346,140 @@ pub fn expect_double_at(vec: &[Value], index: usize) -> Result<f64, VmError> {
         Err(VmError::ValidationException)
     }
 }
+
+/// Formats a `float` or `double` like Java's `Float.toString` and `Double.toString`:
+/// always with a decimal part, and in the computerized scientific notation (i.e. `1.0E10`)
+/// when the magnitude is less than 10^-3 or at least 10^7.
+pub(crate) fn format_java_floating_point<T>(value: T) -> String
+where
+    T: Into<f64> + Copy + Debug + LowerExp,
+{
+    let as_double: f64 = value.into();
+    if as_double.is_nan() {
+        return "NaN".to_string();
+    }
+    if as_double.is_infinite() {
+        return if as_double > 0.0 {
+            "Infinity"
+        } else {
+            "-Infinity"
+        }
+        .to_string();
+    }
+    let magnitude = as_double.abs();
+    if magnitude == 0.0 || (1e-3..1e7).contains(&magnitude) {
+        return format!("{value:?}");
+    }
+    let scientific = format!("{value:e}");
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .expect("scientific notation should contain an exponent");
+    if mantissa.contains('.') {
+        format!("{mantissa}E{exponent}")
+    } else {
+        format!("{mantissa}.0E{exponent}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rjvm_reader::field_type::BaseType;
+
+    use crate::{
+        array_entry_type::ArrayEntryType,
+        value::{format_java_floating_point, Value},
+        vm::{Vm, ONE_MEGABYTE},
+    };
+
+    #[test]
+    fn as_int() {
+        assert_eq!(Some(42), Value::Int(42).as_int());
+        assert_eq!(None, Value::Long(42).as_int());
+    }
+
+    #[test]
+    fn as_long() {
+        assert_eq!(Some(42), Value::Long(42).as_long());
+        assert_eq!(None, Value::Int(42).as_long());
+    }
+
+    #[test]
+    fn as_float() {
+        assert_eq!(Some(1.5), Value::Float(1.5).as_float());
+        assert_eq!(None, Value::Double(1.5).as_float());
+    }
+
+    #[test]
+    fn as_double() {
+        assert_eq!(Some(1.5), Value::Double(1.5).as_double());
+        assert_eq!(None, Value::Float(1.5).as_double());
+    }
+
+    #[test]
+    fn as_object() {
+        let mut vm = Vm::new(ONE_MEGABYTE);
+        let array = vm
+            .new_array(ArrayEntryType::Base(BaseType::Int), 1)
+            .unwrap();
+        assert_eq!(Some(&array), Value::Object(array.clone()).as_object());
+        assert_eq!(None, Value::Null.as_object());
+        assert_eq!(None, Value::Int(0).as_object());
+    }
+
+    #[test]
+    fn as_bool() {
+        assert_eq!(Some(false), Value::Int(0).as_bool());
+        assert_eq!(Some(true), Value::Int(1).as_bool());
+        assert_eq!(None, Value::Int(2).as_bool());
+        assert_eq!(None, Value::Uninitialized.as_bool());
+    }
+
+    #[test]
+    fn narrowed_to() {
+        assert_eq!(Value::Int(0), Value::Int(2).narrowed_to(&BaseType::Boolean));
+        assert_eq!(Value::Int(1), Value::Int(3).narrowed_to(&BaseType::Boolean));
+        assert_eq!(
+            Value::Int(-56),
+            Value::Int(200).narrowed_to(&BaseType::Byte)
+        );
+        assert_eq!(
+            Value::Int(65),
+            Value::Int(65601).narrowed_to(&BaseType::Char)
+        );
+        assert_eq!(
+            Value::Int(-1),
+            Value::Int(65535).narrowed_to(&BaseType::Short)
+        );
+        assert_eq!(
+            Value::Int(65601),
+            Value::Int(65601).narrowed_to(&BaseType::Int)
+        );
+        assert_eq!(
+            Value::Long(2),
+            Value::Long(2).narrowed_to(&BaseType::Boolean)
+        );
+    }
+
+    #[test]
+    fn from_rust_primitives() {
+        assert_eq!(Value::Int(42), 42i32.into());
+        assert_eq!(Value::Long(42), 42i64.into());
+        assert_eq!(Value::Float(1.5), 1.5f32.into());
+        assert_eq!(Value::Double(1.5), 1.5f64.into());
+        assert_eq!(Value::Int(0), false.into());
+        assert_eq!(Value::Int(1), true.into());
+    }
+
+    #[test]
+    fn java_floating_point_formatting() {
+        assert_eq!("1.0", format_java_floating_point(1.0f32));
+        assert_eq!("-0.0", format_java_floating_point(-0.0f64));
+        assert_eq!("0.1", format_java_floating_point(0.1f32));
+        assert_eq!("123.456", format_java_floating_point(123.456f64));
+        assert_eq!("1.0E7", format_java_floating_point(1e7f64));
+        assert_eq!("1.5E-4", format_java_floating_point(1.5e-4f32));
+        assert_eq!("NaN", format_java_floating_point(f32::NAN));
+        assert_eq!("Infinity", format_java_floating_point(f64::INFINITY));
+        assert_eq!("-Infinity", format_java_floating_point(f32::NEG_INFINITY));
+    }
+}
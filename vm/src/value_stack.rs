@@ -10,10 +10,16 @@ use crate::value::Value;
 /// Models the stack in a given call frame
 ///
 /// The java stack has a few more features over the classical push and pop,
-/// i.e. the various dup instructions, so it is modelled explicitly
+/// i.e. the various dup instructions, so it is modelled explicitly.
+///
+/// Note that in the JVM specs long and double values take two slots of the stack, and
+/// the maximum size declared in the class file counts them as such. We store them
+/// in a single [Value], so the declared maximum size is always sufficient. However,
+/// the instructions that work on slots, like `dup2`, need to take this into account.
 #[derive(Debug)]
 pub struct ValueStack<'a> {
     stack: Vec<Value<'a>>,
+    max_size: usize,
 }
 
 /// Errors returned from various stack operations
@@ -31,6 +37,7 @@ impl<'a> ValueStack<'a> {
     pub fn with_max_size(max_size: usize) -> Self {
         Self {
             stack: Vec::with_capacity(max_size),
+            max_size,
         }
     }
 
@@ -39,7 +46,7 @@ impl<'a> ValueStack<'a> {
     }
 
     pub fn push(&mut self, value: Value<'a>) -> Result<(), ValueStackError> {
-        if self.stack.len() < self.stack.capacity() {
+        if self.stack.len() < self.max_size {
             self.stack.push(value);
             Ok(())
         } else {
@@ -55,14 +62,15 @@ impl<'a> ValueStack<'a> {
 
     pub fn pop2(&mut self) -> Result<Value<'a>, ValueStackError> {
         let value = self.pop()?;
-        match value {
-            Value::Long(_) | Value::Double(_) => Ok(value),
-            _ => self.pop().map(|_| value),
+        if is_category_2(&value) {
+            Ok(value)
+        } else {
+            self.pop().map(|_| value)
         }
     }
 
     pub fn truncate(&mut self, len: usize) -> Result<(), ValueStackError> {
-        if len > self.stack.capacity() {
+        if len > self.max_size {
             Err(ValueStackError::MaximumCapacityReached)
         } else {
             self.stack.truncate(len);
@@ -100,6 +108,12 @@ impl<'a> ValueStack<'a> {
     pub fn dup_x2(&mut self) -> Result<(), ValueStackError> {
         let value1 = self.pop()?;
         let value2 = self.pop()?;
+        if is_category_2(&value2) {
+            self.push(value1.clone())?;
+            self.push(value2)?;
+            return self.push(value1);
+        }
+
         let value3 = self.pop()?;
         self.push(value1.clone())?;
         self.push(value3)?;
@@ -109,6 +123,11 @@ impl<'a> ValueStack<'a> {
 
     pub fn dup2(&mut self) -> Result<(), ValueStackError> {
         let value1 = self.pop()?;
+        if is_category_2(&value1) {
+            self.push(value1.clone())?;
+            return self.push(value1);
+        }
+
         let value2 = self.pop()?;
         self.push(value2.clone())?;
         self.push(value1.clone())?;
@@ -118,6 +137,13 @@ impl<'a> ValueStack<'a> {
 
     pub fn dup2_x1(&mut self) -> Result<(), ValueStackError> {
         let value1 = self.pop()?;
+        if is_category_2(&value1) {
+            let value2 = self.pop()?;
+            self.push(value1.clone())?;
+            self.push(value2)?;
+            return self.push(value1);
+        }
+
         let value2 = self.pop()?;
         let value3 = self.pop()?;
         self.push(value2.clone())?;
@@ -129,8 +155,31 @@ impl<'a> ValueStack<'a> {
 
     pub fn dup2_x2(&mut self) -> Result<(), ValueStackError> {
         let value1 = self.pop()?;
+        if is_category_2(&value1) {
+            let value2 = self.pop()?;
+            if is_category_2(&value2) {
+                self.push(value1.clone())?;
+                self.push(value2)?;
+                return self.push(value1);
+            }
+
+            let value3 = self.pop()?;
+            self.push(value1.clone())?;
+            self.push(value3)?;
+            self.push(value2)?;
+            return self.push(value1);
+        }
+
         let value2 = self.pop()?;
         let value3 = self.pop()?;
+        if is_category_2(&value3) {
+            self.push(value2.clone())?;
+            self.push(value1.clone())?;
+            self.push(value3)?;
+            self.push(value2)?;
+            return self.push(value1);
+        }
+
         let value4 = self.pop()?;
         self.push(value2.clone())?;
         self.push(value1.clone())?;
@@ -148,6 +197,11 @@ impl<'a> ValueStack<'a> {
     }
 }
 
+/// Long and double are "category 2" values, i.e. they take two slots in the JVM specs
+fn is_category_2(value: &Value) -> bool {
+    matches!(value, Value::Long(_) | Value::Double(_))
+}
+
 /// Allows using the [] operator
 impl<'a, I> Index<I> for ValueStack<'a>
 where
@@ -162,7 +216,10 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::{value::Value, value_stack::ValueStack};
+    use crate::{
+        value::Value,
+        value_stack::{ValueStack, ValueStackError},
+    };
 
     #[test]
     fn can_do_push_pop_and_indexing() {
@@ -295,4 +352,90 @@ mod tests {
         assert_eq!(Ok(Value::Int(1)), stack.pop());
         assert_eq!(Ok(Value::Int(2)), stack.pop());
     }
+
+    #[test]
+    fn longs_count_as_one_value_against_the_declared_maximum_size() {
+        // The class file would declare a maximum size of 4 for two longs
+        let mut stack = ValueStack::with_max_size(4);
+        stack.push(Value::Long(1)).expect("should be able to push");
+        stack.push(Value::Long(2)).expect("should be able to push");
+        stack.push(Value::Int(3)).expect("should be able to push");
+        stack.push(Value::Int(4)).expect("should be able to push");
+        assert_eq!(
+            Err(ValueStackError::MaximumCapacityReached),
+            stack.push(Value::Int(5))
+        );
+    }
+
+    #[test]
+    fn can_invoke_dup_x2_with_long() {
+        let mut stack = ValueStack::with_max_size(4);
+        stack.push(Value::Long(2)).expect("should be able to push");
+        stack.push(Value::Int(1)).expect("should be able to push");
+        stack.dup_x2().expect("should be able to dup_x2");
+        assert_eq!(3, stack.len());
+        assert_eq!(Ok(Value::Int(1)), stack.pop());
+        assert_eq!(Ok(Value::Long(2)), stack.pop());
+        assert_eq!(Ok(Value::Int(1)), stack.pop());
+    }
+
+    #[test]
+    fn can_invoke_dup2_with_long() {
+        let mut stack = ValueStack::with_max_size(4);
+        stack.push(Value::Long(1)).expect("should be able to push");
+        stack.dup2().expect("should be able to dup2");
+        assert_eq!(2, stack.len());
+        assert_eq!(Ok(Value::Long(1)), stack.pop());
+        assert_eq!(Ok(Value::Long(1)), stack.pop());
+    }
+
+    #[test]
+    fn can_invoke_dup2_x1_with_double() {
+        let mut stack = ValueStack::with_max_size(5);
+        stack.push(Value::Int(2)).expect("should be able to push");
+        stack
+            .push(Value::Double(1f64))
+            .expect("should be able to push");
+        stack.dup2_x1().expect("should be able to dup2_x1");
+        assert_eq!(3, stack.len());
+        assert_eq!(Ok(Value::Double(1f64)), stack.pop());
+        assert_eq!(Ok(Value::Int(2)), stack.pop());
+        assert_eq!(Ok(Value::Double(1f64)), stack.pop());
+    }
+
+    #[test]
+    fn can_invoke_dup2_x2_with_longs() {
+        // Value 1 is long, value 2 and 3 are ints
+        let mut stack = ValueStack::with_max_size(6);
+        stack.push(Value::Int(3)).expect("should be able to push");
+        stack.push(Value::Int(2)).expect("should be able to push");
+        stack.push(Value::Long(1)).expect("should be able to push");
+        stack.dup2_x2().expect("should be able to dup2_x2");
+        assert_eq!(4, stack.len());
+        assert_eq!(Ok(Value::Long(1)), stack.pop());
+        assert_eq!(Ok(Value::Int(2)), stack.pop());
+        assert_eq!(Ok(Value::Int(3)), stack.pop());
+        assert_eq!(Ok(Value::Long(1)), stack.pop());
+
+        // Value 1 and 2 are ints, value 3 is long
+        stack.push(Value::Long(3)).expect("should be able to push");
+        stack.push(Value::Int(2)).expect("should be able to push");
+        stack.push(Value::Int(1)).expect("should be able to push");
+        stack.dup2_x2().expect("should be able to dup2_x2");
+        assert_eq!(5, stack.len());
+        assert_eq!(Ok(Value::Int(1)), stack.pop());
+        assert_eq!(Ok(Value::Int(2)), stack.pop());
+        assert_eq!(Ok(Value::Long(3)), stack.pop());
+        assert_eq!(Ok(Value::Int(1)), stack.pop());
+        assert_eq!(Ok(Value::Int(2)), stack.pop());
+
+        // Value 1 and 2 are both long
+        stack.push(Value::Long(2)).expect("should be able to push");
+        stack.push(Value::Long(1)).expect("should be able to push");
+        stack.dup2_x2().expect("should be able to dup2_x2");
+        assert_eq!(3, stack.len());
+        assert_eq!(Ok(Value::Long(1)), stack.pop());
+        assert_eq!(Ok(Value::Long(2)), stack.pop());
+        assert_eq!(Ok(Value::Long(1)), stack.pop());
+    }
 }
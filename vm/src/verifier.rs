@@ -0,0 +1,350 @@
+use std::collections::BTreeSet;
+
+use log::debug;
+
+use rjvm_reader::{
+    class_file_method::ClassFileMethodCode, class_file_version::ClassFileVersion,
+    class_reader_error::ClassReaderError, constant_pool::ConstantPool, instruction::Instruction,
+    stack_depth::simulate_stack_depths,
+};
+
+use crate::{class_and_method::ClassAndMethod, vm_error::VmError};
+
+/// A lightweight verification of the bytecode of a method, much simpler than the one
+/// specified by the JVM specs: we only check that the branch targets are valid, that the
+/// locals accessed are within `max_locals`, and that the stack depth never exceeds `max_stack`.
+/// We do not do any type checking.
+///
+/// We also check that the `StackMapTable` has a frame for every branch target and exception
+/// handler. Since Java 7, the table is mandatory, but for older classes the JVM falls back
+/// to the verification by type inference if it is missing or invalid. We do not implement
+/// that inference: classes older than Java 7 without a valid table only get the checks on
+/// the branch targets, the locals, and the stack depth.
+pub(crate) fn verify_method(class_and_method: &ClassAndMethod) -> Result<(), VmError> {
+    let code = class_and_method
+        .method
+        .code
+        .as_ref()
+        .ok_or(VmError::ValidationException)?;
+    let verifier = Verifier {
+        code,
+        constants: &class_and_method.class.constants,
+        stack_map_table_required: class_and_method
+            .class
+            .version
+            .is_at_least(ClassFileVersion::Jdk7),
+    };
+    verifier.verify().map_err(|reason| {
+        VmError::VerificationError(
+            class_and_method.class.name.clone(),
+            class_and_method.method.name.clone(),
+            class_and_method.method.type_descriptor.clone(),
+            reason,
+        )
+    })
+}
+
+struct Verifier<'a> {
+    code: &'a ClassFileMethodCode,
+    constants: &'a ConstantPool,
+    /// If false, problems with the `StackMapTable` are tolerated
+    stack_map_table_required: bool,
+}
+
+impl<'a> Verifier<'a> {
+    fn verify(&self) -> Result<(), String> {
+        let instructions =
+            Instruction::parse_instructions(&self.code.code).map_err(|err| err.to_string())?;
+
+        for (address, instruction) in instructions.iter() {
+            self.check_local_access(*address, instruction)?;
+        }
+
+        let stack_depths =
+            simulate_stack_depths(self.code, self.constants).map_err(|err| match err {
+                ClassReaderError::InvalidClassData(reason, _) => reason,
+                err => err.to_string(),
+            })?;
+        if let Some(depth) = stack_depths
+            .iter()
+            .find(|depth| depth.after > self.code.max_stack as usize)
+        {
+            return Err(format!(
+                "stack depth {} exceeds max_stack {} at address {}",
+                depth.after, self.code.max_stack, depth.address
+            ));
+        }
+
+        if let Err(reason) = self.check_stack_map_table(&instructions) {
+            if self.stack_map_table_required {
+                return Err(reason);
+            }
+            debug!("{reason}; ignoring the StackMapTable, since this class version does not require it");
+        }
+        Ok(())
+    }
+
+    fn check_stack_map_table(&self, instructions: &[(usize, Instruction)]) -> Result<(), String> {
+        let mut frames_required: BTreeSet<usize> = BTreeSet::new();
+        for (address, instruction) in instructions {
+            let jump_targets = self
+                .code
+                .successors_of(*address, instruction, None)
+                .map_err(|err| err.to_string())?;
+            frames_required.extend(jump_targets);
+        }
+        frames_required.extend(
+            self.code
+                .exception_table
+                .entries()
+                .map(|entry| entry.handler_pc.0 as usize),
+        );
+
+        let Some(stack_map_table) = &self.code.stack_map_table else {
+            return if frames_required.is_empty() {
+                Ok(())
+            } else {
+                Err("missing StackMapTable".to_string())
+            };
+        };
+
+        let frame_addresses: BTreeSet<usize> =
+            stack_map_table.frame_addresses().into_iter().collect();
+        let instruction_addresses: BTreeSet<usize> =
+            instructions.iter().map(|(address, _)| *address).collect();
+        if let Some(address) = frame_addresses.difference(&instruction_addresses).next() {
+            return Err(format!(
+                "stack map frame at address {address}, which is not the start of an instruction"
+            ));
+        }
+        if let Some(address) = frames_required.difference(&frame_addresses).next() {
+            return Err(format!("missing stack map frame for address {address}"));
+        }
+        Ok(())
+    }
+
+    fn check_local_access(&self, address: usize, instruction: &Instruction) -> Result<(), String> {
+        let (index, size) = match instruction {
+            Instruction::Aload(index)
+            | Instruction::Astore(index)
+            | Instruction::Fload(index)
+            | Instruction::Fstore(index)
+            | Instruction::Iload(index)
+            | Instruction::Istore(index)
+            | Instruction::Iinc(index, _)
+            | Instruction::Ret(index) => (*index as usize, 1),
+            Instruction::Dload(index)
+            | Instruction::Dstore(index)
+            | Instruction::Lload(index)
+            | Instruction::Lstore(index) => (*index as usize, 2),
+            Instruction::Aload_0
+            | Instruction::Astore_0
+            | Instruction::Fload_0
+            | Instruction::Fstore_0
+            | Instruction::Iload_0
+            | Instruction::Istore_0 => (0, 1),
+            Instruction::Aload_1
+            | Instruction::Astore_1
+            | Instruction::Fload_1
+            | Instruction::Fstore_1
+            | Instruction::Iload_1
+            | Instruction::Istore_1 => (1, 1),
+            Instruction::Aload_2
+            | Instruction::Astore_2
+            | Instruction::Fload_2
+            | Instruction::Fstore_2
+            | Instruction::Iload_2
+            | Instruction::Istore_2 => (2, 1),
+            Instruction::Aload_3
+            | Instruction::Astore_3
+            | Instruction::Fload_3
+            | Instruction::Fstore_3
+            | Instruction::Iload_3
+            | Instruction::Istore_3 => (3, 1),
+            Instruction::Dload_0
+            | Instruction::Dstore_0
+            | Instruction::Lload_0
+            | Instruction::Lstore_0 => (0, 2),
+            Instruction::Dload_1
+            | Instruction::Dstore_1
+            | Instruction::Lload_1
+            | Instruction::Lstore_1 => (1, 2),
+            Instruction::Dload_2
+            | Instruction::Dstore_2
+            | Instruction::Lload_2
+            | Instruction::Lstore_2 => (2, 2),
+            Instruction::Dload_3
+            | Instruction::Dstore_3
+            | Instruction::Lload_3
+            | Instruction::Lstore_3 => (3, 2),
+            _ => return Ok(()),
+        };
+        if index + size > self.code.max_locals as usize {
+            Err(format!(
+                "access to local {index} exceeds max_locals {} at address {address}",
+                self.code.max_locals
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rjvm_reader::{
+        class_file_method::ClassFileMethodCode,
+        constant_pool::ConstantPool,
+        exception_table::{ExceptionTable, ExceptionTableEntry},
+        program_counter::ProgramCounter,
+    };
+
+    use crate::verifier::Verifier;
+
+    fn verify(max_stack: u16, max_locals: u16, code: Vec<u8>) -> Result<(), String> {
+        verify_with_exception_table(max_stack, max_locals, code, ExceptionTable::default())
+    }
+
+    fn verify_with_exception_table(
+        max_stack: u16,
+        max_locals: u16,
+        bytecode: Vec<u8>,
+        exception_table: ExceptionTable,
+    ) -> Result<(), String> {
+        let mut code = ClassFileMethodCode::default();
+        code.max_stack = max_stack;
+        code.max_locals = max_locals;
+        code.code = bytecode;
+        code.exception_table = exception_table;
+        let constants = ConstantPool::new();
+        Verifier {
+            code: &code,
+            constants: &constants,
+            stack_map_table_required: false,
+        }
+        .verify()
+    }
+
+    #[test]
+    fn valid_method() {
+        assert_eq!(
+            Ok(()),
+            verify(
+                2,
+                1,
+                vec![
+                    0x1a, // 0: iload_0
+                    0x99, 0x00, 0x07, // 1: ifeq 8
+                    0x1a, // 4: iload_0
+                    0x04, // 5: iconst_1
+                    0x60, // 6: iadd
+                    0xac, // 7: ireturn
+                    0x03, // 8: iconst_0
+                    0xac, // 9: ireturn
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn branch_outside_of_code_is_rejected() {
+        assert_eq!(
+            Err("jump to address 100, which is not the start of an instruction".to_string()),
+            verify(
+                1,
+                1,
+                vec![
+                    0x1a, // 0: iload_0
+                    0x99, 0x00, 0x63, // 1: ifeq 100
+                    0xb1, // 4: return
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn branch_in_the_middle_of_an_instruction_is_rejected() {
+        assert_eq!(
+            Err("jump to address 2, which is not the start of an instruction".to_string()),
+            verify(
+                1,
+                1,
+                vec![
+                    0x1a, // 0: iload_0
+                    0x99, 0x00, 0x01, // 1: ifeq 2
+                    0xb1, // 4: return
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn exceeding_max_stack_is_rejected() {
+        assert_eq!(
+            Err("stack depth 2 exceeds max_stack 1 at address 1".to_string()),
+            verify(
+                1,
+                0,
+                vec![
+                    0x04, // 0: iconst_1
+                    0x04, // 1: iconst_1
+                    0x60, // 2: iadd
+                    0xac, // 3: ireturn
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn stack_underflow_is_rejected() {
+        assert_eq!(
+            Err("stack underflow at address 0".to_string()),
+            verify(1, 0, vec![0xac]) // ireturn
+        );
+    }
+
+    #[test]
+    fn exceeding_max_locals_is_rejected() {
+        assert_eq!(
+            Err("access to local 0 exceeds max_locals 1 at address 0".to_string()),
+            verify(
+                2,
+                1,
+                vec![
+                    0x1e, // 0: lload_0
+                    0xad, // 1: lreturn
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn falling_off_the_end_of_the_code_is_rejected() {
+        assert_eq!(
+            Err("execution can continue past the end of the code".to_string()),
+            verify(1, 0, vec![0x00]) // nop
+        );
+    }
+
+    #[test]
+    fn exception_handlers_start_with_the_exception_on_the_stack() {
+        let exception_table = ExceptionTable::new(vec![ExceptionTableEntry {
+            range: ProgramCounter(0)..ProgramCounter(1),
+            handler_pc: ProgramCounter(2),
+            catch_class: None,
+        }]);
+        assert_eq!(
+            Ok(()),
+            verify_with_exception_table(
+                1,
+                0,
+                vec![
+                    0x00, // 0: nop
+                    0xb1, // 1: return
+                    0xbf, // 2: athrow
+                ],
+                exception_table
+            )
+        );
+    }
+}
@@ -1,28 +1,50 @@
-use std::{collections::HashMap, string::ToString};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+    path::{Path, PathBuf},
+    string::ToString,
+};
 
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use typed_arena::Arena;
 
-use rjvm_reader::type_conversion::ToUsizeSafe;
+use rjvm_reader::{
+    class_file_field::FieldConstantValue, class_file_method::ClassFileMethod,
+    class_reader::ClassReaderLimits, field_flags::FieldFlags, field_type::FieldType,
+    type_conversion::ToUsizeSafe,
+};
 
 use crate::{
     abstract_object::{AbstractObject, ObjectKind},
     array::Array,
     array_entry_type::ArrayEntryType,
-    call_frame::MethodCallResult,
+    call_frame::{
+        CallFrame, CallSite, ClassReferenceSite, FieldSite, InlineCacheEntry, MethodCallResult,
+        ResolvedField,
+    },
     call_stack::CallStack,
     class::{ClassId, ClassRef},
     class_and_method::ClassAndMethod,
     class_manager::{ClassManager, ResolvedClass},
     class_path::ClassPathParseError,
     class_resolver_by_id::ClassByIdResolver,
-    exceptions::MethodCallFailed,
-    gc::ObjectAllocator,
+    exceptions::{JavaException, MethodCallFailed},
+    gc::{GcOutcome, GcStats, HeapUsage, ObjectAllocator},
+    instruction_hook::{InstructionContext, InstructionHook, StepAction},
+    java_objects_creation::{
+        allocate_java_lang_class_object, allocate_main_thread_object,
+        extract_str_from_java_lang_string, extract_utf16_from_java_lang_string,
+        new_java_exception_object, new_java_exception_object_with_message,
+        new_java_lang_string_object,
+    },
     native_methods_impl::array_copy,
-    native_methods_registry::NativeMethodsRegistry,
+    native_methods_registry::{NativeCallback, NativeMethodsRegistry},
+    object::Object,
     stack_trace_element::StackTraceElement,
-    value::Value,
+    value::{format_java_floating_point, Value},
+    verifier::verify_method,
     vm_error::VmError,
+    vm_event::{VmEvent, VmEventListener},
 };
 
 /// An instance of the virtual machine. Single-threaded, can execute one method (generally `main`).
@@ -36,11 +58,38 @@ pub struct Vm<'a> {
     /// Allocated call stacks
     call_stacks: Arena<CallStack<'a>>,
 
+    /// Call stacks that have been released and can be reused by [allocate_call_stack]
+    released_call_stacks: Vec<*mut CallStack<'a>>,
+
     /// To model static fields, we will create one special instance of each class
     /// and we will store it in this map. This is a bit hacky, and wastes memory
     /// because we will allocate space for non-static fields, but it works easily!
     statics: HashMap<ClassId, AbstractObject<'a>>,
 
+    /// The instances of `java.lang.Class` created for each class. We need to keep
+    /// exactly one per class, since code can compare them by reference.
+    class_objects: HashMap<ClassId, AbstractObject<'a>>,
+
+    /// Similar to [class_objects], but for arrays and primitive types, which do not have
+    /// a [ClassId] and thus are keyed by name
+    array_and_primitive_class_objects: HashMap<String, AbstractObject<'a>>,
+
+    /// The instances of `java.lang.String` interned so far, keyed by their utf-16 content. They
+    /// are the string literals loaded by `ldc` and those on which `String.intern` was invoked.
+    interned_strings: HashMap<Vec<u16>, AbstractObject<'a>>,
+
+    /// The instance of `java.lang.Thread` representing the only thread we support,
+    /// created the first time it is requested
+    main_thread: Option<AbstractObject<'a>>,
+
+    /// Values to assign to static fields, keyed by class name, applied right after the
+    /// class is initialized. See [set_static_before_run].
+    static_overrides: HashMap<String, Vec<(String, Value<'a>)>>,
+
+    /// Objects held by rust code that must survive a garbage collection, for example
+    /// while invoking a constructor. See [with_temporary_root].
+    temporary_roots: Vec<AbstractObject<'a>>,
+
     /// Stores native methods
     pub native_methods_registry: NativeMethodsRegistry<'a>,
 
@@ -56,6 +105,99 @@ pub struct Vm<'a> {
     /// Since we do not have I/O, we have a fake native method that does a println.
     /// To check in the tests what the java bytecode printed, we store it here.
     pub printed: Vec<Value<'a>>,
+
+    /// How much the call frames log about the code being executed
+    debug_verbosity: DebugVerbosity,
+
+    /// Whether methods are verified before their first execution. See [set_verification].
+    verification_enabled: bool,
+
+    /// Methods that have already passed verification, identified by their address,
+    /// which is stable since classes are never deallocated
+    verified_methods: HashSet<*const ClassFileMethod>,
+
+    /// Inline caches of the virtual call sites executed so far
+    pub(crate) inline_caches: HashMap<CallSite, InlineCacheEntry<'a>>,
+
+    /// Field references resolved so far, to avoid looking them up again at each access
+    pub(crate) resolved_fields: HashMap<FieldSite, ResolvedField<'a>>,
+
+    /// Classes referenced by `instanceof` and `checkcast` resolved so far
+    pub(crate) resolved_classes: HashMap<ClassReferenceSite, ClassRef<'a>>,
+
+    /// How many times a class referenced by `instanceof` or `checkcast` was not found in
+    /// [resolved_classes] and had to be looked up by name
+    pub(crate) class_reference_lookups: usize,
+
+    /// Callbacks invoked for each [VmEvent]. See [subscribe_to_events].
+    event_listeners: Vec<VmEventListener<'a>>,
+
+    /// The last exception for which we have emitted [VmEvent::ExceptionThrown], to avoid
+    /// emitting it again for each frame that the exception unwinds. It is a gc root, so that
+    /// the reference stays valid while the exception unwinds.
+    last_reported_exception: Option<AbstractObject<'a>>,
+
+    /// Callback invoked before each instruction. See [set_instruction_hook].
+    instruction_hook: Option<InstructionHook<'a>>,
+
+    /// Where `Throwable.printStackTrace` writes; stderr by default. See [set_error_output].
+    pub(crate) error_output: Box<dyn Write + 'a>,
+
+    /// Depth of the frame in which the hook last requested [StepAction::StepOver];
+    /// the hook is not invoked for the frames deeper than it
+    step_over_depth: Option<usize>,
+
+    /// Maximum number of frames of a call stack. See [with_max_call_stack_depth].
+    max_call_stack_depth: usize,
+
+    /// True while creating a `StackOverflowError`, whose constructor needs to push
+    /// up to [STACK_OVERFLOW_ERROR_EXTRA_FRAMES] frames past [max_call_stack_depth]
+    creating_stack_overflow_error: bool,
+
+    /// Classes that have been loaded, but whose static initialization has not run yet:
+    /// the ones just loaded, and the ones kept by [reset]. They are initialized the first
+    /// time they are resolved by [get_or_resolve_class].
+    classes_to_initialize: HashSet<ClassId>,
+}
+
+/// Controls how much the execution of the bytecode is logged, at the debug level.
+/// Each level includes everything logged by the previous ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DebugVerbosity {
+    /// Nothing is logged
+    Off,
+    /// Logs the start and the end of the execution of each method
+    FrameEntry,
+    /// Also logs each instruction, before executing it
+    Instruction,
+    /// Also logs the whole stack and locals, before each instruction
+    #[default]
+    Full,
+}
+
+/// How to interpret the maximum memory given when creating a [Vm]. Since our garbage
+/// collector splits the memory into two halves, only one of which is used at any time,
+/// the usable heap is half of the total memory allocated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MemorySizing {
+    /// The given memory is the total allocated, so only half of it is usable heap
+    #[default]
+    Total,
+    /// The given memory is the usable heap, so twice that amount will be allocated
+    UsableHeap,
+}
+
+/// How the memory of the heap is managed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AllocationStrategy {
+    /// Objects are allocated in one half of the memory, and a copying garbage collector
+    /// moves the reachable ones to the other half when it is full
+    #[default]
+    SemiSpaceCopying,
+    /// Objects are allocated in the whole memory and garbage is never collected, so that
+    /// the execution fails once the memory is full. Useful for short runs, since they can
+    /// use twice the heap of [AllocationStrategy::SemiSpaceCopying] with the same memory.
+    BumpOnly,
 }
 
 pub const ONE_MEGABYTE: usize = 1024 * 1024;
@@ -63,6 +205,40 @@ const DEFAULT_MAX_MB_OF_MEMORY: usize = 100;
 pub const DEFAULT_MAX_MEMORY: usize = 100 * ONE_MEGABYTE;
 pub const DEFAULT_MAX_MEMORY_MB_STR: &str = const_format::formatcp!("{}", DEFAULT_MAX_MB_OF_MEMORY);
 
+/// Since we execute the bytecode recursively, each frame of the java call stack also uses
+/// some of the native one: about 2 KB in release builds, but more than 80 KB in debug builds.
+pub const NATIVE_STACK_BYTES_PER_FRAME: usize = if cfg!(debug_assertions) {
+    96 * 1024
+} else {
+    4 * 1024
+};
+
+/// Similar to what the JVM allows with its default stack size. The vm must be executed in a
+/// thread with a large enough native stack: see [native_stack_size_for_call_stack_depth].
+pub const DEFAULT_MAX_CALL_STACK_DEPTH: usize = 10_000;
+
+/// How many frames past the maximum depth can be used to create a `StackOverflowError`
+const STACK_OVERFLOW_ERROR_EXTRA_FRAMES: usize = 32;
+
+/// Returns the size of the native stack required to execute a call stack of the given depth
+pub const fn native_stack_size_for_call_stack_depth(max_call_stack_depth: usize) -> usize {
+    max_call_stack_depth
+        .saturating_add(STACK_OVERFLOW_ERROR_EXTRA_FRAMES)
+        .saturating_mul(NATIVE_STACK_BYTES_PER_FRAME)
+        .saturating_add(ONE_MEGABYTE)
+}
+
+/// Environment variable that points to the `rt.jar` used by [Vm::with_bundled_runtime]
+pub const RUNTIME_JAR_ENV_VAR: &str = "RJVM_RUNTIME_JAR";
+
+/// Returns the path of the `rt.jar` given the value of the [RUNTIME_JAR_ENV_VAR] environment
+/// variable, failing if it is not set
+pub fn configured_runtime_jar(env_value: Option<String>) -> Result<String, ClassPathParseError> {
+    env_value
+        .filter(|path| !path.is_empty())
+        .ok_or(ClassPathParseError::RuntimeNotConfigured)
+}
+
 impl<'a> ClassByIdResolver<'a> for Vm<'a> {
     fn find_class_by_id(&self, class_id: ClassId) -> Option<ClassRef<'a>> {
         self.class_manager.find_class_by_id(class_id)
@@ -72,49 +248,480 @@ impl<'a> ClassByIdResolver<'a> for Vm<'a> {
 impl<'a> Vm<'a> {
     pub fn new(max_memory: usize) -> Self {
         info!("Creating new VM with maximum memory {}", max_memory);
+        Self::with_object_allocator(ObjectAllocator::with_maximum_memory(max_memory))
+    }
+
+    /// Creates a new VM, interpreting the given maximum memory as specified by [MemorySizing]
+    pub fn with_memory_sizing(
+        max_memory: usize,
+        memory_sizing: MemorySizing,
+    ) -> Result<Self, VmError> {
+        Self::with_allocation_strategy(
+            max_memory,
+            memory_sizing,
+            AllocationStrategy::SemiSpaceCopying,
+        )
+    }
+
+    /// Creates a new VM that manages the heap with the given [AllocationStrategy]
+    pub fn with_allocation_strategy(
+        max_memory: usize,
+        memory_sizing: MemorySizing,
+        allocation_strategy: AllocationStrategy,
+    ) -> Result<Self, VmError> {
+        info!(
+            "Creating new VM with maximum memory {} ({:?}, {:?})",
+            max_memory, memory_sizing, allocation_strategy
+        );
+        let object_allocator = match (allocation_strategy, memory_sizing) {
+            (AllocationStrategy::SemiSpaceCopying, MemorySizing::Total) => {
+                ObjectAllocator::with_maximum_memory(max_memory)
+            }
+            (AllocationStrategy::SemiSpaceCopying, MemorySizing::UsableHeap) => {
+                let total_memory = max_memory
+                    .checked_mul(2)
+                    .ok_or(VmError::MaximumMemoryTooLarge(max_memory))?;
+                ObjectAllocator::with_maximum_memory(total_memory)
+            }
+            // Without the collector, all the memory is usable heap
+            (AllocationStrategy::BumpOnly, _) => ObjectAllocator::bump_only(max_memory),
+        };
+        Ok(Self::with_object_allocator(object_allocator))
+    }
+
+    fn with_object_allocator(object_allocator: ObjectAllocator<'a>) -> Self {
         let mut result = Self {
             class_manager: Default::default(),
-            object_allocator: ObjectAllocator::with_maximum_memory(max_memory),
+            object_allocator,
             call_stacks: Arena::new(),
+            released_call_stacks: Vec::new(),
             statics: Default::default(),
+            class_objects: Default::default(),
+            array_and_primitive_class_objects: Default::default(),
+            interned_strings: Default::default(),
+            main_thread: None,
+            static_overrides: Default::default(),
+            temporary_roots: Vec::new(),
             native_methods_registry: Default::default(),
             throwable_call_stacks: Default::default(),
             printed: Vec::new(),
+            debug_verbosity: Default::default(),
+            verification_enabled: false,
+            verified_methods: Default::default(),
+            inline_caches: Default::default(),
+            resolved_fields: Default::default(),
+            resolved_classes: Default::default(),
+            class_reference_lookups: 0,
+            event_listeners: Vec::new(),
+            last_reported_exception: None,
+            instruction_hook: None,
+            error_output: Box::new(std::io::stderr()),
+            step_over_depth: None,
+            max_call_stack_depth: DEFAULT_MAX_CALL_STACK_DEPTH,
+            creating_stack_overflow_error: false,
+            classes_to_initialize: Default::default(),
         };
         crate::native_methods_impl::register_natives(&mut result.native_methods_registry);
         result
     }
 
+    /// Sets how much the execution of the bytecode will be logged
+    pub fn with_debug_verbosity(mut self, debug_verbosity: DebugVerbosity) -> Self {
+        self.debug_verbosity = debug_verbosity;
+        self
+    }
+
+    /// Sets the limits enforced when reading class files, to reject early those crafted
+    /// to exhaust resources. Classes exceeding them fail to load with a [VmError::ClassLoadingError].
+    pub fn with_class_reader_limits(mut self, limits: ClassReaderLimits) -> Self {
+        self.class_manager.set_class_reader_limits(limits);
+        self
+    }
+
+    /// Sets the maximum number of frames of a call stack. Invoking a method when the
+    /// limit is reached throws a `java.lang.StackOverflowError`.
+    pub fn with_max_call_stack_depth(mut self, max_call_stack_depth: usize) -> Self {
+        self.max_call_stack_depth = max_call_stack_depth;
+        self
+    }
+
+    pub fn debug_verbosity(&self) -> DebugVerbosity {
+        self.debug_verbosity
+    }
+
+    /// Enables or disables the verification of the bytecode of each method, done the
+    /// first time the method is executed. Invalid methods, for example with a branch that
+    /// does not land on an instruction, will throw a `java.lang.VerifyError`.
+    /// Disabled by default.
+    pub fn set_verification(&mut self, enabled: bool) {
+        self.verification_enabled = enabled;
+    }
+
+    /// Registers a callback that will be invoked for every [VmEvent] emitted while
+    /// running code, such as classes being loaded or methods being invoked
+    pub fn subscribe_to_events(&mut self, listener: impl FnMut(&VmEvent) + 'a) {
+        self.event_listeners.push(Box::new(listener));
+    }
+
+    /// Registers a callback that will be invoked before executing each instruction,
+    /// replacing any previous one. The returned [StepAction] controls whether the
+    /// hook is invoked for the instructions of the methods called by the current one,
+    /// which allows building a step debugger.
+    pub fn set_instruction_hook(
+        &mut self,
+        hook: impl FnMut(&InstructionContext) -> StepAction + 'a,
+    ) {
+        self.instruction_hook = Some(Box::new(hook));
+        self.step_over_depth = None;
+    }
+
+    /// Replaces the output to which `Throwable.printStackTrace` writes, which is stderr
+    /// by default
+    pub fn set_error_output(&mut self, output: impl Write + 'a) {
+        self.error_output = Box::new(output);
+    }
+
+    /// Removes the hook registered via [set_instruction_hook]
+    pub fn clear_instruction_hook(&mut self) {
+        self.instruction_hook = None;
+        self.step_over_depth = None;
+    }
+
+    pub(crate) fn has_instruction_hook(&self) -> bool {
+        self.instruction_hook.is_some()
+    }
+
+    /// Invokes the instruction hook, unless we are stepping over a call made
+    /// by a frame shallower than the one executing the instruction
+    pub(crate) fn notify_instruction_hook(&mut self, context: &InstructionContext) {
+        if let Some(step_over_depth) = self.step_over_depth {
+            if context.depth > step_over_depth {
+                return;
+            }
+        }
+        if let Some(hook) = self.instruction_hook.as_mut() {
+            self.step_over_depth = match hook(context) {
+                StepAction::StepInto => None,
+                StepAction::StepOver => Some(context.depth),
+            };
+        }
+    }
+
+    /// Notifies the listeners of an event. The event is built lazily, so that
+    /// we do not pay any cost if nobody is listening.
+    pub(crate) fn emit_event(&mut self, build_event: impl FnOnce() -> VmEvent) {
+        if self.event_listeners.is_empty() {
+            return;
+        }
+        let event = build_event();
+        for listener in self.event_listeners.iter_mut() {
+            listener(&event);
+        }
+    }
+
+    /// Emits [VmEvent::ExceptionThrown], unless we have already done it for the same
+    /// exception while it was unwinding the stack
+    pub(crate) fn report_thrown_exception(&mut self, exception: &JavaException<'a>) {
+        if self.last_reported_exception.as_ref() == Some(&exception.0) {
+            return;
+        }
+        self.last_reported_exception = Some(exception.0.clone());
+        let class_name = self
+            .get_class_by_id(exception.0.class_id())
+            .map(|class| class.name.clone())
+            .unwrap_or_default();
+        self.emit_event(|| VmEvent::ExceptionThrown { class: class_name });
+    }
+
+    /// Marks an exception as caught, so that throwing it again will emit a new event
+    pub(crate) fn report_caught_exception(&mut self) {
+        self.last_reported_exception = None;
+    }
+
+    /// Registers a custom native method, for example to provide host functions to the java code.
+    /// Fails if the method already has a native implementation, including the built-in ones.
+    pub fn register_native(
+        &mut self,
+        class_name: &str,
+        method_name: &str,
+        type_descriptor: &str,
+        callback: NativeCallback<'a>,
+    ) -> Result<(), VmError> {
+        if self
+            .native_methods_registry
+            .get(class_name, method_name, type_descriptor)
+            .is_some()
+        {
+            return Err(VmError::NativeMethodAlreadyRegistered(
+                class_name.to_string(),
+                method_name.to_string(),
+                type_descriptor.to_string(),
+            ));
+        }
+        self.native_methods_registry
+            .register(class_name, method_name, type_descriptor, callback);
+        Ok(())
+    }
+
     pub(crate) fn get_static_instance(&self, class_id: ClassId) -> Option<AbstractObject<'a>> {
         self.statics.get(&class_id).cloned()
     }
 
+    /// Returns the instance of `java.lang.Class` for the given class, creating it
+    /// the first time it is requested
+    pub fn get_or_create_class_object(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class: ClassRef<'a>,
+    ) -> Result<AbstractObject<'a>, MethodCallFailed<'a>> {
+        if let Some(class_object) = self.class_objects.get(&class.id) {
+            return Ok(class_object.clone());
+        }
+        let class_object = allocate_java_lang_class_object(self, call_stack, &class.name)?;
+        self.class_objects.insert(class.id, class_object.clone());
+        Ok(class_object)
+    }
+
+    /// Returns the interned instance of `java.lang.String` with the given content, creating
+    /// it the first time it is requested. String literals with the same content must be
+    /// the same object, since code can compare them by reference.
+    pub fn get_or_create_interned_string(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        content: &str,
+    ) -> Result<AbstractObject<'a>, MethodCallFailed<'a>> {
+        let key: Vec<u16> = content.encode_utf16().collect();
+        if let Some(string) = self.interned_strings.get(&key) {
+            return Ok(string.clone());
+        }
+        let string = new_java_lang_string_object(self, call_stack, content)?;
+        self.interned_strings.insert(key, string.clone());
+        Ok(string)
+    }
+
+    /// Implements `String.intern`: returns the interned instance with the same content as the
+    /// given string, or interns the given one if there is none
+    pub(crate) fn intern_string(
+        &mut self,
+        string: AbstractObject<'a>,
+    ) -> Result<AbstractObject<'a>, VmError> {
+        // Keyed on the utf-16 code units, since Java strings can contain lone surrogates
+        let content = extract_utf16_from_java_lang_string(self, &string)?;
+        Ok(self
+            .interned_strings
+            .entry(content)
+            .or_insert(string)
+            .clone())
+    }
+
+    /// Renders a value the way Java would print it: numbers as in `String.valueOf`,
+    /// strings with their content, and other objects as `Object.toString` does by default,
+    /// i.e. `<class name>@<identity hash code in hex>`
+    pub fn format_value(&self, value: &Value<'a>) -> String {
+        match value {
+            Value::Uninitialized => "<uninitialized>".to_string(),
+            Value::Int(int) => int.to_string(),
+            Value::Long(long) => long.to_string(),
+            Value::Float(float) => format_java_floating_point(*float),
+            Value::Double(double) => format_java_floating_point(*double),
+            Value::Null => "null".to_string(),
+            Value::Object(object) => {
+                if object.kind() == ObjectKind::Object {
+                    if let Ok(string) = extract_str_from_java_lang_string(self, object) {
+                        return string;
+                    }
+                }
+                format!(
+                    "{}@{:x}",
+                    self.java_class_name_of(object),
+                    object.identity_hash_code()
+                )
+            }
+        }
+    }
+
+    /// Returns the name and the current value of all the instance fields of the given object,
+    /// including the inherited ones, in declaration order starting from the base class.
+    /// Static fields are not included, since they are not part of the object's state.
+    pub fn object_fields(
+        &self,
+        object: &AbstractObject<'a>,
+    ) -> Result<Vec<(&'a str, Value<'a>)>, VmError> {
+        if object.kind() != ObjectKind::Object {
+            return Err(VmError::ValidationException);
+        }
+        let class = self.get_class_by_id(object.class_id())?;
+        Ok(class
+            .all_fields()
+            .enumerate()
+            .filter(|(_, field)| !field.flags.contains(FieldFlags::STATIC))
+            .map(|(index, field)| (field.name.as_str(), object.get_field(class, index)))
+            .collect())
+    }
+
+    /// Returns the class name in the form of `Class.getName`, e.g. `java.lang.String`
+    /// or `[Ljava.lang.String;`
+    fn java_class_name_of(&self, object: &AbstractObject<'a>) -> String {
+        let internal_name = match object.kind() {
+            ObjectKind::Object => self
+                .find_class_by_id(object.class_id())
+                .map(|class| class.name.clone()),
+            ObjectKind::Array => self.array_class_name(&object.elements_type()),
+        };
+        internal_name
+            .unwrap_or_else(|| "<unknown class>".to_string())
+            .replace('/', ".")
+    }
+
+    /// Returns the internal name of the class of the arrays with the given elements, such as
+    /// `[I`, `[Ljava/lang/String;` or `[[I`. `None` is returned for elements of an unknown class.
+    pub(crate) fn array_class_name(&self, elements_type: &ArrayEntryType) -> Option<String> {
+        elements_type
+            .clone()
+            .into_field_type(self)
+            .map(|elements_type| FieldType::Array(Box::new(elements_type)).descriptor())
+    }
+
+    /// Returns the instance of `java.lang.Thread` representing the main thread, creating it
+    /// the first time it is requested
+    pub fn get_or_create_main_thread(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+    ) -> Result<AbstractObject<'a>, MethodCallFailed<'a>> {
+        if let Some(main_thread) = &self.main_thread {
+            return Ok(main_thread.clone());
+        }
+        let main_thread = allocate_main_thread_object(self, call_stack)?;
+        self.main_thread = Some(main_thread.clone());
+        Ok(main_thread)
+    }
+
+    /// Returns the instance of `java.lang.Class` for the given array or primitive type, creating it
+    /// the first time it is requested
+    pub fn get_or_create_array_or_primitive_class_object(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        name: &str,
+    ) -> Result<AbstractObject<'a>, MethodCallFailed<'a>> {
+        if let Some(class_object) = self.array_and_primitive_class_objects.get(name) {
+            return Ok(class_object.clone());
+        }
+        let class_object = allocate_java_lang_class_object(self, call_stack, name)?;
+        self.array_and_primitive_class_objects
+            .insert(name.to_string(), class_object.clone());
+        Ok(class_object)
+    }
+
     pub fn append_class_path(&mut self, class_path: &str) -> Result<(), ClassPathParseError> {
         self.class_manager.append_class_path(class_path)
     }
 
+    /// Adds the Java runtime classes to the class path, so that embedders do not need to know
+    /// where the `rt.jar` is. Its path is read from the [RUNTIME_JAR_ENV_VAR] environment
+    /// variable, see [configured_runtime_jar].
+    pub fn with_bundled_runtime(self) -> Result<Self, ClassPathParseError> {
+        let path = configured_runtime_jar(std::env::var(RUNTIME_JAR_ENV_VAR).ok())?;
+        self.with_runtime(&path)
+    }
+
+    /// Adds the given `rt.jar` to the class path
+    pub fn with_runtime(mut self, path: &str) -> Result<Self, ClassPathParseError> {
+        if !Path::new(path).is_file() {
+            return Err(ClassPathParseError::RuntimeNotFound(path.to_string()));
+        }
+        self.append_class_path(path)?;
+        Ok(self)
+    }
+
+    /// Adds a directory to the class path, for which the path of the class files is
+    /// computed by the given function rather than using the standard layout, i.e.
+    /// one directory per package and the `.class` extension. The function receives the
+    /// class name, such as `java/lang/Object`, and returns the path relative to the directory.
+    pub fn append_class_path_directory(
+        &mut self,
+        path: &str,
+        file_name_mapping: impl Fn(&str) -> PathBuf + 'static,
+    ) -> Result<(), ClassPathParseError> {
+        self.class_manager
+            .append_class_path_directory(path, Box::new(file_name_mapping))
+    }
+
     pub fn get_or_resolve_class(
         &mut self,
         stack: &mut CallStack<'a>,
         class_name: &str,
     ) -> Result<ClassRef<'a>, MethodCallFailed<'a>> {
-        let class = self.class_manager.get_or_resolve_class(class_name)?;
+        let class = self.get_or_load_class(class_name)?;
+        self.initialize_if_pending(stack, class)?;
+        Ok(class)
+    }
+
+    /// Like [get_or_resolve_class], but does not initialize the class, which will happen the
+    /// first time it gets resolved. For example, `ldc` of a class constant must not run
+    /// the static initializer of the class.
+    pub(crate) fn get_or_load_class(
+        &mut self,
+        class_name: &str,
+    ) -> Result<ClassRef<'a>, MethodCallFailed<'a>> {
+        let class = self.class_manager.get_or_resolve_class(class_name);
+        for (name, source) in self.class_manager.take_newly_loaded_classes() {
+            self.emit_event(|| VmEvent::ClassLoaded { name, source });
+        }
+        let class = class?;
         if let ResolvedClass::NewClass(classes_to_init) = &class {
-            for class_to_init in classes_to_init.to_initialize.iter() {
-                self.init_class(stack, class_to_init)?;
-            }
+            self.classes_to_initialize
+                .extend(classes_to_init.to_initialize.iter().map(|class| class.id));
         }
         Ok(class.get_class())
     }
 
+    /// Resolves and initializes the given classes, unless already loaded, so that the cost
+    /// of loading them is paid up front, rather than the first time they are used
+    pub fn preload_classes(
+        &mut self,
+        stack: &mut CallStack<'a>,
+        class_names: &[&str],
+    ) -> Result<(), MethodCallFailed<'a>> {
+        for class_name in class_names {
+            self.get_or_resolve_class(stack, class_name)?;
+        }
+        Ok(())
+    }
+
+    /// Initializes the given class, if it is waiting for its initialization, after its
+    /// superclasses and interfaces. A class can be resolved after some of its ancestors were
+    /// kept by [reset], so they need to be checked even when the class itself is new.
+    fn initialize_if_pending(
+        &mut self,
+        stack: &mut CallStack<'a>,
+        class: ClassRef<'a>,
+    ) -> Result<(), MethodCallFailed<'a>> {
+        if self.classes_to_initialize.is_empty() {
+            return Ok(());
+        }
+        if let Some(superclass) = class.superclass {
+            self.initialize_if_pending(stack, superclass)?;
+        }
+        for interface in class.interfaces.iter() {
+            self.initialize_if_pending(stack, interface)?;
+        }
+        // Removing the class before running its initializer ensures that any recursive
+        // request of the same class, such as from its own <clinit>, does not initialize it again
+        if self.classes_to_initialize.remove(&class.id) {
+            self.init_class(stack, &class)?;
+        }
+        Ok(())
+    }
+
     fn init_class(
         &mut self,
         stack: &mut CallStack<'a>,
         class_to_init: &ClassRef<'a>,
     ) -> Result<(), MethodCallFailed<'a>> {
         debug!("creating static instance of {}", class_to_init.name);
-        let static_instance = self.new_object_of_class(class_to_init);
+        let static_instance = self.new_object_of_class(class_to_init)?;
         self.statics.insert(class_to_init.id, static_instance);
+        self.init_static_constants(stack, class_to_init)?;
         if let Some(clinit_method) = class_to_init.find_method("<clinit>", "()V") {
             debug!("invoking {}::<clinit>()", class_to_init.name);
             self.invoke(
@@ -127,6 +734,143 @@ impl<'a> Vm<'a> {
                 Vec::new(),
             )?;
         }
+        if let Some(overrides) = self.static_overrides.get(&class_to_init.name) {
+            for (field_name, value) in overrides.iter() {
+                self.set_static_field(class_to_init, field_name, value.clone())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Assigns the static fields that have a `ConstantValue` attribute, such as
+    /// `static final int X = 5;`. The compiler does not generate any code in `<clinit>`
+    /// for them, so this must happen before running it.
+    fn init_static_constants(
+        &mut self,
+        stack: &mut CallStack<'a>,
+        class: ClassRef<'a>,
+    ) -> Result<(), MethodCallFailed<'a>> {
+        for field in class.fields.iter() {
+            if !field.flags.contains(FieldFlags::STATIC) {
+                continue;
+            }
+            let value = match &field.constant_value {
+                None => continue,
+                Some(FieldConstantValue::Int(value)) => Value::Int(*value),
+                Some(FieldConstantValue::Long(value)) => Value::Long(*value),
+                Some(FieldConstantValue::Float(value)) => Value::Float(*value),
+                Some(FieldConstantValue::Double(value)) => Value::Double(*value),
+                Some(FieldConstantValue::String(value)) => {
+                    Value::Object(self.get_or_create_interned_string(stack, value)?)
+                }
+            };
+            self.set_static_field(class, &field.name, value)?;
+        }
+        Ok(())
+    }
+
+    /// Overrides the value of a static field, for example to inject some configuration in
+    /// a class without modifying it. The value is assigned right after the execution of the
+    /// class' static initializer, or immediately if the class was already initialized.
+    pub fn set_static_before_run(
+        &mut self,
+        class_name: &str,
+        field_name: &str,
+        value: Value<'a>,
+    ) -> Result<(), VmError> {
+        if let Some(class) = self.find_class_by_name(class_name) {
+            if self.statics.contains_key(&class.id) {
+                return self.set_static_field(class, field_name, value);
+            }
+        }
+        self.static_overrides
+            .entry(class_name.to_string())
+            .or_default()
+            .push((field_name.to_string(), value));
+        Ok(())
+    }
+
+    fn set_static_field(
+        &self,
+        class: ClassRef<'a>,
+        field_name: &str,
+        value: Value<'a>,
+    ) -> Result<(), VmError> {
+        let (index, field) = class
+            .fields
+            .iter()
+            .enumerate()
+            .find(|(_, field)| field.name == field_name && field.flags.contains(FieldFlags::STATIC))
+            .map(|(index, field)| (index + class.first_field_index, field))
+            .ok_or_else(|| {
+                VmError::FieldNotFoundException(class.name.clone(), field_name.to_string())
+            })?;
+        let is_valid = value.matches_type(&field.type_descriptor, self, |class_name| {
+            self.find_class_by_name(class_name)
+        });
+        if !is_valid {
+            return Err(VmError::InvalidFieldValue(
+                class.name.clone(),
+                field_name.to_string(),
+                field.type_descriptor.to_string(),
+            ));
+        }
+        let static_instance = self
+            .get_static_instance(class.id)
+            .ok_or(VmError::ValidationException)?;
+        static_instance.set_field(index, value);
+        Ok(())
+    }
+
+    /// Returns the value of the field of the given object with the given name, that can
+    /// also be declared in a superclass. Returns `None` if the object is an array, or if its
+    /// class does not have a non-static field with that name.
+    pub fn get_object_field(
+        &self,
+        object: &AbstractObject<'a>,
+        field_name: &str,
+    ) -> Option<Value<'a>> {
+        if object.kind() != ObjectKind::Object {
+            return None;
+        }
+        let class = self.find_class_by_id(object.class_id())?;
+        match class.find_field(field_name) {
+            Some((index, field)) if !field.flags.contains(FieldFlags::STATIC) => {
+                Some(object.get_field(class, index))
+            }
+            _ => None,
+        }
+    }
+
+    /// Sets the value of the field of the given object with the given name, that can
+    /// also be declared in a superclass, checking that the value matches the field type.
+    pub fn set_object_field(
+        &self,
+        object: &AbstractObject<'a>,
+        field_name: &str,
+        value: Value<'a>,
+    ) -> Result<(), VmError> {
+        if object.kind() != ObjectKind::Object {
+            return Err(VmError::ValidationException);
+        }
+        let class = self.get_class_by_id(object.class_id())?;
+        let (index, field) = class
+            .find_field(field_name)
+            .filter(|(_, field)| !field.flags.contains(FieldFlags::STATIC))
+            .ok_or_else(|| {
+                VmError::FieldNotFoundException(class.name.clone(), field_name.to_string())
+            })?;
+        let is_valid = value.matches_type(&field.type_descriptor, self, |class_name| {
+            self.find_class_by_name(class_name)
+        });
+        if !is_valid {
+            return Err(VmError::InvalidFieldValue(
+                class.name.clone(),
+                field_name.to_string(),
+                field.type_descriptor.to_string(),
+            ));
+        }
+        object.set_field(index, value);
         Ok(())
     }
 
@@ -139,6 +883,27 @@ impl<'a> Vm<'a> {
         self.class_manager.find_class_by_name(class_name)
     }
 
+    /// Returns the formatted constant pool of the given class, or `None` if the class has not
+    /// been loaded yet. Useful to debug how the references of a class are resolved.
+    pub fn dump_constant_pool(&self, class_name: &str) -> Option<String> {
+        self.find_class_by_name(class_name)
+            .map(|class| class.constants.to_string())
+    }
+
+    /// Returns the name and type descriptor of all the methods marked as native in the
+    /// given class, which must have already been loaded
+    pub fn required_natives(&self, class_name: &str) -> Result<Vec<(String, String)>, VmError> {
+        let class = self
+            .find_class_by_name(class_name)
+            .ok_or(VmError::ClassNotFoundException(class_name.to_string()))?;
+        Ok(class
+            .methods
+            .iter()
+            .filter(|method| method.is_native())
+            .map(|method| (method.name.clone(), method.type_descriptor.clone()))
+            .collect())
+    }
+
     pub fn resolve_class_method(
         &mut self,
         call_stack: &mut CallStack<'a>,
@@ -168,19 +933,283 @@ impl<'a> Vm<'a> {
         object: Option<AbstractObject<'a>>,
         args: Vec<Value<'a>>,
     ) -> MethodCallResult<'a> {
+        self.emit_event(|| VmEvent::MethodInvoked {
+            class: class_and_method.class.name.clone(),
+            name: class_and_method.method.name.clone(),
+        });
         if class_and_method.method.is_native() {
             return self.invoke_native(call_stack, class_and_method, object, args);
         }
+        if class_and_method.is_abstract() {
+            // Can happen if a class was compiled against a different version of its superclass
+            debug!(
+                "cannot invoke abstract method {}::{} {}",
+                class_and_method.class.name,
+                class_and_method.method.name,
+                class_and_method.method.type_descriptor
+            );
+            let exception =
+                new_java_exception_object(self, call_stack, "java/lang/AbstractMethodError")?;
+            return Err(MethodCallFailed::ExceptionThrown(JavaException(exception)));
+        }
+        if let Some(native_override) = self.native_methods_registry.get_override(&class_and_method)
+        {
+            debug!(
+                "executing native override of method {}::{} {}",
+                class_and_method.class.name,
+                class_and_method.method.name,
+                class_and_method.method.type_descriptor
+            );
+            return native_override(self, call_stack, object, args);
+        }
 
         // Generic bytecode method
+        if call_stack.depth() >= self.max_call_stack_depth {
+            if !self.creating_stack_overflow_error {
+                return Err(self.new_stack_overflow_error(call_stack));
+            }
+            if call_stack.depth() >= self.max_call_stack_depth + STACK_OVERFLOW_ERROR_EXTRA_FRAMES {
+                return Err(VmError::CannotCreateStackOverflowError.into());
+            }
+        }
+        self.verify_if_needed(call_stack, &class_and_method)?;
         let mut frame = call_stack.add_frame(class_and_method, object, args)?;
         let result = frame.as_mut().execute(self, call_stack);
         call_stack
             .pop_frame()
             .expect("should be able to pop the frame we just pushed");
+        if call_stack.depth() == 0 {
+            // Any exception has left the outermost frame, so throwing it again is a new event
+            self.last_reported_exception = None;
+        }
         result
     }
 
+    /// Creates the `StackOverflowError` thrown when the call stack is too deep
+    fn new_stack_overflow_error(&mut self, call_stack: &mut CallStack<'a>) -> MethodCallFailed<'a> {
+        let message = Self::stack_overflow_message(call_stack, self.max_call_stack_depth);
+        self.creating_stack_overflow_error = true;
+        let exception = new_java_exception_object_with_message(
+            self,
+            call_stack,
+            "java/lang/StackOverflowError",
+            &message,
+        );
+        self.creating_stack_overflow_error = false;
+        match exception {
+            Ok(exception) => MethodCallFailed::ExceptionThrown(JavaException(exception)),
+            Err(err) => err,
+        }
+    }
+
+    /// Builds the message of a `StackOverflowError`, naming the top-most method that appears
+    /// more than once in the call stack, which is generally the one recursing unintentionally
+    fn stack_overflow_message(call_stack: &CallStack<'a>, max_call_stack_depth: usize) -> String {
+        let elements = call_stack.get_stack_trace_elements();
+        let repeated = elements.iter().enumerate().find(|(index, element)| {
+            elements[index + 1..].iter().any(|other| {
+                other.class_name == element.class_name && other.method_name == element.method_name
+            })
+        });
+        match repeated {
+            Some((_, element)) => format!(
+                "call stack depth exceeded {max_call_stack_depth}, recursion in {}::{}",
+                element.class_name, element.method_name
+            ),
+            None => format!("call stack depth exceeded {max_call_stack_depth}"),
+        }
+    }
+
+    /// Invokes a static method. Unlike [invoke], this is meant to be used by embedders: the
+    /// arguments, one for each parameter, are validated against the method's descriptor.
+    pub fn call_static(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_and_method: ClassAndMethod<'a>,
+        args: Vec<Value<'a>>,
+    ) -> MethodCallResult<'a> {
+        if !class_and_method.is_static() {
+            return Err(MethodCallFailed::InternalError(VmError::MethodIsNotStatic(
+                class_and_method.class.name.clone(),
+                class_and_method.method.name.clone(),
+                class_and_method.method.type_descriptor.clone(),
+            )));
+        }
+        let args = self.validate_arguments(&class_and_method, args)?;
+        self.invoke(call_stack, class_and_method, None, args)
+    }
+
+    /// Resolves a static method by name and invokes it, like [call_static]
+    pub fn invoke_static_by_name(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_name: &str,
+        method_name: &str,
+        method_type_descriptor: &str,
+        args: Vec<Value<'a>>,
+    ) -> MethodCallResult<'a> {
+        let class_and_method =
+            self.resolve_class_method(call_stack, class_name, method_name, method_type_descriptor)?;
+        self.call_static(call_stack, class_and_method, args)
+    }
+
+    /// Runs the entry point of a program, passing it the given arguments, and returns its exit
+    /// code. The standard entry point is `void main(String[])`, for which the exit code is zero,
+    /// but we also accept `int main(String[])`, in which case the returned value is the exit code.
+    /// If the program calls `System.exit`, its status is the exit code.
+    pub fn run_main(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_name: &str,
+        args: &[String],
+    ) -> Result<i32, MethodCallFailed<'a>> {
+        let main_method = self
+            .resolve_class_method(call_stack, class_name, "main", "([Ljava/lang/String;)V")
+            .or_else(|err| match err {
+                MethodCallFailed::InternalError(VmError::MethodNotFoundException(..)) => self
+                    .resolve_class_method(call_stack, class_name, "main", "([Ljava/lang/String;)I"),
+                _ => Err(err),
+            })?;
+        if !main_method.is_static() {
+            return Err(MethodCallFailed::InternalError(
+                VmError::MethodNotFoundException(
+                    class_name.to_string(),
+                    "main".to_string(),
+                    main_method.method.type_descriptor.clone(),
+                ),
+            ));
+        }
+
+        let main_args = self.new_java_lang_string_array(call_stack, args)?;
+        match self.invoke(call_stack, main_method, None, vec![main_args]) {
+            Ok(None) => Ok(0),
+            Ok(Some(Value::Int(exit_code))) => Ok(exit_code),
+            Ok(Some(_)) => Err(MethodCallFailed::InternalError(
+                VmError::ValidationException,
+            )),
+            Err(MethodCallFailed::Exit(exit_code)) => Ok(exit_code),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn new_java_lang_string_array(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        strings: &[String],
+    ) -> Result<Value<'a>, MethodCallFailed<'a>> {
+        let class_id_java_lang_string = self
+            .get_or_resolve_class(call_stack, "java/lang/String")?
+            .id;
+        let mut array = self.new_array(
+            ArrayEntryType::Object(class_id_java_lang_string),
+            strings.len(),
+        )?;
+        for (index, string) in strings.iter().enumerate() {
+            // The array must stay rooted while the string is allocated, since it could be moved
+            let (rooted_array, string) = self.with_temporary_root(array, |vm| {
+                new_java_lang_string_object(vm, call_stack, string)
+            });
+            array = rooted_array;
+            array.set_element(index, Value::Object(string?))?;
+        }
+        Ok(Value::Object(array))
+    }
+
+    /// Invokes an instance method, dispatching on the class of the receiver. Like [call_static],
+    /// the arguments are validated against the method's descriptor.
+    pub fn invoke_virtual(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_and_method: ClassAndMethod<'a>,
+        receiver: AbstractObject<'a>,
+        args: Vec<Value<'a>>,
+    ) -> MethodCallResult<'a> {
+        let class_and_method =
+            CallFrame::resolve_virtual_method(self, Some(receiver.clone()), class_and_method)?;
+        let args = self.validate_arguments(&class_and_method, args)?;
+        self.invoke(call_stack, class_and_method, Some(receiver), args)
+    }
+
+    /// Verifies the method, if needed, throwing a `java.lang.VerifyError` if it is not valid
+    fn verify_if_needed(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_and_method: &ClassAndMethod<'a>,
+    ) -> Result<(), MethodCallFailed<'a>> {
+        if !self.verification_enabled {
+            return Ok(());
+        }
+        let method_address: *const ClassFileMethod = class_and_method.method;
+        if self.verified_methods.contains(&method_address) {
+            return Ok(());
+        }
+        match verify_method(class_and_method) {
+            Ok(()) => {
+                self.verified_methods.insert(method_address);
+                Ok(())
+            }
+            Err(err @ VmError::VerificationError(..)) => {
+                warn!("{}", err);
+                let exception = new_java_exception_object_with_message(
+                    self,
+                    call_stack,
+                    "java/lang/VerifyError",
+                    &err.to_string(),
+                )?;
+                Err(MethodCallFailed::ExceptionThrown(JavaException(exception)))
+            }
+            Err(err) => Err(MethodCallFailed::InternalError(err)),
+        }
+    }
+
+    /// Checks the arguments against the parameter types of the method, and converts them in
+    /// the form expected by [invoke], i.e. with long and double taking two slots
+    fn validate_arguments(
+        &self,
+        class_and_method: &ClassAndMethod<'a>,
+        mut args: Vec<Value<'a>>,
+    ) -> Result<Vec<Value<'a>>, VmError> {
+        let parameters = &class_and_method.method.parsed_type_descriptor.parameters;
+        if parameters.len() != args.len() {
+            return Err(VmError::InvalidArgumentsCount(
+                class_and_method.class.name.clone(),
+                class_and_method.method.name.clone(),
+                class_and_method.method.type_descriptor.clone(),
+                parameters.len(),
+                args.len(),
+            ));
+        }
+
+        self.validate_argument_types(class_and_method, &args)?;
+        CallFrame::fix_long_and_double_params(&mut args)?;
+        Ok(args)
+    }
+
+    /// Checks that each argument, not yet padded for longs and doubles, matches
+    /// the type of the corresponding parameter of the method's descriptor
+    pub(crate) fn validate_argument_types(
+        &self,
+        class_and_method: &ClassAndMethod<'a>,
+        args: &[Value<'a>],
+    ) -> Result<(), VmError> {
+        let parameters = &class_and_method.method.parsed_type_descriptor.parameters;
+        for (index, (parameter, arg)) in parameters.iter().zip(args.iter()).enumerate() {
+            let is_valid = arg.matches_type(parameter, self, |class_name| {
+                self.find_class_by_name(class_name)
+            });
+            if !is_valid {
+                return Err(VmError::InvalidArgumentType(
+                    class_and_method.class.name.clone(),
+                    class_and_method.method.name.clone(),
+                    class_and_method.method.type_descriptor.clone(),
+                    index,
+                    parameter.to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     fn invoke_native(
         &mut self,
         call_stack: &mut CallStack<'a>,
@@ -210,11 +1239,82 @@ impl<'a> Vm<'a> {
 
     /// Allocates a new call stack. We need to store it to be able to refer it later, for
     /// extracting the gc roots.
+    /// If a call stack was previously released via [release_call_stack], it will be reused.
     pub fn allocate_call_stack(&mut self) -> &'a mut CallStack<'a> {
-        let stack = self.call_stacks.alloc(CallStack::new());
-        unsafe {
-            let stack_ptr: *mut CallStack<'a> = stack;
-            &mut *stack_ptr
+        let stack_ptr: *mut CallStack<'a> = match self.released_call_stacks.pop() {
+            Some(stack_ptr) => stack_ptr,
+            None => self.call_stacks.alloc(CallStack::new()),
+        };
+        unsafe { &mut *stack_ptr }
+    }
+
+    /// Gives back to the VM a call stack whose top level invocation has completed, so that
+    /// it can be reused by [allocate_call_stack]. Otherwise, since call stacks are never
+    /// deallocated, embedders that run code repeatedly would keep using more memory.
+    /// The call stack must not be used anymore after releasing it!
+    pub fn release_call_stack(&mut self, call_stack: &'a mut CallStack<'a>) -> Result<(), VmError> {
+        let stack_ptr: *mut CallStack<'a> = call_stack;
+        if !call_stack.is_empty() || self.released_call_stacks.contains(&stack_ptr) {
+            return Err(VmError::ValidationException);
+        }
+        call_stack.reset();
+        self.released_call_stacks.push(stack_ptr);
+        Ok(())
+    }
+
+    /// Returns the number of call stacks allocated so far, including the released ones
+    pub fn allocated_call_stacks(&self) -> usize {
+        self.call_stacks.len()
+    }
+
+    /// Brings the VM back to the state it had after being created, so that it can run
+    /// another program without allocating the heap again. All the objects are discarded,
+    /// along with the static fields, the stack traces and what was printed, and all the
+    /// call stacks are emptied. The configuration, the class path and the native methods
+    /// are kept, while the overrides of [set_static_before_run] are discarded.
+    ///
+    /// If `keep_loaded_classes` is true, the classes are not read again from the class path,
+    /// but their static initializers are executed again the first time they are used.
+    /// Otherwise, they will be loaded again, with different ids. Note that the memory of the
+    /// dropped classes is only released when the VM is dropped, since they might still be
+    /// referenced, so embedders that reset many times should keep the loaded classes, or
+    /// create a new VM from time to time.
+    ///
+    /// Must not be invoked while some code is executing. Any [AbstractObject] or [Value]
+    /// obtained before the reset becomes invalid and must not be used anymore, and so does
+    /// any [ClassRef] if the classes are not kept.
+    pub fn reset(&mut self, keep_loaded_classes: bool) {
+        info!(
+            "resetting vm, keeping loaded classes: {}",
+            keep_loaded_classes
+        );
+        self.object_allocator.reset();
+        for call_stack in self.call_stacks.iter_mut() {
+            call_stack.reset();
+        }
+        self.statics.clear();
+        self.class_objects.clear();
+        self.array_and_primitive_class_objects.clear();
+        self.interned_strings.clear();
+        self.main_thread = None;
+        self.static_overrides.clear();
+        self.temporary_roots.clear();
+        self.throwable_call_stacks.clear();
+        self.printed.clear();
+        self.inline_caches.clear();
+        self.resolved_fields.clear();
+        self.resolved_classes.clear();
+        self.class_reference_lookups = 0;
+        self.last_reported_exception = None;
+        self.step_over_depth = None;
+        self.creating_stack_overflow_error = false;
+
+        if keep_loaded_classes {
+            self.classes_to_initialize = self.class_manager.loaded_class_ids().collect();
+        } else {
+            self.class_manager.unload_all_classes();
+            self.classes_to_initialize.clear();
+            self.verified_methods.clear();
         }
     }
 
@@ -224,19 +1324,48 @@ impl<'a> Vm<'a> {
         class_name: &str,
     ) -> Result<AbstractObject<'a>, MethodCallFailed<'a>> {
         let class = self.get_or_resolve_class(call_stack, class_name)?;
-        Ok(self.new_object_of_class(class))
+        Ok(self.new_object_of_class(class)?)
+    }
+
+    /// Allocates an instance of the given class and initializes it, by invoking the
+    /// constructor with the given descriptor. Like [call_static], the arguments are
+    /// validated against the constructor's descriptor.
+    pub fn new_instance(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_name: &str,
+        constructor_type_descriptor: &str,
+        args: Vec<Value<'a>>,
+    ) -> Result<AbstractObject<'a>, MethodCallFailed<'a>> {
+        let constructor = self.resolve_class_method(
+            call_stack,
+            class_name,
+            "<init>",
+            constructor_type_descriptor,
+        )?;
+        let args = self.validate_arguments(&constructor, args)?;
+        let (args, object) =
+            self.with_temporary_root_values(args, |vm| vm.new_object_of_class(constructor.class));
+        let object = object?;
+        let (object, result) = self.with_temporary_root(object.clone(), |vm| {
+            vm.invoke(call_stack, constructor, Some(object), args)
+        });
+        result?;
+        Ok(object)
     }
 
-    pub fn new_object_of_class(&mut self, class: ClassRef<'a>) -> AbstractObject<'a> {
+    pub fn new_object_of_class(
+        &mut self,
+        class: ClassRef<'a>,
+    ) -> Result<AbstractObject<'a>, VmError> {
         debug!("allocating new instance of {}", class.name);
         match self.object_allocator.allocate_object(class) {
-            Some(object) => object,
+            Some(object) => Ok(object),
             None => {
-                self.run_garbage_collection()
-                    .expect("could run garbage collection");
+                self.collect_garbage_to_allocate()?;
                 self.object_allocator
                     .allocate_object(class)
-                    .expect("cannot allocate object even after full garbage collection!")
+                    .ok_or(VmError::OutOfMemory)
             }
         }
     }
@@ -245,28 +1374,37 @@ impl<'a> Vm<'a> {
         &mut self,
         elements_type: ArrayEntryType,
         length: usize,
-    ) -> AbstractObject<'a> {
+    ) -> Result<AbstractObject<'a>, VmError> {
         match self
             .object_allocator
             .allocate_array(elements_type.clone(), length)
         {
-            Some(array) => array,
+            Some(array) => Ok(array),
             None => {
-                self.run_garbage_collection()
-                    .expect("could run garbage collection");
+                self.collect_garbage_to_allocate()?;
                 self.object_allocator
                     .allocate_array(elements_type, length)
-                    .expect("cannot allocate array even after full garbage collection!")
+                    .ok_or(VmError::OutOfMemory)
             }
         }
     }
 
+    fn collect_garbage_to_allocate(&mut self) -> Result<(), VmError> {
+        if !self.object_allocator.is_collection_enabled() {
+            return Err(VmError::OutOfMemory);
+        }
+        self.run_garbage_collection()
+    }
+
     pub fn clone_array(&mut self, value: Value<'a>) -> Result<Value<'a>, VmError> {
         match &value {
             Value::Object(array) if array.kind() == ObjectKind::Array => {
-                let new_array =
-                    self.new_array(array.elements_type(), array.len().into_usize_safe());
-                array_copy(array, 0, &new_array, 0, array.len().into_usize_safe())?;
+                let length = array.len().into_usize_safe();
+                let (array, new_array) = self.with_temporary_root(array.clone(), |vm| {
+                    vm.new_array(array.elements_type(), length)
+                });
+                let new_array = new_array?;
+                array_copy(&array, 0, &new_array, 0, length)?;
                 Ok(Value::Object(new_array))
             }
             _ => Err(VmError::ValidationException),
@@ -290,6 +1428,11 @@ impl<'a> Vm<'a> {
             .get(&throwable.identity_hash_code())
     }
 
+    /// Returns everything that was printed so far, and clears the buffer
+    pub fn take_printed(&mut self) -> Vec<Value<'a>> {
+        std::mem::take(&mut self.printed)
+    }
+
     pub fn debug_stats(&self) {
         debug!(
             "VM classes={:?} allocator={:?}",
@@ -297,6 +1440,84 @@ impl<'a> Vm<'a> {
         )
     }
 
+    /// Returns how much of the heap is currently used
+    pub fn heap_usage(&self) -> HeapUsage {
+        self.object_allocator.heap_usage()
+    }
+
+    /// Returns how many times a class referenced by `instanceof` or `checkcast` had to be
+    /// looked up by name. Since the result is cached, this happens once for each instruction.
+    pub fn class_reference_lookups(&self) -> usize {
+        self.class_reference_lookups
+    }
+
+    /// Returns the statistics of the garbage collections executed so far
+    pub fn gc_stats(&self) -> &GcStats {
+        self.object_allocator.stats()
+    }
+
+    /// Returns false if the vm was created with [AllocationStrategy::BumpOnly]
+    pub fn is_garbage_collection_enabled(&self) -> bool {
+        self.object_allocator.is_collection_enabled()
+    }
+
+    /// Runs a garbage collection immediately, rather than waiting for an allocation to fail.
+    /// Useful for embedders that want to release memory proactively.
+    pub fn collect_garbage(&mut self) -> Result<GcOutcome, VmError> {
+        let used_before = self.object_allocator.heap_usage().used;
+        self.run_garbage_collection()?;
+        Ok(GcOutcome {
+            used_before,
+            used_after: self.object_allocator.heap_usage().used,
+        })
+    }
+
+    /// Invokes the given function keeping the given object alive across the garbage
+    /// collections that it might trigger. Since the collector moves the objects, the
+    /// copy held by the caller is stale afterwards: the returned one must be used instead.
+    pub(crate) fn with_temporary_root<T>(
+        &mut self,
+        object: AbstractObject<'a>,
+        function: impl FnOnce(&mut Self) -> T,
+    ) -> (AbstractObject<'a>, T) {
+        self.temporary_roots.push(object);
+        let result = function(self);
+        let object = self
+            .temporary_roots
+            .pop()
+            .expect("should find the temporary root we pushed");
+        (object, result)
+    }
+
+    /// Like [with_temporary_root], but roots all the objects among the given values, which are
+    /// returned with their possibly moved references
+    pub(crate) fn with_temporary_root_values<T>(
+        &mut self,
+        values: Vec<Value<'a>>,
+        function: impl FnOnce(&mut Self) -> T,
+    ) -> (Vec<Value<'a>>, T) {
+        let first_root = self.temporary_roots.len();
+        self.temporary_roots
+            .extend(values.iter().filter_map(|value| match value {
+                Value::Object(object) => Some(object.clone()),
+                _ => None,
+            }));
+        let result = function(self);
+        let mut roots = self.temporary_roots.split_off(first_root).into_iter();
+        let values = values
+            .into_iter()
+            .map(|value| match value {
+                Value::Object(_) => Value::Object(
+                    roots
+                        .next()
+                        .expect("should find the temporary root we pushed"),
+                ),
+                value => value,
+            })
+            .collect();
+        (values, result)
+    }
+
     pub fn run_garbage_collection(&mut self) -> Result<(), VmError> {
         let mut roots = vec![];
         roots.extend(
@@ -304,12 +1525,60 @@ impl<'a> Vm<'a> {
                 .iter_mut()
                 .map(|(_, object)| object as *mut AbstractObject<'a>),
         );
+        roots.extend(
+            self.class_objects
+                .iter_mut()
+                .map(|(_, object)| object as *mut AbstractObject<'a>),
+        );
+        roots.extend(
+            self.array_and_primitive_class_objects
+                .iter_mut()
+                .map(|(_, object)| object as *mut AbstractObject<'a>),
+        );
+        roots.extend(
+            self.interned_strings
+                .values_mut()
+                .map(|object| object as *mut AbstractObject<'a>),
+        );
+        roots.extend(
+            self.main_thread
+                .iter_mut()
+                .map(|object| object as *mut AbstractObject<'a>),
+        );
+        roots.extend(
+            self.last_reported_exception
+                .iter_mut()
+                .map(|object| object as *mut AbstractObject<'a>),
+        );
+        roots.extend(
+            self.temporary_roots
+                .iter_mut()
+                .map(|object| object as *mut AbstractObject<'a>),
+        );
+        roots.extend(
+            self.static_overrides
+                .values_mut()
+                .flat_map(|overrides| overrides.iter_mut())
+                .filter_map(|(_, value)| match value {
+                    Value::Object(object) => Some(object as *mut AbstractObject<'a>),
+                    _ => None,
+                }),
+        );
+        roots.extend(self.printed.iter_mut().filter_map(|value| match value {
+            Value::Object(object) => Some(object as *mut AbstractObject<'a>),
+            _ => None,
+        }));
         roots.extend(self.call_stacks.iter_mut().flat_map(|s| s.gc_roots()));
 
+        let used_before = self.object_allocator.heap_usage().used;
         unsafe {
             self.object_allocator
                 .do_garbage_collection(roots, &self.class_manager)?;
         }
+        let used_after = self.object_allocator.heap_usage().used;
+        self.emit_event(|| VmEvent::GcRan {
+            freed_bytes: used_before.saturating_sub(used_after),
+        });
         Ok(())
     }
 }
@@ -24,6 +24,21 @@ pub enum VmError {
     #[error("field not found: {0}.{1}")]
     FieldNotFoundException(String, String),
 
+    #[error("invalid value for field {0}.{1}: expected a value of type {2}")]
+    InvalidFieldValue(String, String, String),
+
+    #[error("native method already registered: {0}.{1}#{2}")]
+    NativeMethodAlreadyRegistered(String, String, String),
+
+    #[error("method {0}.{1}#{2} is not static")]
+    MethodIsNotStatic(String, String, String),
+
+    #[error("method {0}.{1}#{2} expects {3} arguments, but got {4}")]
+    InvalidArgumentsCount(String, String, String, usize, usize),
+
+    #[error("invalid argument {3} for method {0}.{1}#{2}: expected a value of type {4}")]
+    InvalidArgumentType(String, String, String, usize, String),
+
     /// This is an overly generic error, abused to mean "something unexpected happened".
     /// It includes mostly errors that should be checked during the linking phase of the class file
     /// (which we have not implemented).
@@ -37,6 +52,9 @@ pub enum VmError {
     #[error("not yet implemented")]
     NotImplemented,
 
+    #[error("verification of method {0}.{1}#{2} failed: {3}")]
+    VerificationError(String, String, String, String),
+
     /// TODO: this should become throwing a real `java.lang.ArrayIndexOutOfBoundsException`
     #[error("array index out of bounds")]
     ArrayIndexOutOfBoundsException,
@@ -44,6 +62,19 @@ pub enum VmError {
     /// TODO: this should become throwing a real `java.lang.ClassCastException`
     #[error("class cast exception")]
     ClassCastException,
+
+    #[error("garbage collection is disabled")]
+    GarbageCollectionDisabled,
+
+    /// TODO: this should become throwing a real `java.lang.OutOfMemoryError`
+    #[error("out of memory")]
+    OutOfMemory,
+
+    #[error("call stack depth exceeded while creating a StackOverflowError")]
+    CannotCreateStackOverflowError,
+
+    #[error("maximum memory of {0} bytes is too large")]
+    MaximumMemoryTooLarge(usize),
 }
 
 // TODO: remove once we implement exceptions
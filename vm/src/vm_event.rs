@@ -0,0 +1,16 @@
+/// Events emitted by the [crate::vm::Vm] while executing code, that embedders can
+/// observe by subscribing via [crate::vm::Vm::subscribe_to_events].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VmEvent {
+    /// A class was loaded, from the given class path entry
+    ClassLoaded { name: String, source: String },
+    /// A method is about to be invoked
+    MethodInvoked { class: String, name: String },
+    /// The garbage collector has run
+    GcRan { freed_bytes: usize },
+    /// An exception of the given class was thrown
+    ExceptionThrown { class: String },
+}
+
+/// A callback invoked for each [VmEvent]
+pub type VmEventListener<'a> = Box<dyn FnMut(&VmEvent) + 'a>;
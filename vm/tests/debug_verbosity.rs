@@ -0,0 +1,65 @@
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use rjvm_vm::vm::{DebugVerbosity, Vm, DEFAULT_MAX_MEMORY};
+
+// This test lives in its own binary, since it needs to install a global logger
+// that captures the messages, rather than the one of test_log
+
+static CAPTURED_MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CapturingLogger;
+
+impl Log for CapturingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Debug
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            CAPTURED_MESSAGES
+                .lock()
+                .unwrap()
+                .push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger;
+
+#[test]
+fn frame_entry_verbosity_does_not_log_instructions() {
+    log::set_logger(&LOGGER).expect("should be able to install the logger");
+    log::set_max_level(LevelFilter::Debug);
+
+    let mut vm = Vm::new(DEFAULT_MAX_MEMORY).with_debug_verbosity(DebugVerbosity::FrameEntry);
+    let src_dir = env!("CARGO_MANIFEST_DIR");
+    vm.append_class_path(&format!("{src_dir}/rt.jar:{src_dir}/tests/resources",))
+        .expect("should be able to add entries to the classpath");
+
+    let call_stack = vm.allocate_call_stack();
+    let main_method = vm
+        .resolve_class_method(
+            call_stack,
+            "rjvm/SimpleMain",
+            "main",
+            "([Ljava/lang/String;)V",
+        )
+        .expect("should find main method");
+    let main_result = vm.invoke(call_stack, main_method, None, vec![]);
+    assert_eq!(Ok(None), main_result);
+
+    let messages = CAPTURED_MESSAGES.lock().unwrap();
+    assert!(messages
+        .iter()
+        .any(|message| message.starts_with("starting execution of method rjvm/SimpleMain::main")));
+    assert!(messages
+        .iter()
+        .any(|message| message.starts_with("completed execution of method rjvm/SimpleMain::main")));
+    assert!(!messages
+        .iter()
+        .any(|message| message.starts_with("FRAME STATUS")));
+}
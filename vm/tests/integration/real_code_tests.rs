@@ -1,8 +1,22 @@
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
+use rjvm_reader::{
+    class_reader::ClassReaderLimits, field_type::BaseType, instruction::Instruction,
+};
 use rjvm_vm::{
+    array::Array,
+    array_entry_type::ArrayEntryType,
     exceptions::MethodCallFailed,
-    java_objects_creation::extract_str_from_java_lang_string,
-    value::{expect_concrete_object_at, Value},
-    vm::{Vm, DEFAULT_MAX_MEMORY},
+    instruction_hook::StepAction,
+    java_objects_creation::{extract_str_from_java_lang_string, new_java_lang_string_object},
+    object::Object,
+    value::{expect_concrete_object_at, expect_int_at, Value},
+    vm::{
+        configured_runtime_jar, native_stack_size_for_call_stack_depth, AllocationStrategy,
+        MemorySizing, Vm, DEFAULT_MAX_MEMORY, ONE_MEGABYTE,
+    },
+    vm_error::VmError,
+    vm_event::VmEvent,
 };
 
 // This file tests the real classes in ../resources/rjvm
@@ -189,6 +203,36 @@ fn object_arrays() {
     assert_eq!(vec![Value::Int(5),], vm.printed);
 }
 
+#[test_log::test]
+fn array_return() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ArrayReturn",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+    assert_eq!(
+        vec![Value::Int(4), Value::Int(9), Value::Int(3)],
+        vm.printed
+    );
+
+    let call_stack = vm.allocate_call_stack();
+    let method = vm
+        .resolve_class_method(call_stack, "rjvm/ArrayReturn", "squares", "(I)[I")
+        .expect("should find method");
+    let result = vm.call_static(call_stack, method, vec![Value::Int(3)]);
+    let Ok(Some(Value::Object(array))) = result else {
+        panic!("should have returned an array, got {result:?}");
+    };
+    assert_eq!(3, array.len());
+    let elements: Vec<Value> = (0..3)
+        .map(|index| array.get_element(index).expect("should be a valid index"))
+        .collect();
+    assert_eq!(vec![Value::Int(0), Value::Int(1), Value::Int(4)], elements);
+}
+
 #[test_log::test]
 fn statics() {
     let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
@@ -326,7 +370,7 @@ fn stack_trace_printing() {
         extract_printed_string(&vm, 1)
     );
     assert_eq!(
-        "java/lang/Exception::<init> - Exception.java:55",
+        "java/lang/Exception::<init> - Exception.java:54",
         extract_printed_string(&vm, 2)
     );
     assert_eq!(
@@ -370,9 +414,2425 @@ fn gabarge_collector() {
     assert_eq!(Ok(None), main_result);
 }
 
+#[test_log::test]
+fn garbage_collector_stats() {
+    let mut vm = create_base_vm(10_000_000);
+    assert_eq!(0, vm.gc_stats().collections);
+
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/GarbageCollection",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    let stats = vm.gc_stats();
+    assert!(stats.collections >= 1);
+    assert!(stats.live_bytes > 0);
+    assert!(stats.bytes_copied >= stats.live_bytes as u64);
+}
+
 #[test_log::test]
 fn generic() {
     let mut vm = create_base_vm(10_000_000);
     let main_result = invoke(&mut vm, "rjvm/Generic", "main", "([Ljava/lang/String;)V");
     assert_eq!(Ok(None), main_result);
 }
+
+#[test_log::test]
+fn strings_equals_and_hash_code() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/StringsEqualsAndHashCode",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(99162322),
+            Value::Int(0),
+            Value::Int(0),
+            Value::Int(0),
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn get_class() {
+    let mut vm = create_base_vm(10_000_000);
+    let main_result = invoke(&mut vm, "rjvm/GetClass", "main", "([Ljava/lang/String;)V");
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![Value::Int(1), Value::Int(0), Value::Int(1)],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn class_literals() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ClassLiterals",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    let expected: Vec<Value> = [1, 1, 0, 1, 1, 100, 5]
+        .into_iter()
+        .map(Value::Int)
+        .collect();
+    assert_eq!(expected, vm.printed);
+}
+
+#[test_log::test]
+fn arrays_of_arrays() {
+    let mut vm = create_base_vm(10_000_000);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ArraysOfArrays",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(2),
+            Value::Int(42),
+            Value::Int(3),
+            Value::Int(5),
+            Value::Int(49)
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn garbage_collection_of_arrays_of_arrays() {
+    let mut vm = create_base_vm(10_000_000);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/GarbageArraysOfArrays",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert!(vm.gc_stats().collections >= 1);
+    assert_eq!(vec![Value::Int(6), Value::Int(1)], vm.printed);
+}
+
+#[test_log::test]
+fn collect_garbage() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/GarbageArraysOfArrays",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+    assert_eq!(0, vm.gc_stats().collections);
+
+    // The main method has completed, so all the arrays it allocated are unreachable
+    let outcome = vm
+        .collect_garbage()
+        .expect("should be able to collect garbage");
+    assert!(outcome.reclaimed_bytes() > 5000 * 2 * 100 * 4);
+    assert_eq!(outcome.used_after, vm.gc_stats().live_bytes);
+    assert_eq!(1, vm.gc_stats().collections);
+}
+
+#[test_log::test]
+fn signed_bytes() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/SignedBytes",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(-2),
+            Value::Int(-128),
+            Value::Int(127),
+            Value::Int(-100)
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn memory_sizing_usable_heap() {
+    let mut vm = Vm::with_memory_sizing(ONE_MEGABYTE, MemorySizing::UsableHeap)
+        .expect("should be able to create the vm");
+
+    // With the default sizing, only half a megabyte would be usable
+    let array = vm
+        .new_array(ArrayEntryType::Base(BaseType::Long), ONE_MEGABYTE / 8 - 16)
+        .expect("should be able to allocate the array");
+    assert_eq!(ONE_MEGABYTE as u32 / 8 - 16, array.len());
+}
+
+#[test_log::test]
+fn memory_sizing_usable_heap_too_large() {
+    let result = Vm::with_memory_sizing(usize::MAX, MemorySizing::UsableHeap);
+    assert!(matches!(
+        result,
+        Err(VmError::MaximumMemoryTooLarge(usize::MAX))
+    ));
+}
+
+fn create_vm_with_allocation_strategy(
+    max_memory: usize,
+    allocation_strategy: AllocationStrategy,
+) -> Vm<'static> {
+    let mut vm = Vm::with_allocation_strategy(max_memory, MemorySizing::Total, allocation_strategy)
+        .expect("should be able to create the vm");
+    let src_dir = env!("CARGO_MANIFEST_DIR");
+    vm.append_class_path(&format!("{src_dir}/rt.jar:{src_dir}/tests/resources",))
+        .expect("should be able to add entries to the classpath");
+    vm
+}
+
+fn run_large_allocation(
+    allocation_strategy: AllocationStrategy,
+) -> Result<Vec<Value<'static>>, MethodCallFailed<'static>> {
+    let mut vm = create_vm_with_allocation_strategy(4 * ONE_MEGABYTE, allocation_strategy);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/LargeAllocation",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    main_result.map(|_| vm.take_printed())
+}
+
+#[test_log::test]
+fn bump_only_allocation_can_use_all_the_memory() {
+    assert_eq!(
+        Ok(vec![Value::Long(42)]),
+        run_large_allocation(AllocationStrategy::BumpOnly)
+    );
+}
+
+#[test_log::test]
+fn semi_space_copying_allocation_can_use_half_the_memory() {
+    assert_eq!(
+        Err(MethodCallFailed::InternalError(VmError::OutOfMemory)),
+        run_large_allocation(AllocationStrategy::SemiSpaceCopying)
+    );
+}
+
+#[test_log::test]
+fn system_gc_does_nothing_when_garbage_collection_is_disabled() {
+    let mut vm =
+        create_vm_with_allocation_strategy(DEFAULT_MAX_MEMORY, AllocationStrategy::BumpOnly);
+    let call_stack = vm.allocate_call_stack();
+    let object = vm
+        .new_instance(
+            call_stack,
+            "rjvm/GcInConstructor",
+            "(I)V",
+            vec![Value::Int(42)],
+        )
+        .expect("should be able to construct the object");
+
+    assert_eq!(0, vm.gc_stats().collections);
+    assert_eq!(Some(Value::Int(42)), vm.get_object_field(&object, "value"));
+}
+
+#[test_log::test]
+fn interface_object_methods() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/InterfaceObjectMethods",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!("Square(3)", extract_printed_string(&vm, 0));
+    assert_eq!(
+        vec![
+            Value::Int(93),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(1)
+        ],
+        vm.printed[1..]
+    );
+}
+
+#[test_log::test]
+fn references_and_finalize() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(&mut vm, "rjvm/References", "main", "([Ljava/lang/String;)V");
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(Value::Int(-42), vm.printed[0]);
+    assert_eq!(Value::Int(1), vm.printed[1]);
+    assert_eq!("soft", extract_printed_string(&vm, 2));
+    assert_eq!(Value::Int(0), vm.printed[3]);
+    assert_eq!(Value::Int(1), vm.printed[4]);
+}
+
+#[test_log::test]
+fn heap_usage() {
+    let mut vm = Vm::new(10 * ONE_MEGABYTE);
+    let initial_usage = vm.heap_usage();
+    assert_eq!(0, initial_usage.used);
+    assert_eq!(5 * ONE_MEGABYTE, initial_usage.capacity);
+
+    for _ in 0..10 {
+        vm.new_array(ArrayEntryType::Base(BaseType::Int), 100)
+            .expect("should be able to allocate the array");
+    }
+    let usage_after_allocations = vm.heap_usage();
+    assert!(usage_after_allocations.used > 10 * 100 * 4);
+    assert_eq!(initial_usage.capacity, usage_after_allocations.capacity);
+
+    // Nothing refers to the arrays, so they will all be freed
+    vm.collect_garbage()
+        .expect("should be able to collect garbage");
+    assert_eq!(0, vm.heap_usage().used);
+}
+
+#[test_log::test]
+fn byte_array_output_stream() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ByteArrayOutput",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(8),
+            Value::Int(8),
+            Value::Int(104),
+            Value::Int(105),
+            Value::Int(119),
+            Value::Int(111),
+            Value::Int(114),
+            Value::Int(108),
+            Value::Int(100),
+            Value::Int(-1),
+        ],
+        vm.printed[..10]
+    );
+    assert_eq!("wo", extract_printed_string(&vm, 10));
+    assert_eq!("out of bounds", extract_printed_string(&vm, 11));
+    assert_eq!(Value::Int(59), vm.printed[12]);
+    assert_eq!(
+        "answer: 42\n\u{e8}!\nThe quick brown fox jumps over the lazy dog\n",
+        extract_printed_string(&vm, 13)
+    );
+}
+
+#[test_log::test]
+fn set_static_before_run() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let name = new_java_lang_string_object(&mut vm, call_stack, "injected")
+        .expect("should be able to create a string");
+    vm.set_static_before_run("rjvm/StaticConfiguration", "limit", Value::Int(42))
+        .expect("should be able to set the static field");
+    vm.set_static_before_run("rjvm/StaticConfiguration", "name", Value::Object(name))
+        .expect("should be able to set the static field");
+
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/StaticConfiguration",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+    assert_eq!(Value::Int(42), vm.printed[0]);
+    assert_eq!("injected", extract_printed_string(&vm, 1));
+    // The static initializer ran before the override was applied
+    assert_eq!(Value::Int(20), vm.printed[2]);
+
+    // Once the class is initialized, the fields are validated immediately
+    assert_eq!(
+        Err(VmError::FieldNotFoundException(
+            "rjvm/StaticConfiguration".to_string(),
+            "missing".to_string()
+        )),
+        vm.set_static_before_run("rjvm/StaticConfiguration", "missing", Value::Int(1))
+    );
+    assert_eq!(
+        Err(VmError::InvalidFieldValue(
+            "rjvm/StaticConfiguration".to_string(),
+            "limit".to_string(),
+            "Int".to_string()
+        )),
+        vm.set_static_before_run("rjvm/StaticConfiguration", "limit", Value::Long(1))
+    );
+}
+
+#[test_log::test]
+fn floating_point_bits() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/FloatingPointBits",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(0x7f800001),
+            Value::Int(0x7fc00000),
+            Value::Int(1.5f32.to_bits() as i32),
+            Value::Long(0x7ff0000000000001),
+            Value::Long(0x7ff8000000000000),
+            Value::Long(1.5f64.to_bits() as i64),
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn floating_point_bits_round_trip() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/FloatingPointRoundTrip",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(1),
+            Value::Float(2.75),
+            Value::Int(1),
+            Value::Double(f64::from_bits(1e-300f64.to_bits() + 1)),
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn floating_point_remainder() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/FloatingPointRemainder",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    let expected: Vec<Value> = [2.0, -2.0, 2.0, f64::NAN, -0.0, f64::NAN, 5.0, f64::NAN]
+        .into_iter()
+        .flat_map(|remainder: f64| {
+            // Like doubleToLongBits and floatToIntBits, we use the canonical NaN
+            let (double_bits, float_bits) = if remainder.is_nan() {
+                (0x7ff8000000000000, 0x7fc00000)
+            } else {
+                (
+                    remainder.to_bits() as i64,
+                    (remainder as f32).to_bits() as i32,
+                )
+            };
+            [Value::Long(double_bits), Value::Int(float_bits)]
+        })
+        .collect();
+    assert_eq!(expected, vm.printed);
+}
+
+#[test_log::test]
+fn verification_rejects_invalid_branch_target() {
+    for verification in [false, true] {
+        let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+        vm.set_verification(verification);
+        let call_stack = vm.allocate_call_stack();
+        let method = vm
+            .resolve_class_method(call_stack, "rjvm/InvalidBranchTarget", "run", "(I)V")
+            .expect("should find method");
+        let result = vm.invoke(call_stack, method, None, vec![Value::Int(0)]);
+
+        if verification {
+            assert_verify_error(
+                &vm,
+                result,
+                "verification of method rjvm/InvalidBranchTarget.run#(I)V failed: \
+                jump to address 101, which is not the start of an instruction",
+            );
+            assert!(vm.printed.is_empty());
+        } else {
+            // The invalid branch is not taken, so the method runs fine
+            assert_eq!(Ok(None), result);
+            assert_eq!(vec![Value::Int(1)], vm.printed);
+        }
+    }
+}
+
+#[test_log::test]
+fn verification_rejects_branch_into_the_middle_of_an_instruction() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    vm.set_verification(true);
+    let call_stack = vm.allocate_call_stack();
+    let method = vm
+        .resolve_class_method(call_stack, "rjvm/BranchIntoLdcW", "run", "(I)V")
+        .expect("should find method");
+    let result = vm.invoke(call_stack, method, None, vec![Value::Int(1)]);
+
+    assert_verify_error(
+        &vm,
+        result,
+        "verification of method rjvm/BranchIntoLdcW.run#(I)V failed: \
+        jump to address 11, which is not the start of an instruction",
+    );
+    assert!(vm.printed.is_empty());
+}
+
+#[test_log::test]
+fn verification_does_not_require_stack_map_table_for_java_6_classes() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    vm.set_verification(true);
+    let call_stack = vm.allocate_call_stack();
+
+    let method = vm
+        .resolve_class_method(call_stack, "rjvm/NoStackMapTableJava6", "sum", "(I)I")
+        .expect("should find method");
+    let result = vm.invoke(call_stack, method, None, vec![Value::Int(4)]);
+    assert_eq!(Ok(Some(Value::Int(10))), result);
+
+    let method = vm
+        .resolve_class_method(call_stack, "rjvm/NoStackMapTableJava7", "sum", "(I)I")
+        .expect("should find method");
+    let result = vm.invoke(call_stack, method, None, vec![Value::Int(4)]);
+    assert_verify_error(
+        &vm,
+        result,
+        "verification of method rjvm/NoStackMapTableJava7.sum#(I)I failed: \
+        missing StackMapTable",
+    );
+}
+
+#[test_log::test]
+fn verification_accepts_classes_with_stack_map_table() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    vm.set_verification(true);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/PolymorphicCalls",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+}
+
+fn assert_verify_error<'a>(
+    vm: &Vm<'a>,
+    result: Result<Option<Value<'a>>, MethodCallFailed<'a>>,
+    expected_message: &str,
+) {
+    match result {
+        Err(MethodCallFailed::ExceptionThrown(exception)) => {
+            let class = vm
+                .get_class_by_id(exception.0.class_id())
+                .expect("should find the exception class");
+            assert_eq!("java/lang/VerifyError", class.name);
+            assert_eq!(
+                Some(expected_message.to_string()),
+                exception
+                    .message(vm)
+                    .expect("should be able to read message")
+            );
+        }
+        _ => panic!("expected a VerifyError, got {result:?}"),
+    }
+}
+
+fn collect_events(vm: &mut Vm<'static>) -> Rc<RefCell<Vec<VmEvent>>> {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let events_clone = events.clone();
+    vm.subscribe_to_events(move |event| events_clone.borrow_mut().push(event.clone()));
+    events
+}
+
+#[test_log::test]
+fn events_for_class_loading_and_method_invocation() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let events = collect_events(&mut vm);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/SuperClasses",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    let events = events.borrow();
+    let loaded_classes: Vec<&str> = events
+        .iter()
+        .filter_map(|event| match event {
+            VmEvent::ClassLoaded { name, source } => {
+                assert!(!source.is_empty());
+                Some(name.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    let base_class_index = loaded_classes
+        .iter()
+        .position(|name| *name == "rjvm/SuperClasses$BaseClass")
+        .expect("should have loaded the superclass");
+    let derived_class_index = loaded_classes
+        .iter()
+        .position(|name| *name == "rjvm/SuperClasses$DerivedClass")
+        .expect("should have loaded the derived class");
+    assert!(base_class_index < derived_class_index);
+
+    assert!(events.contains(&VmEvent::ClassLoaded {
+        name: "rjvm/SuperClasses".to_string(),
+        source: format!("{}/tests/resources", env!("CARGO_MANIFEST_DIR")),
+    }));
+    assert!(events.contains(&VmEvent::MethodInvoked {
+        class: "rjvm/SuperClasses$BaseClass".to_string(),
+        name: "sum".to_string(),
+    }));
+}
+
+#[test_log::test]
+fn events_for_exceptions_are_emitted_once() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let events = collect_events(&mut vm);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/UncaughtException",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert!(matches!(
+        main_result,
+        Err(MethodCallFailed::ExceptionThrown(_))
+    ));
+
+    let events = events.borrow();
+    let thrown: Vec<&VmEvent> = events
+        .iter()
+        .filter(|event| matches!(event, VmEvent::ExceptionThrown { .. }))
+        .collect();
+    assert_eq!(
+        vec![&VmEvent::ExceptionThrown {
+            class: "java/lang/IllegalStateException".to_string()
+        }],
+        thrown
+    );
+}
+
+#[test_log::test]
+fn events_for_the_same_exception_thrown_by_consecutive_invocations() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let events = collect_events(&mut vm);
+    for _ in 0..2 {
+        let result = invoke(&mut vm, "rjvm/CachedException", "fail", "()V");
+        assert!(matches!(result, Err(MethodCallFailed::ExceptionThrown(_))));
+    }
+
+    let events = events.borrow();
+    let thrown = events
+        .iter()
+        .filter(|event| matches!(event, VmEvent::ExceptionThrown { .. }))
+        .count();
+    assert_eq!(2, thrown);
+}
+
+#[test_log::test]
+fn class_members_lookup_includes_inherited_members() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/SuperClasses",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    let base_class = vm
+        .find_class_by_name("rjvm/SuperClasses$BaseClass")
+        .expect("should have loaded the base class");
+    let derived_class = vm
+        .find_class_by_name("rjvm/SuperClasses$DerivedClass")
+        .expect("should have loaded the derived class");
+
+    let (base_value_index, _) = derived_class
+        .find_field("baseValue")
+        .expect("should find inherited field");
+    assert_eq!(
+        Some(base_value_index),
+        base_class.find_field("baseValue").map(|(index, _)| index)
+    );
+    let (derived_value_index, _) = derived_class
+        .find_field("derivedValue")
+        .expect("should find own field");
+    assert_eq!(derived_class.first_field_index, derived_value_index);
+    assert!(derived_class.find_field("missing").is_none());
+
+    assert!(derived_class.find_method("setBaseValue", "(I)V").is_none());
+    assert!(base_class.find_method("setBaseValue", "(I)V").is_some());
+    assert!(derived_class
+        .find_method("derivedClassValue", "()I")
+        .is_some());
+    assert!(derived_class
+        .find_method("derivedClassValue", "()J")
+        .is_none());
+
+    // Virtual method resolution finds the override
+    let call_stack = vm.allocate_call_stack();
+    let derived_object = vm
+        .new_object(call_stack, "rjvm/SuperClasses$DerivedClass")
+        .expect("should be able to allocate an object");
+    let sum = vm
+        .resolve_class_method(call_stack, "rjvm/SuperClasses$BaseClass", "sum", "()I")
+        .expect("should find method");
+    let result = vm.invoke_virtual(call_stack, sum, derived_object, vec![]);
+    assert_eq!(Ok(Some(Value::Int(1))), result);
+}
+
+#[test_log::test]
+fn polymorphic_call_sites() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/PolymorphicCalls",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            // legs()
+            Value::Int(4),
+            Value::Int(4),
+            Value::Int(2),
+            Value::Int(4),
+            Value::Int(2),
+            // describe()
+            Value::Int(40),
+            Value::Int(40),
+            Value::Int(20),
+            Value::Int(41),
+            Value::Int(20),
+            // area()
+            Value::Int(4),
+            Value::Int(6),
+            Value::Int(9),
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn constructors_are_not_inherited() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/InheritedConstructor",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(
+        Err(MethodCallFailed::InternalError(
+            VmError::MethodNotFoundException(
+                "rjvm/InheritedConstructor$Child".to_string(),
+                "<init>".to_string(),
+                "()V".to_string(),
+            )
+        )),
+        main_result
+    );
+    assert!(vm.printed.is_empty());
+}
+
+#[test_log::test]
+fn return_with_extra_values_on_the_stack() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ReturnWithExtraStackValues",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(2, vm.printed.len());
+    assert_eq!(Value::Int(42), vm.printed[0]);
+    assert_eq!("top", extract_printed_string(&vm, 1));
+}
+
+#[test_log::test]
+fn class_path_directory_with_custom_file_names() {
+    let mut vm = Vm::new(DEFAULT_MAX_MEMORY);
+    let src_dir = env!("CARGO_MANIFEST_DIR");
+    vm.append_class_path(&format!("{src_dir}/rt.jar"))
+        .expect("should be able to add rt.jar to the classpath");
+    vm.append_class_path_directory(&format!("{src_dir}/tests"), |class_name| {
+        PathBuf::from(format!("resources/{class_name}.class"))
+    })
+    .expect("should be able to add the directory to the classpath");
+
+    let main_result = invoke(&mut vm, "rjvm/SimpleMain", "main", "([Ljava/lang/String;)V");
+    assert_eq!(Ok(None), main_result);
+    assert_eq!(vec![Value::Int(3), Value::Int(6)], vm.printed);
+}
+
+#[test_log::test]
+fn field_resolution_across_classes() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/FieldResolution",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(1),
+            Value::Int(2),
+            Value::Int(5),
+            Value::Int(6),
+            Value::Int(2),
+            Value::Int(100),
+            Value::Int(11),
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn instanceof_resolves_the_class_once() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/RepeatedInstanceof",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+    assert_eq!(vec![Value::Int(50)], vm.printed);
+    assert_eq!(1, vm.class_reference_lookups());
+}
+
+#[test_log::test]
+fn strings_constructed_from_bytecode() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/StringConstruction",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(5, vm.printed.len());
+    assert_eq!("hello", extract_printed_string(&vm, 0));
+    assert_eq!("ell", extract_printed_string(&vm, 1));
+    assert_eq!("literal", extract_printed_string(&vm, 2));
+    assert_eq!(Value::Int(5), vm.printed[3]);
+    assert_eq!(Value::Int(1), vm.printed[4]);
+}
+
+#[test_log::test]
+fn classes_exceeding_the_reader_limits_are_not_loaded() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY).with_class_reader_limits(ClassReaderLimits {
+        max_methods: 15,
+        ..Default::default()
+    });
+    let call_stack = vm.allocate_call_stack();
+    let result = vm.get_or_resolve_class(call_stack, "rjvm/NumericTypes");
+    match result {
+        Err(MethodCallFailed::InternalError(VmError::ClassLoadingError(message))) => assert_eq!(
+            "invalid class file: number of methods is 16, which exceeds the limit of 15",
+            message
+        ),
+        _ => panic!("expected a class loading error, got {result:?}"),
+    }
+}
+
+#[test_log::test]
+fn released_call_stacks_are_reused() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    for _ in 0..20 {
+        let call_stack = vm.allocate_call_stack();
+        let main_method = vm
+            .resolve_class_method(
+                call_stack,
+                "rjvm/SimpleMain",
+                "main",
+                "([Ljava/lang/String;)V",
+            )
+            .expect("should find main method");
+        assert_eq!(Ok(None), vm.invoke(call_stack, main_method, None, vec![]));
+        vm.release_call_stack(call_stack)
+            .expect("should be able to release the call stack");
+    }
+
+    assert_eq!(40, vm.printed.len());
+    assert_eq!(1, vm.allocated_call_stacks());
+}
+
+#[test_log::test]
+fn static_fields_of_all_types() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/StaticFields",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(11, vm.printed.len());
+    assert_eq!(Value::Int(42), vm.printed[0]);
+    assert_eq!(Value::Long(1 << 40), vm.printed[1]);
+    assert_eq!(Value::Float(1.5), vm.printed[2]);
+    assert_eq!(Value::Double(2.25), vm.printed[3]);
+    assert_eq!(Value::Int(1), vm.printed[4]);
+    assert_eq!(Value::Int('x' as i32), vm.printed[5]);
+    assert_eq!("hello", extract_printed_string(&vm, 6));
+    assert_eq!(Value::Int(3), vm.printed[7]);
+    assert_eq!(Value::Int(4), vm.printed[8]);
+    assert_eq!(Value::Int(1), vm.printed[9]);
+    assert_eq!(Value::Int(1), vm.printed[10]);
+}
+
+#[test_log::test]
+fn garbage_collection_with_objects_on_the_operand_stack() {
+    let mut vm = create_base_vm(2 * ONE_MEGABYTE);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/GcWithObjectsOnTheStack",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    // Sum of 4 * i for the constructor, plus i for the static call
+    assert_eq!(vec![Value::Long(5 * 19900)], vm.printed);
+    assert!(vm.gc_stats().collections >= 10);
+}
+
+#[test_log::test]
+fn object_fields_can_be_accessed_by_name() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let class_name = "rjvm/FieldResolution$Point3D";
+    let point = vm
+        .new_object(call_stack, class_name)
+        .expect("should be able to create the object");
+    let constructor = vm
+        .resolve_class_method(call_stack, class_name, "<init>", "(III)V")
+        .expect("should find the constructor");
+    let result = vm.invoke(
+        call_stack,
+        constructor,
+        Some(point.clone()),
+        vec![Value::Int(1), Value::Int(2), Value::Int(3)],
+    );
+    assert_eq!(Ok(None), result);
+
+    // x is declared in the superclass
+    assert_eq!(Some(Value::Int(1)), vm.get_object_field(&point, "x"));
+    assert_eq!(Some(Value::Int(3)), vm.get_object_field(&point, "z"));
+    assert_eq!(None, vm.get_object_field(&point, "missing"));
+
+    assert_eq!(Ok(()), vm.set_object_field(&point, "y", Value::Int(42)));
+    assert_eq!(Some(Value::Int(42)), vm.get_object_field(&point, "y"));
+    assert_eq!(
+        Err(VmError::InvalidFieldValue(
+            class_name.to_string(),
+            "y".to_string(),
+            "Int".to_string()
+        )),
+        vm.set_object_field(&point, "y", Value::Long(1))
+    );
+    assert_eq!(
+        Err(VmError::FieldNotFoundException(
+            class_name.to_string(),
+            "missing".to_string()
+        )),
+        vm.set_object_field(&point, "missing", Value::Int(1))
+    );
+}
+
+#[test_log::test]
+fn arguments_can_be_converted_from_rust_values() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let method = vm
+        .resolve_class_method(call_stack, "rjvm/ArgumentsFromRust", "combine", "(IJFDZ)D")
+        .expect("should find method");
+    let result = vm.call_static(
+        call_stack,
+        method,
+        vec![
+            1.into(),
+            2i64.into(),
+            0.5f32.into(),
+            0.25.into(),
+            true.into(),
+        ],
+    );
+    assert_eq!(Ok(Some(Value::Double(-3.75))), result);
+}
+
+#[test_log::test]
+fn exceptions_thrown_by_native_methods_can_be_caught() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/NativeExceptionCaught",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(1),
+            Value::Int(2),
+            Value::Int(3),
+            Value::Int(0),
+            Value::Int(5)
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn math_functions() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/MathFunctions",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    let printed: Vec<f64> = vm
+        .printed
+        .iter()
+        .map(|value| value.as_double().expect("should have printed a double"))
+        .collect();
+    let expected = [
+        2f64.sqrt(),
+        1.0,
+        1.0,
+        1.0,
+        1024.0,
+        1.0,
+        3.5,
+        2.0,
+        3.0,
+        -1.0,
+        0.0,
+        0.0,
+    ];
+    assert_eq!(expected.len(), printed.len());
+    for (expected, actual) in expected.iter().zip(printed) {
+        assert!(
+            (expected - actual).abs() < 1e-12,
+            "expected {expected} but got {actual}"
+        );
+    }
+}
+
+#[test_log::test]
+fn floating_point_comparisons_and_long_shifts() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ComparisonsAndShifts",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    // Bits of the comparison results: 1 for <, 2 for <=, 4 for >, 8 for >=, and 16 for ==.
+    // Every comparison with NaN is false.
+    let comparisons = [26, 3, 0, 12, 26, 0, 0, 0, 0].map(Value::Int);
+    assert_eq!(comparisons, vm.printed[0..9]);
+    assert_eq!(comparisons, vm.printed[9..18]);
+    assert_eq!(
+        vec![
+            Value::Long(5001116544311230464),
+            Value::Long(-1),
+            Value::Long(15),
+            Value::Long(4886718345),
+        ],
+        vm.printed[18..]
+    );
+}
+
+#[test_log::test]
+fn array_copy() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(&mut vm, "rjvm/ArrayCopy", "main", "([Ljava/lang/String;)V");
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(11234),
+            Value::Int(23455),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(2),
+            Value::Int(2),
+            Value::Int(2),
+            Value::Int(3),
+            Value::Int(3),
+            Value::Int(3),
+            Value::Int(3),
+            Value::Int(3),
+            Value::Int(2),
+        ],
+        vm.printed[0..13]
+    );
+    assert_eq!("a", extract_printed_string(&vm, 13));
+    assert_eq!("x", extract_printed_string(&vm, 14));
+    assert_eq!(Value::Int(0), vm.printed[15]);
+    assert_eq!("e", extract_printed_string(&vm, 16));
+}
+
+#[test_log::test]
+fn vm_with_runtime() {
+    let runtime_jar =
+        configured_runtime_jar(Some(format!("{}/rt.jar", env!("CARGO_MANIFEST_DIR"))))
+            .expect("should accept the configured runtime");
+    let mut vm = Vm::new(DEFAULT_MAX_MEMORY)
+        .with_runtime(&runtime_jar)
+        .expect("should find the runtime");
+    vm.append_class_path(&format!("{}/tests/resources", env!("CARGO_MANIFEST_DIR")))
+        .expect("should be able to add entries to the classpath");
+
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/StringConstruction",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+    assert_eq!("hello", extract_printed_string(&vm, 0));
+}
+
+#[test_log::test]
+fn runtime_must_be_configured() {
+    for env_value in [None, Some(String::new())] {
+        let error =
+            configured_runtime_jar(env_value).expect_err("should not accept a missing runtime");
+        assert!(error
+            .to_string()
+            .starts_with("the Java runtime is not configured"));
+    }
+}
+
+#[test_log::test]
+fn vm_with_missing_runtime() {
+    let error = Vm::new(DEFAULT_MAX_MEMORY)
+        .with_runtime("/does/not/exist/rt.jar")
+        .err()
+        .expect("should not find the runtime");
+    assert!(error
+        .to_string()
+        .starts_with("cannot find the Java runtime at /does/not/exist/rt.jar"));
+}
+
+#[test_log::test]
+fn switches() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(&mut vm, "rjvm/Switches", "main", "([Ljava/lang/String;)V");
+    assert_eq!(Ok(None), main_result);
+
+    let expected: Vec<Value> = [-1, 10, 11, 23, 23, -1, 1, 2, 3, 0, 2, 0]
+        .into_iter()
+        .map(Value::Int)
+        .collect();
+    assert_eq!(expected, vm.printed);
+}
+
+#[test_log::test]
+fn wrapper_types_parsing_and_conversions() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/WrapperParsing",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    let expected: Vec<Value> = [-1234, 127, 44, 4464, 7, 35, -1, -1, -1]
+        .into_iter()
+        .map(Value::Int)
+        .collect();
+    assert_eq!(expected, vm.printed);
+}
+
+#[test_log::test]
+fn instruction_hook_can_step_over_method_calls() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let steps = Rc::new(RefCell::new(Vec::new()));
+    let steps_clone = steps.clone();
+    vm.set_instruction_hook(move |context| {
+        steps_clone.borrow_mut().push((
+            context.method_name.to_string(),
+            context.pc.0,
+            context.depth,
+        ));
+        match context.instruction {
+            Instruction::Invokestatic(_) => StepAction::StepOver,
+            _ => StepAction::StepInto,
+        }
+    });
+    let result = invoke(&mut vm, "rjvm/StepOver", "run", "()I");
+    assert_eq!(Ok(Some(Value::Int(10))), result);
+
+    let steps = steps.borrow();
+    assert!(steps.iter().all(|(method, _, _)| method != "square"));
+    // The invokestatic of square is at pc 5 and takes three bytes
+    let call_index = steps
+        .iter()
+        .position(|(method, pc, _)| method == "run" && *pc == 5)
+        .expect("should have stopped at the call of square");
+    let call_depth = steps[call_index].2;
+    assert_eq!(("run".to_string(), 8, call_depth), steps[call_index + 1]);
+}
+
+#[test_log::test]
+fn invocation_with_mismatched_argument_type() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/MismatchedArgumentType",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(
+        Err(MethodCallFailed::InternalError(
+            VmError::InvalidArgumentType(
+                "rjvm/MismatchedArgumentType".to_string(),
+                "takesString".to_string(),
+                "(Ljava/lang/String;)V".to_string(),
+                0,
+                "java/lang/String".to_string(),
+            )
+        )),
+        main_result
+    );
+}
+
+#[test_log::test]
+fn storing_a_long_invalidates_the_next_local_slot() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let result = invoke(&mut vm, "rjvm/LongStoreOverwritesNextSlot", "run", "()I");
+    assert_eq!(
+        Err(MethodCallFailed::InternalError(
+            VmError::ValidationException
+        )),
+        result
+    );
+}
+
+#[test_log::test]
+fn new_instance_invokes_the_constructor() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let initial_content = new_java_lang_string_object(&mut vm, call_stack, "hello")
+        .expect("should be able to create a string");
+    let builder = vm
+        .new_instance(
+            call_stack,
+            "java/lang/StringBuilder",
+            "(Ljava/lang/String;)V",
+            vec![Value::Object(initial_content)],
+        )
+        .expect("should be able to construct a StringBuilder");
+
+    let to_string = vm
+        .resolve_class_method(
+            call_stack,
+            "java/lang/StringBuilder",
+            "toString",
+            "()Ljava/lang/String;",
+        )
+        .expect("should find method");
+    let result = vm
+        .invoke_virtual(call_stack, to_string, builder, vec![])
+        .expect("should be able to invoke toString");
+    let Some(Value::Object(string)) = result else {
+        panic!("toString should return an object, but returned {result:?}");
+    };
+    assert_eq!(
+        "hello",
+        extract_str_from_java_lang_string(&vm, &string).expect("should have a valid string")
+    );
+}
+
+#[test_log::test]
+fn new_instance_arguments_survive_a_garbage_collection() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let to_string = vm
+        .resolve_class_method(
+            call_stack,
+            "java/lang/StringBuilder",
+            "toString",
+            "()Ljava/lang/String;",
+        )
+        .expect("should find method");
+    let initial_content = new_java_lang_string_object(&mut vm, call_stack, "hello")
+        .expect("should be able to create a string");
+
+    // Fill the heap with garbage, so that allocating the StringBuilder triggers a collection
+    let heap_usage = vm.heap_usage();
+    vm.new_array(
+        ArrayEntryType::Base(BaseType::Long),
+        (heap_usage.capacity - heap_usage.used) / 8 - 4,
+    )
+    .expect("should be able to allocate the array");
+
+    let builder = vm
+        .new_instance(
+            call_stack,
+            "java/lang/StringBuilder",
+            "(Ljava/lang/String;)V",
+            vec![Value::Object(initial_content)],
+        )
+        .expect("should be able to construct a StringBuilder");
+    assert_eq!(1, vm.gc_stats().collections);
+
+    let result = vm
+        .invoke_virtual(call_stack, to_string, builder, vec![])
+        .expect("should be able to invoke toString");
+    let Some(Value::Object(string)) = result else {
+        panic!("toString should return an object, but returned {result:?}");
+    };
+    assert_eq!(
+        "hello",
+        extract_str_from_java_lang_string(&vm, &string).expect("should have a valid string")
+    );
+}
+
+#[test_log::test]
+fn new_instance_survives_a_garbage_collection_in_the_constructor() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let object = vm
+        .new_instance(
+            call_stack,
+            "rjvm/GcInConstructor",
+            "(I)V",
+            vec![Value::Int(42)],
+        )
+        .expect("should be able to construct the object");
+
+    assert_eq!(1, vm.gc_stats().collections);
+    assert_eq!(Some(Value::Int(42)), vm.get_object_field(&object, "value"));
+}
+
+#[test_log::test]
+fn new_instance_validates_the_constructor_arguments() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let result = vm.new_instance(
+        call_stack,
+        "java/lang/StringBuilder",
+        "(Ljava/lang/String;)V",
+        vec![Value::Int(1)],
+    );
+    assert!(matches!(
+        result,
+        Err(MethodCallFailed::InternalError(
+            VmError::InvalidArgumentType(..)
+        ))
+    ));
+}
+
+#[test_log::test]
+fn invoke_static_by_name() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let result = vm.invoke_static_by_name(
+        call_stack,
+        "rjvm/ArgumentsValidation",
+        "sum",
+        "(II)I",
+        vec![Value::Int(2), Value::Int(3)],
+    );
+    assert_eq!(Ok(Some(Value::Int(5))), result);
+
+    let result = vm.invoke_static_by_name(
+        call_stack,
+        "rjvm/ArgumentsValidation",
+        "increment",
+        "(I)I",
+        vec![Value::Int(1)],
+    );
+    assert_eq!(
+        Err(MethodCallFailed::InternalError(VmError::MethodIsNotStatic(
+            "rjvm/ArgumentsValidation".to_string(),
+            "increment".to_string(),
+            "(I)I".to_string(),
+        ))),
+        result
+    );
+}
+
+#[test_log::test]
+fn invoke_static_by_name_rejects_nested_arrays_for_arrays_of_primitives() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let nested_array = vm
+        .new_array(
+            ArrayEntryType::array_of(ArrayEntryType::Base(BaseType::Int)),
+            1,
+        )
+        .expect("should be able to allocate the array");
+    let result = vm.invoke_static_by_name(
+        call_stack,
+        "java/util/Arrays",
+        "hashCode",
+        "([I)I",
+        vec![Value::Object(nested_array)],
+    );
+    assert!(matches!(
+        result,
+        Err(MethodCallFailed::InternalError(
+            VmError::InvalidArgumentType(..)
+        ))
+    ));
+}
+
+#[test_log::test]
+fn system_exit_unwinds_without_running_finally_blocks() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(&mut vm, "rjvm/SystemExit", "main", "([Ljava/lang/String;)V");
+    assert_eq!(Err(MethodCallFailed::Exit(7)), main_result);
+    assert_eq!(vec![Value::Int(1)], vm.printed);
+}
+
+#[test_log::test]
+fn run_main() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let exit_code = vm.run_main(call_stack, "rjvm/SimpleMain", &[]);
+    assert_eq!(Ok(0), exit_code);
+    assert_eq!(vec![Value::Int(3), Value::Int(6)], vm.printed);
+}
+
+#[test_log::test]
+fn run_main_returns_the_exit_code() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let exit_code = vm.run_main(call_stack, "rjvm/IntMain", &["a".to_string()]);
+    assert_eq!(Ok(41), exit_code);
+
+    let exit_code = vm.run_main(call_stack, "rjvm/SystemExit", &[]);
+    assert_eq!(Ok(7), exit_code);
+}
+
+#[test_log::test]
+fn run_main_arguments_survive_a_garbage_collection() {
+    let mut vm = create_base_vm(2 * ONE_MEGABYTE);
+    let call_stack = vm.allocate_call_stack();
+    // Fill most of the heap with garbage, so that creating the arguments triggers a collection
+    vm.new_array(ArrayEntryType::Base(BaseType::Long), 100_000)
+        .expect("should be able to allocate the array");
+    let args: Vec<String> = (0..1000).map(|i| format!("{i:022}")).collect();
+    let exit_code = vm.run_main(call_stack, "rjvm/MainArguments", &args);
+    assert_eq!(Ok(0), exit_code);
+    assert!(vm.gc_stats().collections > 0);
+    assert_eq!(vec![Value::Int(1000), Value::Int(1000)], vm.printed);
+}
+
+#[test_log::test]
+fn current_thread() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/CurrentThread",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!("main", extract_printed_string(&vm, 0));
+    assert_eq!(vec![Value::Int(1), Value::Int(5)], vm.printed[1..]);
+}
+
+#[test_log::test]
+fn unbalanced_monitors() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/UnbalancedMonitors",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![Value::Int(1), Value::Int(2), Value::Int(3)],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn narrow_types_are_normalized_when_stored() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/NarrowTypesNormalization",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![Value::Int(1), Value::Int(1), Value::Int(1), Value::Int(65)],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn string_interning() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/StringInterning",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(1),
+        ],
+        vm.printed
+    );
+
+    let call_stack = vm.allocate_call_stack();
+    let lone_surrogate = vm
+        .invoke_static_by_name(
+            call_stack,
+            "rjvm/StringInterning",
+            "loneSurrogate",
+            "(C)Ljava/lang/String;",
+            vec![Value::Int(0xD800)],
+        )
+        .expect("should be able to create the string")
+        .expect("should return a string");
+    assert_eq!("\u{FFFD}", vm.format_value(&lone_surrogate));
+}
+
+#[test_log::test]
+fn objects_utilities_and_object_identity() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ObjectsUtilities",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(0),
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn format_value() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    assert_eq!("42", vm.format_value(&Value::Int(42)));
+    assert_eq!("-7", vm.format_value(&Value::Long(-7)));
+    assert_eq!("1.5", vm.format_value(&Value::Float(1.5)));
+    assert_eq!("2.0", vm.format_value(&Value::Double(2.0)));
+    assert_eq!("1.0E10", vm.format_value(&Value::Double(1e10)));
+    assert_eq!("null", vm.format_value(&Value::Null));
+
+    let call_stack = vm.allocate_call_stack();
+    let string = new_java_lang_string_object(&mut vm, call_stack, "hello")
+        .expect("should be able to create a string");
+    assert_eq!("hello", vm.format_value(&Value::Object(string)));
+
+    let object = vm
+        .new_instance(call_stack, "java/lang/Object", "()V", vec![])
+        .expect("should be able to create an object");
+    assert_eq!(
+        format!("java.lang.Object@{:x}", object.identity_hash_code()),
+        vm.format_value(&Value::Object(object))
+    );
+
+    let array = vm
+        .new_array(ArrayEntryType::Base(BaseType::Int), 2)
+        .expect("should be able to allocate the array");
+    assert_eq!(
+        format!("[I@{:x}", array.identity_hash_code()),
+        vm.format_value(&Value::Object(array))
+    );
+
+    let matrix = vm
+        .new_array(
+            ArrayEntryType::array_of(ArrayEntryType::array_of(ArrayEntryType::Base(
+                BaseType::Int,
+            ))),
+            2,
+        )
+        .expect("should be able to allocate the array");
+    assert_eq!(
+        format!("[[[I@{:x}", matrix.identity_hash_code()),
+        vm.format_value(&Value::Object(matrix))
+    );
+}
+
+/// An output that can be read after being given to the vm
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl std::io::Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Replaces the error output of the vm, and returns the buffer that will collect it
+fn capture_error_output(vm: &mut Vm<'static>) -> Rc<RefCell<Vec<u8>>> {
+    let error_output = Rc::new(RefCell::new(Vec::new()));
+    vm.set_error_output(SharedOutput(error_output.clone()));
+    error_output
+}
+
+fn lines_of(output: &RefCell<Vec<u8>>) -> Vec<String> {
+    String::from_utf8_lossy(&output.borrow())
+        .lines()
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test_log::test]
+fn print_stack_trace() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let error_output = capture_error_output(&mut vm);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/PrintStackTrace",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+    assert_eq!(1, vm.printed.len());
+    assert_eq!("done", extract_printed_string(&vm, 0));
+    assert_eq!(
+        vec![
+            "java.lang.IllegalStateException: something went wrong",
+            "\tat java.lang.Throwable.fillInStackTrace(Throwable.java:783)",
+            "\tat java.lang.Throwable.<init>(Throwable.java:265)",
+            "\tat java.lang.Exception.<init>(Exception.java:66)",
+            "\tat java.lang.RuntimeException.<init>(RuntimeException.java:62)",
+            "\tat java.lang.IllegalStateException.<init>(IllegalStateException.java:55)",
+            "\tat rjvm.PrintStackTrace.fail(PrintStackTrace.java:14)",
+            "\tat rjvm.PrintStackTrace.main(PrintStackTrace.java:6)",
+        ],
+        lines_of(&error_output)
+    );
+}
+
+#[test_log::test]
+fn chained_exceptions() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let error_output = capture_error_output(&mut vm);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ChainedExceptions",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(5, vm.printed.len());
+    assert_eq!(Value::Int(1), vm.printed[0]);
+    assert_eq!("root cause", extract_printed_string(&vm, 1));
+    assert_eq!(
+        vec![Value::Int(1), Value::Int(1), Value::Int(1)],
+        vm.printed[2..]
+    );
+    assert_eq!(
+        vec![
+            "java.lang.RuntimeException: wrapper",
+            "\tat java.lang.Throwable.fillInStackTrace(Throwable.java:783)",
+            "\tat java.lang.Throwable.<init>(Throwable.java:287)",
+            "\tat java.lang.Exception.<init>(Exception.java:84)",
+            "\tat java.lang.RuntimeException.<init>(RuntimeException.java:80)",
+            "\tat rjvm.ChainedExceptions.wrap(ChainedExceptions.java:24)",
+            "\tat rjvm.ChainedExceptions.main(ChainedExceptions.java:6)",
+            "Caused by: java.lang.IllegalStateException: root cause",
+            "\tat java.lang.Throwable.fillInStackTrace(Throwable.java:783)",
+            "\tat java.lang.Throwable.<init>(Throwable.java:265)",
+            "\tat java.lang.Exception.<init>(Exception.java:66)",
+            "\tat java.lang.RuntimeException.<init>(RuntimeException.java:62)",
+            "\tat java.lang.IllegalStateException.<init>(IllegalStateException.java:55)",
+            "\tat rjvm.ChainedExceptions.fail(ChainedExceptions.java:29)",
+            "\tat rjvm.ChainedExceptions.wrap(ChainedExceptions.java:22)",
+            "\t... 1 more",
+        ],
+        lines_of(&error_output)
+    );
+}
+
+#[test_log::test]
+fn circular_causes_are_printed_once() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let error_output = capture_error_output(&mut vm);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/CircularCauses",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    let lines = lines_of(&error_output);
+    assert_eq!("java.lang.Exception: first", lines[0]);
+    let causes: Vec<&String> = lines
+        .iter()
+        .filter(|line| !line.starts_with("\tat "))
+        .collect();
+    assert_eq!(
+        vec![
+            "java.lang.Exception: first",
+            "Caused by: java.lang.Exception: second",
+            "\t[CIRCULAR REFERENCE:java.lang.Exception: first]",
+        ],
+        causes
+    );
+}
+
+#[test_log::test]
+fn static_final_fields_are_initialized_from_their_constant_value() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/StaticConstants",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+    assert_eq!(
+        vec![
+            Value::Int(5),
+            Value::Long(1234567890123),
+            Value::Float(1.5),
+            Value::Double(2.25),
+            Value::Int(1),
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn object_fields() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let result = vm
+        .invoke_static_by_name(
+            call_stack,
+            "rjvm/ObjectFields",
+            "create",
+            "()Lrjvm/ObjectFields$Derived;",
+            vec![],
+        )
+        .expect("should be able to invoke create");
+    let Some(Value::Object(object)) = result else {
+        panic!("create should return an object, but returned {result:?}");
+    };
+
+    let fields = vm
+        .object_fields(&object)
+        .expect("should be able to read the fields");
+    let names: Vec<&str> = fields.iter().map(|(name, _)| *name).collect();
+    assert_eq!(vec!["id", "big", "ratio", "flag", "name", "other"], names);
+    assert_eq!(Value::Int(7), fields[0].1);
+    assert_eq!(Value::Long(1234567890123), fields[1].1);
+    assert_eq!(Value::Double(0.5), fields[2].1);
+    assert_eq!(Value::Int(1), fields[3].1);
+    assert_eq!("derived", vm.format_value(&fields[4].1));
+
+    let Value::Object(other) = &fields[5].1 else {
+        panic!("expected an object reference, but got {:?}", fields[5].1);
+    };
+    assert_eq!(
+        vec![("id", Value::Int(7))],
+        vm.object_fields(other)
+            .expect("should be able to read the fields")
+    );
+
+    let array = vm
+        .new_array(ArrayEntryType::Base(BaseType::Int), 1)
+        .expect("should be able to allocate the array");
+    assert_eq!(Err(VmError::ValidationException), vm.object_fields(&array));
+}
+
+#[test_log::test]
+fn object_clone() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ObjectClone",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+    assert_eq!(
+        vec![
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(3),
+            Value::Long(4),
+            Value::Int(1),
+            Value::Int(3),
+            Value::Int(1),
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn super_calls() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(&mut vm, "rjvm/SuperCalls", "main", "([Ljava/lang/String;)V");
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(2, vm.printed.len());
+    assert_eq!("derived, middle, base", extract_printed_string(&vm, 0));
+    assert_eq!("patched, middle, base", extract_printed_string(&vm, 1));
+}
+
+/// Each java frame uses a lot of native stack in debug builds, so the tests that recurse
+/// deeply, up to 1000 frames, are run in a thread with a larger stack than the default one
+fn run_with_large_stack(test: impl FnOnce() + Send + 'static) {
+    std::thread::Builder::new()
+        .stack_size(native_stack_size_for_call_stack_depth(1000))
+        .spawn(test)
+        .expect("should be able to spawn the thread")
+        .join()
+        .expect("test should succeed");
+}
+
+#[test_log::test]
+fn stack_overflow_names_the_recursive_method() {
+    run_with_large_stack(|| {
+        let mut vm = create_base_vm(DEFAULT_MAX_MEMORY).with_max_call_stack_depth(1000);
+        let main_result = invoke(
+            &mut vm,
+            "rjvm/StackOverflow",
+            "main",
+            "([Ljava/lang/String;)V",
+        );
+        assert_eq!(Ok(None), main_result);
+
+        assert_eq!(2, vm.printed.len());
+        assert_eq!(Value::Int(125250), vm.printed[0]);
+        assert_eq!(
+            "call stack depth exceeded 1000, recursion in rjvm/StackOverflow::ping",
+            extract_printed_string(&vm, 1)
+        );
+    });
+}
+
+#[test_log::test]
+fn max_call_stack_depth_can_be_configured() {
+    run_with_large_stack(|| {
+        let mut vm = create_base_vm(DEFAULT_MAX_MEMORY).with_max_call_stack_depth(100);
+        let main_result = invoke(
+            &mut vm,
+            "rjvm/StackOverflow",
+            "main",
+            "([Ljava/lang/String;)V",
+        );
+        assert!(matches!(
+            main_result,
+            Err(MethodCallFailed::ExceptionThrown(_))
+        ));
+    });
+}
+
+#[test_log::test]
+fn dump_constant_pool() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    assert_eq!(None, vm.dump_constant_pool("rjvm/SimpleMain"));
+
+    let main_result = invoke(&mut vm, "rjvm/SimpleMain", "main", "([Ljava/lang/String;)V");
+    assert_eq!(Ok(None), main_result);
+
+    let dump = vm
+        .dump_constant_pool("rjvm/SimpleMain")
+        .expect("class should have been loaded");
+    assert!(dump.starts_with("Constant pool: "));
+    // The indexes of the entries depend on the compiler, so we only check their content
+    assert!(dump.lines().any(|line| line.contains("MethodReference: ")
+        && line.contains("(ClassReference: ")
+        && line.contains("(String: \"rjvm/SimpleMain$Generator\")), (NameAndTypeDescriptor: ")
+        && line.ends_with("(String: \"next\"), (String: \"()I\"))")));
+}
+
+#[test_log::test]
+fn type_confusion_in_locals_is_rejected() {
+    for (method_name, descriptor) in [("nullAsInt", "()I"), ("overwrittenLong", "()J")] {
+        let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+        let call_stack = vm.allocate_call_stack();
+        let method = vm
+            .resolve_class_method(
+                call_stack,
+                "rjvm/LocalTypeConfusion",
+                method_name,
+                descriptor,
+            )
+            .expect("should find method");
+        let result = vm.invoke(call_stack, method, None, vec![]);
+        assert_eq!(
+            Err(MethodCallFailed::InternalError(
+                VmError::ValidationException
+            )),
+            result,
+            "wrong result for method {method_name}"
+        );
+    }
+}
+
+#[test_log::test]
+fn array_bulk_operations() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ArrayBulkOperations",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+    assert_eq!(
+        vec![
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(0),
+            Value::Int(99998 * 3),
+            Value::Int(14),
+            Value::Long(1 << 40),
+            Value::Double(0.5),
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn arrays_fill_and_equals() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ArraysFillAndEquals",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(13, vm.printed.len());
+    assert_eq!(
+        vec![
+            Value::Int(14),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(0),
+        ],
+        vm.printed[0..9]
+    );
+    assert_eq!("x", extract_printed_string(&vm, 9));
+    assert_eq!("IllegalArgumentException", extract_printed_string(&vm, 10));
+    assert_eq!(
+        "ArrayIndexOutOfBoundsException",
+        extract_printed_string(&vm, 11)
+    );
+    assert_eq!("ArrayStoreException", extract_printed_string(&vm, 12));
+}
+
+#[test_log::test]
+fn reset_allows_running_programs_independently() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    for keep_loaded_classes in [true, false] {
+        let main_result = invoke(&mut vm, "rjvm/VmReset", "main", "([Ljava/lang/String;)V");
+        assert_eq!(Ok(None), main_result);
+        assert_eq!(Value::Int(11), vm.printed[0]);
+        assert_eq!("log:11", extract_printed_string(&vm, 1));
+        assert_eq!(Value::Int(1), vm.printed[2]);
+
+        vm.reset(keep_loaded_classes);
+        assert!(vm.printed.is_empty());
+        assert_eq!(0, vm.heap_usage().used);
+        assert_eq!(
+            keep_loaded_classes,
+            vm.find_class_by_name("rjvm/VmReset").is_some()
+        );
+    }
+
+    let main_result = invoke(&mut vm, "rjvm/SimpleMain", "main", "([Ljava/lang/String;)V");
+    assert_eq!(Ok(None), main_result);
+    assert_eq!(vec![Value::Int(3), Value::Int(6)], vm.printed);
+}
+
+#[test_log::test]
+fn reset_initializes_the_kept_superclasses_of_new_classes() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(&mut vm, "rjvm/VmReset", "main", "([Ljava/lang/String;)V");
+    assert_eq!(Ok(None), main_result);
+
+    // VmReset$Late is loaded only after the reset, but its superclass was kept
+    vm.reset(true);
+    let result = invoke(&mut vm, "rjvm/VmReset", "runLate", "()V");
+    assert_eq!(Ok(None), result);
+    assert_eq!(vec![Value::Int(100)], vm.printed);
+}
+
+#[test_log::test]
+fn preloaded_classes_are_not_loaded_again() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let classes = [
+        "rjvm/SuperClasses",
+        "rjvm/SuperClasses$BaseClass",
+        "rjvm/SuperClasses$DerivedClass",
+    ];
+    let call_stack = vm.allocate_call_stack();
+    vm.preload_classes(call_stack, &classes)
+        .expect("should be able to preload the classes");
+    for class_name in classes {
+        assert!(vm.find_class_by_name(class_name).is_some());
+    }
+
+    let events = collect_events(&mut vm);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/SuperClasses",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+    assert!(!events.borrow().iter().any(|event| matches!(
+        event,
+        VmEvent::ClassLoaded { name, .. } if classes.contains(&name.as_str())
+    )));
+}
+
+#[test_log::test]
+fn preloading_a_missing_class_fails() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let result = vm.preload_classes(call_stack, &["rjvm/SimpleMain", "rjvm/DoesNotExist"]);
+    assert_eq!(
+        Err(MethodCallFailed::InternalError(
+            VmError::ClassNotFoundException("rjvm/DoesNotExist".to_string())
+        )),
+        result
+    );
+    assert!(vm.find_class_by_name("rjvm/SimpleMain").is_some());
+}
+
+#[test_log::test]
+fn take_printed() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+
+    let first_result = invoke(&mut vm, "rjvm/PrintingPhases", "first", "()V");
+    assert_eq!(Ok(None), first_result);
+    assert_eq!(vec![Value::Int(1), Value::Int(2)], vm.take_printed());
+
+    let second_result = invoke(&mut vm, "rjvm/PrintingPhases", "second", "()V");
+    assert_eq!(Ok(None), second_result);
+    assert_eq!(vec![Value::Int(3)], vm.take_printed());
+    assert!(vm.printed.is_empty());
+}
+
+#[test_log::test]
+fn class_names() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(&mut vm, "rjvm/ClassNames", "main", "([Ljava/lang/String;)V");
+    assert_eq!(Ok(None), main_result);
+
+    let printed: Vec<String> = (0..vm.printed.len())
+        .map(|index| extract_printed_string(&vm, index))
+        .collect();
+    assert_eq!(
+        vec![
+            "java.lang.Object",
+            "Object",
+            "rjvm.ClassNames$Inner",
+            "Inner",
+            "[I",
+            "int[]",
+            "[[Ljava.lang.String;",
+            "String[][]",
+            "[[I",
+            "[[[Ljava.lang.String;",
+        ],
+        printed
+    );
+}
+
+#[test_log::test]
+fn custom_native() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    vm.register_native("rjvm/CustomNative", "compute", "(I)I", |_, _, _, args| {
+        let value = expect_int_at(&args, 0)?;
+        Ok(Some(Value::Int(value * 2 + 2)))
+    })
+    .expect("should be able to register a new native method");
+
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/CustomNative",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+    assert_eq!(vec![Value::Int(42)], vm.printed);
+}
+
+#[test_log::test]
+fn custom_native_cannot_replace_built_in() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let result = vm.register_native("java/lang/System", "nanoTime", "()J", |_, _, _, _| {
+        Ok(Some(Value::Long(0)))
+    });
+    assert_eq!(
+        Err(VmError::NativeMethodAlreadyRegistered(
+            "java/lang/System".to_string(),
+            "nanoTime".to_string(),
+            "()J".to_string()
+        )),
+        result
+    );
+}
+
+#[test_log::test]
+fn array_and_primitive_classes() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ArrayAndPrimitiveClasses",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(0),
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(1),
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn call_static_validates_arguments() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let method = vm
+        .resolve_class_method(call_stack, "rjvm/ArgumentsValidation", "twice", "(J)J")
+        .expect("should find method");
+
+    let result = vm.call_static(call_stack, method.clone(), vec![Value::Long(21)]);
+    assert_eq!(Ok(Some(Value::Long(42))), result);
+
+    let result = vm.call_static(call_stack, method.clone(), vec![Value::Int(21)]);
+    assert_eq!(
+        Err(MethodCallFailed::InternalError(
+            VmError::InvalidArgumentType(
+                "rjvm/ArgumentsValidation".to_string(),
+                "twice".to_string(),
+                "(J)J".to_string(),
+                0,
+                "Long".to_string()
+            )
+        )),
+        result
+    );
+
+    let result = vm.call_static(call_stack, method, vec![]);
+    assert_eq!(
+        Err(MethodCallFailed::InternalError(
+            VmError::InvalidArgumentsCount(
+                "rjvm/ArgumentsValidation".to_string(),
+                "twice".to_string(),
+                "(J)J".to_string(),
+                1,
+                0
+            )
+        )),
+        result
+    );
+}
+
+#[test_log::test]
+fn invoke_virtual_validates_arguments() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let call_stack = vm.allocate_call_stack();
+    let method = vm
+        .resolve_class_method(call_stack, "rjvm/ArgumentsValidation", "increment", "(I)I")
+        .expect("should find method");
+    let receiver = vm
+        .new_object(call_stack, "rjvm/ArgumentsValidation")
+        .expect("should be able to allocate object");
+
+    let result = vm.invoke_virtual(
+        call_stack,
+        method.clone(),
+        receiver.clone(),
+        vec![Value::Int(41)],
+    );
+    assert_eq!(Ok(Some(Value::Int(42))), result);
+
+    let result = vm.invoke_virtual(call_stack, method, receiver, vec![Value::Double(41.0)]);
+    assert!(matches!(
+        result,
+        Err(MethodCallFailed::InternalError(
+            VmError::InvalidArgumentType(..)
+        ))
+    ));
+}
+
+#[test_log::test]
+fn abstract_method_call() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/AbstractMethodCall",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(vec![Value::Int(-1)], vm.printed);
+}
+
+#[test_log::test]
+fn exception_messages() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ExceptionMessages",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(2, vm.printed.len());
+    assert_eq!("boom", extract_printed_string(&vm, 0));
+    assert_eq!(Value::Int(1), vm.printed[1]);
+
+    let fail_result = invoke(&mut vm, "rjvm/ExceptionMessages", "fail", "()V");
+    match fail_result {
+        Err(MethodCallFailed::ExceptionThrown(exception)) => assert_eq!(
+            Ok(Some("something went wrong".to_string())),
+            exception.message(&vm)
+        ),
+        _ => panic!("expected an exception, got {fail_result:?}"),
+    }
+}
+
+#[test_log::test]
+fn required_natives() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    assert_eq!(
+        Err(VmError::ClassNotFoundException(
+            "rjvm/CustomNative".to_string()
+        )),
+        vm.required_natives("rjvm/CustomNative")
+    );
+
+    let call_stack = vm.allocate_call_stack();
+    vm.get_or_resolve_class(call_stack, "rjvm/CustomNative")
+        .expect("should be able to load class");
+    assert_eq!(
+        Ok(vec![
+            ("compute".to_string(), "(I)I".to_string()),
+            ("tempPrint".to_string(), "(I)V".to_string()),
+        ]),
+        vm.required_natives("rjvm/CustomNative")
+    );
+}
+
+#[test_log::test]
+fn incompatible_invocations() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/IncompatibleInvocations",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(vec![Value::Int(10), Value::Int(20)], vm.printed);
+}
+
+#[test_log::test]
+fn array_covariance() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/ArrayCovariance",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(0),
+        ],
+        vm.printed
+    );
+}
+
+#[test_log::test]
+fn nested_array_types() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/NestedArrayTypes",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Int(1),
+        ],
+        vm.printed
+    );
+
+    let cast_result = invoke(
+        &mut vm,
+        "rjvm/NestedArrayTypes",
+        "castToIntegers",
+        "()[[Ljava/lang/Integer;",
+    );
+    assert_eq!(
+        Err(MethodCallFailed::InternalError(VmError::ClassCastException)),
+        cast_result
+    );
+}
+
+#[test_log::test]
+fn longs_on_stack() {
+    let mut vm = create_base_vm(DEFAULT_MAX_MEMORY);
+    let main_result = invoke(
+        &mut vm,
+        "rjvm/LongsOnStack",
+        "main",
+        "([Ljava/lang/String;)V",
+    );
+    assert_eq!(Ok(None), main_result);
+
+    assert_eq!(
+        vec![
+            Value::Long(10),
+            Value::Long(10),
+            Value::Long(11),
+            Value::Long(20),
+            Value::Long(21),
+            Value::Long(9),
+        ],
+        vm.printed
+    );
+}
@@ -1,2 +1,4 @@
 #!/usr/bin/env sh
-javac -source 6 -target 6 rjvm/*.java
+set -e
+javac -nowarn -source 7 -target 7 -bootclasspath ../../rt.jar rjvm/*.java
+./patch_classes.py
@@ -0,0 +1,345 @@
+#!/usr/bin/env python3
+# Generates the class files used by the tests that javac would never produce. Some of them
+# are compiled from a modified version of their source, to simulate classes compiled against
+# a different version of their dependencies; others are compiled normally and then patched.
+#
+# Run by compile.sh, after compiling all the sources. Every patch checks the bytes it replaces,
+# so that it fails loudly rather than producing a wrong class if the output of javac changes.
+
+import os
+import shutil
+import struct
+import subprocess
+import sys
+import tempfile
+
+# Keep in sync with compile.sh
+JAVAC = ["javac", "-nowarn", "-source", "7", "-target", "7", "-bootclasspath", "../../rt.jar"]
+
+# Opcodes used by the patches
+NOP = 0x00
+ICONST_1 = 0x04
+ICONST_3 = 0x06
+BIPUSH = 0x10
+LDC = 0x12
+ILOAD_0 = 0x1A
+ILOAD_1 = 0x1B
+ASTORE = 0x3A
+ISTORE_0 = 0x3B
+LSTORE_1 = 0x40
+LSTORE_3 = 0x42
+ASTORE_0 = 0x4B
+ASTORE_1 = 0x4C
+ASTORE_2 = 0x4D
+ASTORE_3 = 0x4E
+I2C = 0x92
+IFNE = 0x9A
+MONITORENTER = 0xC2
+MONITOREXIT = 0xC3
+
+# Constant pool tags
+CONSTANT_UTF8 = 1
+CONSTANT_CLASS = 7
+CONSTANT_STRING = 8
+CONSTANT_METHODREF = 10
+
+# Size of the content of each constant pool entry, after the tag. Utf8 entries have a variable size.
+CONSTANT_SIZES = {3: 4, 4: 4, 5: 8, 6: 8, 7: 2, 8: 2, 9: 4, 10: 4, 11: 4, 12: 4, 15: 3, 16: 2, 17: 4, 18: 4, 19: 2, 20: 2}
+
+
+class ClassFile:
+    """A compiled class, parsed just enough to locate the bytes to patch"""
+
+    def __init__(self, path):
+        self.path = path
+        with open(path, "rb") as f:
+            self.data = bytearray(f.read())
+        self.constants = {}
+        self.methods = {}
+        self._parse()
+
+    def u2(self, offset):
+        return struct.unpack_from(">H", self.data, offset)[0]
+
+    def u4(self, offset):
+        return struct.unpack_from(">I", self.data, offset)[0]
+
+    def _parse(self):
+        offset = self._parse_constants(8)
+        interfaces_count = self.u2(offset + 6)
+        offset += 8 + 2 * interfaces_count
+        offset, _ = self._parse_members(offset)
+        _, self.methods = self._parse_members(offset)
+
+    def _parse_constants(self, offset):
+        count = self.u2(offset)
+        offset += 2
+        index = 1
+        while index < count:
+            tag = self.data[offset]
+            self.constants[index] = (tag, offset + 1)
+            if tag == CONSTANT_UTF8:
+                offset += 3 + self.u2(offset + 1)
+            else:
+                offset += 1 + CONSTANT_SIZES[tag]
+            # Long and double entries take two slots
+            index += 2 if tag in (5, 6) else 1
+        return offset
+
+    def _parse_members(self, offset):
+        members = {}
+        count = self.u2(offset)
+        offset += 2
+        for _ in range(count):
+            name = self.utf8(self.u2(offset + 2))
+            descriptor = self.utf8(self.u2(offset + 4))
+            attributes = {}
+            attributes_count = self.u2(offset + 6)
+            offset += 8
+            for _ in range(attributes_count):
+                attribute_name = self.utf8(self.u2(offset))
+                attributes[attribute_name] = offset + 6
+                offset += 6 + self.u4(offset + 2)
+            members[(name, descriptor)] = attributes
+        return offset, members
+
+    def utf8(self, index):
+        tag, offset = self.constants[index]
+        assert tag == CONSTANT_UTF8, f"constant {index} is not an utf8"
+        length = self.u2(offset)
+        return self.data[offset + 2 : offset + 2 + length].decode("utf-8")
+
+    def find_constant(self, tag, predicate):
+        for index, (constant_tag, offset) in self.constants.items():
+            if constant_tag == tag and predicate(offset):
+                return index, offset
+        raise AssertionError(f"constant not found in {self.path}")
+
+    def find_utf8(self, value):
+        return self.find_constant(CONSTANT_UTF8, lambda offset: self.utf8_at(offset) == value)
+
+    def utf8_at(self, offset):
+        return self.data[offset + 2 : offset + 2 + self.u2(offset)].decode("utf-8")
+
+    def find_string(self, value):
+        return self.find_constant(CONSTANT_STRING, lambda offset: self.utf8(self.u2(offset)) == value)
+
+    def find_class(self, name):
+        return self.find_constant(CONSTANT_CLASS, lambda offset: self.utf8(self.u2(offset)) == name)
+
+    def find_methodref(self, class_name, name, descriptor):
+        class_index, _ = self.find_class(class_name)
+
+        def matches(offset):
+            if self.u2(offset) != class_index:
+                return False
+            _, name_and_type = self.constants[self.u2(offset + 2)]
+            return (self.utf8(self.u2(name_and_type)), self.utf8(self.u2(name_and_type + 2))) == (
+                name,
+                descriptor,
+            )
+
+        return self.find_constant(CONSTANT_METHODREF, matches)
+
+    def code_offset(self, method):
+        """Returns the offset of the Code attribute of the given (name, descriptor) method"""
+        attributes = self.methods[method]
+        assert "Code" in attributes, f"method {method} of {self.path} has no code"
+        return attributes["Code"]
+
+    def replace(self, offset, expected, replacement, what):
+        expected = bytes(expected)
+        replacement = bytes(replacement)
+        assert len(expected) == len(replacement), f"{what}: patches cannot change the size"
+        actual = bytes(self.data[offset : offset + len(expected)])
+        assert actual == expected, f"{what}: expected {expected.hex()} but found {actual.hex()}"
+        self.data[offset : offset + len(expected)] = replacement
+
+    def patch_code(self, method, pc, expected, replacement):
+        code = self.code_offset(method) + 8
+        self.replace(code + pc, expected, replacement, f"{self.path} {method} at pc {pc}")
+
+    def set_max_stack(self, method, expected, value):
+        self.replace(
+            self.code_offset(method),
+            struct.pack(">H", expected),
+            struct.pack(">H", value),
+            f"{self.path} {method} max_stack",
+        )
+
+    def set_major_version(self, expected, value):
+        self.replace(6, struct.pack(">H", expected), struct.pack(">H", value), f"{self.path} version")
+
+    def rename_utf8(self, old, new):
+        _, offset = self.find_utf8(old)
+        self.replace(offset + 2, old.encode("utf-8"), new.encode("utf-8"), f"{self.path} {old}")
+
+    def set_methodref_class(self, method_ref, class_name):
+        _, offset = self.find_methodref(*method_ref)
+        old_class_index = self.u2(offset)
+        new_class_index, _ = self.find_class(class_name)
+        self.replace(
+            offset,
+            struct.pack(">H", old_class_index),
+            struct.pack(">H", new_class_index),
+            f"{self.path} class of {method_ref}",
+        )
+
+    def save(self):
+        with open(self.path, "wb") as f:
+            f.write(self.data)
+
+
+def patch(class_name, apply):
+    class_file = ClassFile(f"rjvm/{class_name}.class")
+    apply(class_file)
+    class_file.save()
+
+
+def compile_modified(sources, replacements, classes):
+    """Compiles the given sources, after applying the replacements, and copies the given
+    classes in place of the ones compiled from the unmodified sources"""
+    with tempfile.TemporaryDirectory() as output_dir:
+        source_dir = os.path.join(output_dir, "src")
+        os.makedirs(os.path.join(source_dir, "rjvm"))
+        paths = []
+        for source in sources:
+            with open(f"rjvm/{source}.java") as f:
+                text = f.read()
+            for old, new in replacements.get(source, []):
+                assert old in text, f"{source}.java does not contain {old!r}"
+                text = text.replace(old, new)
+            path = os.path.join(source_dir, "rjvm", f"{source}.java")
+            with open(path, "w") as f:
+                f.write(text)
+            paths.append(path)
+        subprocess.run(JAVAC + ["-cp", ".", "-d", output_dir] + paths, check=True)
+        for class_name in classes:
+            shutil.copyfile(os.path.join(output_dir, "rjvm", f"{class_name}.class"), f"rjvm/{class_name}.class")
+
+
+def main():
+    # Base.value is concrete and Impl does not override it
+    compile_modified(
+        ["AbstractMethodCall"],
+        {
+            "AbstractMethodCall": [
+                ("abstract int value();", "int value() {\n            return 0;\n        }"),
+                ("int value() {\n            return 42;\n        }", ""),
+            ]
+        },
+        ["AbstractMethodCall$Impl"],
+    )
+
+    # staticMethod is an instance method and instanceMethod is static
+    compile_modified(
+        ["IncompatibleInvocations"],
+        {
+            "IncompatibleInvocations": [
+                ("static int staticMethod()", "int staticMethod()"),
+                ("int instanceMethod()", "static int instanceMethod()"),
+                ("Target.staticMethod()", "new Target().staticMethod()"),
+                ("new Target().instanceMethod()", "Target.instanceMethod()"),
+            ]
+        },
+        ["IncompatibleInvocations$Target"],
+    )
+
+    # The branch of the `if` jumps outside of the method's code
+    patch(
+        "InvalidBranchTarget",
+        lambda c: c.patch_code(("run", "(I)V"), 1, [IFNE, 0, 10], [IFNE, 0, 100]),
+    )
+
+    # The branch of the `if` jumps in the middle of the operand of the `ldc_w`
+    patch(
+        "BranchIntoLdcW",
+        lambda c: c.patch_code(("run", "(I)V"), 1, [IFNE, 0, 9], [IFNE, 0, 10]),
+    )
+
+    # The first value is left on the stack below the returned one
+    def return_with_extra_stack_values(c):
+        for method, store in [(("extraInt", "()I"), ISTORE_0), (("extraObject", "()Ljava/lang/String;"), ASTORE_0)]:
+            c.patch_code(method, 2, [store], [NOP])
+            c.set_max_stack(method, 1, 2)
+
+    patch("ReturnWithExtraStackValues", return_with_extra_stack_values)
+
+    # The StackMapTable is ignored as an unknown attribute, both in a Java 6 and a Java 7 class
+    def no_stack_map_table_java6(c):
+        c.rename_utf8("StackMapTable", "StackMapTablX")
+        c.set_major_version(51, 50)
+
+    patch("NoStackMapTableJava6", no_stack_map_table_java6)
+    patch("NoStackMapTableJava7", lambda c: c.rename_utf8("StackMapTable", "StackMapTablX"))
+
+    # An int is passed where a String is expected
+    def mismatched_argument_type(c):
+        text, _ = c.find_string("text")
+        c.patch_code(("main", "([Ljava/lang/String;)V"), 0, [LDC, text], [BIPUSH, 1])
+
+    patch("MismatchedArgumentType", mismatched_argument_type)
+
+    # The long overwrites the slots 1 and 2
+    patch(
+        "LongStoreOverwritesNextSlot",
+        lambda c: c.patch_code(("run", "()I"), 5, [LSTORE_3], [LSTORE_1]),
+    )
+
+    # Monitors exited without being entered, or in the wrong order
+    def unbalanced_monitors(c):
+        c.patch_code(("exitWithoutEnter", "(Ljava/lang/Object;)V"), 1, [ASTORE_1], [MONITOREXIT])
+        exit_in_wrong_order = ("exitInWrongOrder", "(Ljava/lang/Object;Ljava/lang/Object;)V")
+        c.patch_code(exit_in_wrong_order, 1, [ASTORE_2], [MONITORENTER])
+        c.patch_code(exit_in_wrong_order, 3, [ASTORE_3], [MONITORENTER])
+        c.patch_code(exit_in_wrong_order, 5, [ASTORE, 4], [MONITOREXIT, NOP])
+
+    patch("UnbalancedMonitors", unbalanced_monitors)
+
+    # Values outside the range of boolean and char are stored in fields and arrays
+    def narrow_types_normalization(c):
+        main = ("main", "([Ljava/lang/String;)V")
+        for pc in [9, 20, 36]:
+            c.patch_code(main, pc, [ICONST_1], [ICONST_3])
+        c.patch_code(main, 49, [I2C], [NOP])
+
+    patch("NarrowTypesNormalization", narrow_types_normalization)
+
+    # The fields of ConstantFields are not final, so that javac does not inline them
+    compile_modified(
+        ["ConstantFields", "StaticConstants"],
+        {"ConstantFields": [("static final", "static")]},
+        ["StaticConstants"],
+    )
+
+    # The invokespecial refers to Base.describe rather than Middle.describe
+    patch(
+        "SuperCalls$Patched",
+        lambda c: c.set_methodref_class(
+            ("rjvm/SuperCalls$Middle", "describe", "()Ljava/lang/String;"), "rjvm/SuperCalls$Base"
+        ),
+    )
+
+    # Type confusions in the local variables
+    def local_type_confusion(c):
+        c.patch_code(("nullAsInt", "()I"), 5, [ILOAD_1], [ILOAD_0])
+        c.patch_code(("overwrittenLong", "()J"), 3, [ASTORE_2], [ASTORE_1])
+
+    patch("LocalTypeConfusion", local_type_confusion)
+
+    # Child does not declare a constructor without arguments
+    compile_modified(
+        ["InheritedConstructor"],
+        {
+            "InheritedConstructor": [
+                ("        Child() {\n            super(2);\n        }\n\n", ""),
+                ("new Child()", "new Child(3)"),
+            ]
+        },
+        ["InheritedConstructor$Child"],
+    )
+
+
+if __name__ == "__main__":
+    os.chdir(os.path.dirname(os.path.abspath(__file__)))
+    sys.exit(main())
@@ -0,0 +1,27 @@
+package rjvm;
+
+// Note: the class file of Impl is compiled by patch_classes.py from a modified version of this
+// source, where Base::value is concrete and Impl does not override it. This simulates a class
+// compiled against a different version of its superclass.
+public class AbstractMethodCall {
+    public static void main(String[] args) {
+        Base base = new Impl();
+        try {
+            tempPrint(base.value());
+        } catch (AbstractMethodError e) {
+            tempPrint(-1);
+        }
+    }
+
+    abstract static class Base {
+        abstract int value();
+    }
+
+    static class Impl extends Base {
+        int value() {
+            return 42;
+        }
+    }
+
+    private static native void tempPrint(int value);
+}
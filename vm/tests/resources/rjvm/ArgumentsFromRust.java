@@ -0,0 +1,8 @@
+package rjvm;
+
+public class ArgumentsFromRust {
+    public static double combine(int anInt, long aLong, float aFloat, double aDouble, boolean negate) {
+        double result = anInt + aLong + aFloat + aDouble;
+        return negate ? -result : result;
+    }
+}
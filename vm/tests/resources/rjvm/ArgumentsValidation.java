@@ -0,0 +1,15 @@
+package rjvm;
+
+public class ArgumentsValidation {
+    public static long twice(long value) {
+        return value * 2;
+    }
+
+    public static int sum(int a, int b) {
+        return a + b;
+    }
+
+    public int increment(int value) {
+        return value + 1;
+    }
+}
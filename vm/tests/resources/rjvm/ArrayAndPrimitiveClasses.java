@@ -0,0 +1,21 @@
+package rjvm;
+
+public class ArrayAndPrimitiveClasses {
+    public static void main(String[] args) {
+        tempPrint(int[].class.isArray());
+        tempPrint(String[].class.isArray());
+        tempPrint(Object.class.isArray());
+        tempPrint(int[].class == int[].class);
+
+        tempPrint(int.class.isPrimitive());
+        tempPrint(int.class.isArray());
+        tempPrint(int[].class.isPrimitive());
+        tempPrint(Object.class.isPrimitive());
+
+        tempPrint(new int[1].getClass() == int[].class);
+        tempPrint(new String[1].getClass() == String[].class);
+        tempPrint(new String[1].getClass().isArray());
+    }
+
+    private static native void tempPrint(boolean value);
+}
@@ -0,0 +1,41 @@
+package rjvm;
+
+import java.util.Arrays;
+
+public class ArrayBulkOperations {
+    public static void main(String[] args) {
+        int[] source = new int[100000];
+        for (int i = 0; i < source.length; ++i) {
+            source[i] = i * 3;
+        }
+
+        int[] copy = new int[source.length];
+        System.arraycopy(source, 0, copy, 0, source.length);
+        tempPrint(Arrays.equals(source, copy));
+
+        // Overlapping ranges of the same array
+        System.arraycopy(copy, 0, copy, 1, copy.length - 1);
+        tempPrint(copy[0]);
+        tempPrint(copy[1]);
+        tempPrint(copy[copy.length - 1]);
+
+        Arrays.fill(copy, 7);
+        tempPrint(copy[0] + copy[copy.length - 1]);
+
+        long[] longs = new long[10];
+        Arrays.fill(longs, 1L << 40);
+        tempPrint(longs[9]);
+
+        double[] doubles = new double[10];
+        Arrays.fill(doubles, 0.5);
+        tempPrint(doubles[3]);
+    }
+
+    private static native void tempPrint(boolean value);
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(long value);
+
+    private static native void tempPrint(double value);
+}
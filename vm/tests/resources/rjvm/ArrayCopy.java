@@ -0,0 +1,59 @@
+package rjvm;
+
+public class ArrayCopy {
+    public static void main(String[] args) {
+        // Overlapping regions behave as if the source was copied to a temporary array first
+        int[] forward = new int[] {1, 2, 3, 4, 5};
+        System.arraycopy(forward, 0, forward, 1, 4);
+        tempPrint(digits(forward));
+        int[] backward = new int[] {1, 2, 3, 4, 5};
+        System.arraycopy(backward, 1, backward, 0, 4);
+        tempPrint(digits(backward));
+
+        int[] ints = new int[] {1, 2, 3};
+        tempPrint(copy(null, 0, ints, 0, 1));
+        tempPrint(copy(ints, 0, null, 0, 1));
+        tempPrint(copy(ints, 0, new long[3], 0, 1));
+        tempPrint(copy(ints, 0, new Object[3], 0, 1));
+        tempPrint(copy(new Object(), 0, ints, 0, 1));
+        tempPrint(copy(ints, -1, new int[3], 0, 1));
+        tempPrint(copy(ints, 0, new int[3], -1, 1));
+        tempPrint(copy(ints, 0, new int[3], 0, -1));
+        tempPrint(copy(ints, 2, new int[3], 0, 2));
+        tempPrint(copy(ints, 0, new int[3], 2, 2));
+
+        // Arrays of references are checked element by element
+        String[] strings = new String[] {"x", "x", "x"};
+        tempPrint(copy(new Object[] {"a", new Object(), "c"}, 0, strings, 0, 3));
+        tempPrint(strings[0]);
+        tempPrint(strings[1]);
+        Object[] objects = new Object[2];
+        tempPrint(copy(new String[] {"d", "e"}, 0, objects, 0, 2));
+        tempPrint((String) objects[1]);
+    }
+
+    private static int copy(Object src, int srcPos, Object dest, int destPos, int length) {
+        try {
+            System.arraycopy(src, srcPos, dest, destPos, length);
+            return 0;
+        } catch (NullPointerException e) {
+            return 1;
+        } catch (ArrayStoreException e) {
+            return 2;
+        } catch (IndexOutOfBoundsException e) {
+            return 3;
+        }
+    }
+
+    private static int digits(int[] array) {
+        int result = 0;
+        for (int value : array) {
+            result = result * 10 + value;
+        }
+        return result;
+    }
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(String value);
+}
@@ -0,0 +1,17 @@
+package rjvm;
+
+import java.util.Arrays;
+
+public class ArrayCopyBenchmark {
+    public static void main(String[] args) {
+        int[] source = new int[100000];
+        int[] dest = new int[source.length];
+        for (int i = 0; i < 1000; ++i) {
+            Arrays.fill(source, i);
+            System.arraycopy(source, 0, dest, 0, source.length);
+        }
+        tempPrint(dest[dest.length - 1]);
+    }
+
+    private static native void tempPrint(int value);
+}
@@ -0,0 +1,27 @@
+package rjvm;
+
+import java.io.Serializable;
+
+public class ArrayCovariance {
+    public static void main(String[] args) {
+        Object matrix = new String[2][];
+        tempPrint(matrix instanceof Object);
+        tempPrint(matrix instanceof Object[]);
+        tempPrint(matrix instanceof String[][]);
+        tempPrint(matrix instanceof String[]);
+        tempPrint(matrix instanceof Cloneable);
+        tempPrint(matrix instanceof Serializable);
+        tempPrint(matrix instanceof Runnable);
+
+        Object[] asObjects = (Object[]) matrix;
+        String[][] asMatrix = (String[][]) asObjects;
+        tempPrint(asMatrix.length == 2);
+
+        Object ints = new int[1];
+        tempPrint(ints instanceof int[]);
+        tempPrint(ints instanceof long[]);
+        tempPrint(ints instanceof Object[]);
+    }
+
+    private static native void tempPrint(boolean value);
+}
@@ -0,0 +1,24 @@
+package rjvm;
+
+public class ArrayReturn {
+    public static void main(String[] args) {
+        int[] squares = squares(4);
+        tempPrint(squares.length);
+        tempPrint(squares[3]);
+        tempPrint(grid()[1][0]);
+    }
+
+    public static int[] squares(int n) {
+        int[] result = new int[n];
+        for (int i = 0; i < n; ++i) {
+            result[i] = i * i;
+        }
+        return result;
+    }
+
+    public static int[][] grid() {
+        return new int[][] {{1, 2}, {3, 4}};
+    }
+
+    private static native void tempPrint(int value);
+}
@@ -0,0 +1,50 @@
+package rjvm;
+
+import java.util.Arrays;
+
+public class ArraysFillAndEquals {
+    public static void main(String[] args) {
+        int[] sevens = new int[5];
+        Arrays.fill(sevens, 7);
+        tempPrint(sevens[0] + sevens[4]);
+        tempPrint(Arrays.equals(sevens, new int[] {7, 7, 7, 7, 7}));
+        tempPrint(Arrays.equals(sevens, new int[] {7, 7, 0, 7, 7}));
+        tempPrint(Arrays.equals(sevens, new int[] {7, 7, 7, 7}));
+        int[] nothing = null;
+        tempPrint(Arrays.equals(nothing, null));
+        tempPrint(Arrays.equals(sevens, null));
+
+        Arrays.fill(sevens, 1, 3, 0);
+        tempPrint(Arrays.equals(sevens, new int[] {7, 0, 0, 7, 7}));
+
+        tempPrint(Arrays.equals(new double[] {Double.NaN}, new double[] {Double.NaN}));
+        tempPrint(Arrays.equals(new float[] {0.0f}, new float[] {-0.0f}));
+
+        String[] strings = new String[3];
+        Arrays.fill(strings, "x");
+        tempPrint(strings[2]);
+
+        try {
+            Arrays.fill(sevens, 3, 1, 0);
+        } catch (IllegalArgumentException e) {
+            tempPrint("IllegalArgumentException");
+        }
+        try {
+            Arrays.fill(sevens, 0, 6, 0);
+        } catch (ArrayIndexOutOfBoundsException e) {
+            tempPrint("ArrayIndexOutOfBoundsException");
+        }
+        try {
+            Object[] integers = new Integer[2];
+            Arrays.fill(integers, "not an integer");
+        } catch (ArrayStoreException e) {
+            tempPrint("ArrayStoreException");
+        }
+    }
+
+    private static native void tempPrint(boolean value);
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(String value);
+}
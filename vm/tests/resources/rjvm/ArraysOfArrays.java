@@ -0,0 +1,26 @@
+package rjvm;
+
+public class ArraysOfArrays {
+    public static void main(String[] args) {
+        int[][] matrix = new int[2][];
+        matrix[0] = new int[] {1, 2};
+        matrix[1] = new int[3];
+        matrix[1][2] = 42;
+
+        tempPrint(matrix[0][1]);
+        tempPrint(matrix[1][2]);
+        tempPrint(matrix[1].length);
+
+        int[] row = matrix[0];
+        row[0] = 5;
+        tempPrint(matrix[0][0]);
+
+        // The inner arrays must survive a garbage collection
+        for (int i = 0; i < 10000; ++i) {
+            int[] garbage = new int[100];
+        }
+        tempPrint(matrix[0][0] + matrix[0][1] + matrix[1][2]);
+    }
+
+    private static native void tempPrint(int value);
+}
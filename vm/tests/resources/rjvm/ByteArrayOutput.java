@@ -0,0 +1,46 @@
+package rjvm;
+
+import java.io.ByteArrayOutputStream;
+import java.io.PrintStream;
+
+public class ByteArrayOutput {
+    public static void main(String[] args) {
+        ByteArrayOutputStream stream = new ByteArrayOutputStream();
+        stream.write('h');
+        stream.write(0x169);
+        byte[] bytes = new byte[]{'-', 'w', 'o', 'r', 'l', 'd', '-'};
+        stream.write(bytes, 1, 5);
+        stream.write(-1);
+
+        byte[] content = stream.toByteArray();
+        tempPrint(content.length);
+        tempPrint(stream.size());
+        for (byte b : content) {
+            tempPrint(b);
+        }
+
+        stream.reset();
+        stream.write(bytes, 1, 2);
+        tempPrint(stream.toString());
+
+        try {
+            stream.write(bytes, 5, 3);
+        } catch (IndexOutOfBoundsException e) {
+            tempPrint("out of bounds");
+        }
+
+        ByteArrayOutputStream printed = new ByteArrayOutputStream();
+        PrintStream printStream = new PrintStream(printed);
+        printStream.print("answer: ");
+        printStream.println(42);
+        printStream.print('\u00e8');
+        printStream.println(new char[]{'!'});
+        printStream.println("The quick brown fox jumps over the lazy dog");
+        tempPrint(printed.size());
+        tempPrint(printed.toString());
+    }
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(String value);
+}
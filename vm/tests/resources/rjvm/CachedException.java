@@ -0,0 +1,9 @@
+package rjvm;
+
+public class CachedException {
+    private static final IllegalStateException FAILURE = new IllegalStateException("cached");
+
+    public static void fail() {
+        throw FAILURE;
+    }
+}
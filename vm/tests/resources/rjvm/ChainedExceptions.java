@@ -0,0 +1,35 @@
+package rjvm;
+
+public class ChainedExceptions {
+    public static void main(String[] args) {
+        try {
+            wrap();
+        } catch (RuntimeException e) {
+            tempPrint(e.getCause() instanceof IllegalStateException);
+            tempPrint(e.getCause().getMessage());
+            e.printStackTrace();
+        }
+
+        IllegalArgumentException withoutCause = new IllegalArgumentException("no cause");
+        tempPrint(withoutCause.getCause() == null);
+        Exception cause = new Exception("late cause");
+        tempPrint(withoutCause.initCause(cause) == withoutCause);
+        tempPrint(withoutCause.getCause() == cause);
+    }
+
+    private static void wrap() {
+        try {
+            fail();
+        } catch (IllegalStateException e) {
+            throw new RuntimeException("wrapper", e);
+        }
+    }
+
+    private static void fail() {
+        throw new IllegalStateException("root cause");
+    }
+
+    private static native void tempPrint(boolean value);
+
+    private static native void tempPrint(String value);
+}
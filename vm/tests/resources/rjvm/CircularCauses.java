@@ -0,0 +1,10 @@
+package rjvm;
+
+public class CircularCauses {
+    public static void main(String[] args) {
+        Exception first = new Exception("first");
+        Exception second = new Exception("second", first);
+        first.initCause(second);
+        first.printStackTrace();
+    }
+}
@@ -0,0 +1,32 @@
+package rjvm;
+
+public class ClassLiterals {
+    public static void main(String[] args) {
+        Class<?> first = String.class;
+        Class<?> second = String.class;
+
+        tempPrint(first == second);
+        tempPrint(first == "hello".getClass());
+        tempPrint(first == Object.class);
+        tempPrint(Object.class == new Object().getClass());
+
+        // Class literals must not initialize the class
+        Class<?> lazy = Lazy.class;
+        tempPrint(1);
+        tempPrint(Lazy.value);
+    }
+
+    static class Lazy {
+        static int value = 5;
+
+        static {
+            tempPrint(100);
+        }
+
+        private static native void tempPrint(int value);
+    }
+
+    private static native void tempPrint(boolean value);
+
+    private static native void tempPrint(int value);
+}
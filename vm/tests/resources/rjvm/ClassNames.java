@@ -0,0 +1,21 @@
+package rjvm;
+
+public class ClassNames {
+    public static void main(String[] args) {
+        tempPrint(Object.class.getName());
+        tempPrint(Object.class.getSimpleName());
+        tempPrint(Inner.class.getName());
+        tempPrint(Inner.class.getSimpleName());
+        tempPrint(int[].class.getName());
+        tempPrint(int[].class.getSimpleName());
+        tempPrint(String[][].class.getName());
+        tempPrint(String[][].class.getSimpleName());
+        tempPrint(new int[1][].getClass().getName());
+        tempPrint(new String[1][][].getClass().getName());
+    }
+
+    static class Inner {
+    }
+
+    private static native void tempPrint(Object value);
+}
@@ -0,0 +1,38 @@
+package rjvm;
+
+public class ComparisonsAndShifts {
+    public static void main(String[] args) {
+        float[] floats = {1.5f, 2.5f, Float.NaN};
+        for (float a : floats) {
+            for (float b : floats) {
+                tempPrint(compare(a, b));
+            }
+        }
+        double[] doubles = {1.5, 2.5, Double.NaN};
+        for (double a : doubles) {
+            for (double b : doubles) {
+                tempPrint(compare(a, b));
+            }
+        }
+
+        // The shift distance of longs is masked to six bits, rather than five like for ints
+        long value = 0x123456789L;
+        tempPrint(value << 40);
+        tempPrint(-value >> 40);
+        tempPrint(-value >>> 60);
+        tempPrint(value << 64);
+    }
+
+    // javac compiles < and <= with fcmpg and dcmpg, while > and >= with fcmpl and dcmpl
+    private static int compare(float a, float b) {
+        return (a < b ? 1 : 0) | (a <= b ? 2 : 0) | (a > b ? 4 : 0) | (a >= b ? 8 : 0) | (a == b ? 16 : 0);
+    }
+
+    private static int compare(double a, double b) {
+        return (a < b ? 1 : 0) | (a <= b ? 2 : 0) | (a > b ? 4 : 0) | (a >= b ? 8 : 0) | (a == b ? 16 : 0);
+    }
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(long value);
+}
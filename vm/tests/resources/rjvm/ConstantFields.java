@@ -0,0 +1,9 @@
+package rjvm;
+
+public class ConstantFields {
+    static final int INT = 5;
+    static final long LONG = 1234567890123L;
+    static final float FLOAT = 1.5f;
+    static final double DOUBLE = 2.25;
+    static final String STRING = "constant";
+}
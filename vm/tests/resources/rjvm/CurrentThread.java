@@ -0,0 +1,16 @@
+package rjvm;
+
+public class CurrentThread {
+    public static void main(String[] args) {
+        Thread thread = Thread.currentThread();
+        tempPrint(thread.getName());
+        tempPrint(thread == Thread.currentThread());
+        tempPrint(thread.getPriority());
+    }
+
+    private static native void tempPrint(String value);
+
+    private static native void tempPrint(boolean value);
+
+    private static native void tempPrint(int value);
+}
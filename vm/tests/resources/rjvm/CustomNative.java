@@ -0,0 +1,11 @@
+package rjvm;
+
+public class CustomNative {
+    public static void main(String[] args) {
+        tempPrint(compute(20));
+    }
+
+    private static native int compute(int value);
+
+    private static native void tempPrint(int value);
+}
@@ -0,0 +1,26 @@
+package rjvm;
+
+public class EntryMethod {
+    public static int run() {
+        return 40 + 2;
+    }
+
+    public static String greet() {
+        return "hello from " + "greet";
+    }
+
+    public static void nothing() {
+    }
+
+    public static boolean isReady() {
+        return true;
+    }
+
+    public static char initial() {
+        return 'r';
+    }
+
+    public int notStatic() {
+        return 1;
+    }
+}
@@ -0,0 +1,19 @@
+package rjvm;
+
+public class ExceptionMessages {
+    public static void main(String[] args) {
+        RuntimeException withMessage = new RuntimeException("boom");
+        tempPrint(withMessage.getMessage());
+
+        RuntimeException withoutMessage = new RuntimeException();
+        tempPrint(withoutMessage.getMessage() == null);
+    }
+
+    public static void fail() {
+        throw new IllegalStateException("something went wrong");
+    }
+
+    private static native void tempPrint(Object value);
+
+    private static native void tempPrint(boolean value);
+}
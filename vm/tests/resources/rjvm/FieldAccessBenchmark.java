@@ -0,0 +1,17 @@
+package rjvm;
+
+public class FieldAccessBenchmark {
+    private static int total;
+    private int count;
+
+    public static void main(String[] args) {
+        FieldAccessBenchmark benchmark = new FieldAccessBenchmark();
+        for (int i = 0; i < 1000000; ++i) {
+            benchmark.count += 1;
+            total += benchmark.count & 1;
+        }
+        tempPrint(total);
+    }
+
+    private static native void tempPrint(int value);
+}
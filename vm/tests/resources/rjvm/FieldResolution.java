@@ -0,0 +1,58 @@
+package rjvm;
+
+public class FieldResolution {
+    public static void main(String[] args) {
+        // The same getfield instruction sees objects of different classes
+        Point[] points = new Point[] {new Point(1), new Point3D(2, 3, 4), new Point(5), new Point3D(6, 7, 8)};
+        for (Point point : points) {
+            tempPrint(point.x);
+        }
+
+        // Each class has its own constant pool, so these use the same constant indexes
+        First.increment();
+        Second.increment();
+        First.increment();
+        tempPrint(First.counter);
+        tempPrint(Second.counter);
+        tempPrint(new First().value + new Second().value);
+    }
+
+    private static native void tempPrint(int value);
+
+    static class Point {
+        final int x;
+
+        Point(int x) {
+            this.x = x;
+        }
+    }
+
+    static class Point3D extends Point {
+        final int y;
+        final int z;
+
+        Point3D(int x, int y, int z) {
+            super(x);
+            this.y = y;
+            this.z = z;
+        }
+    }
+
+    static class First {
+        static int counter;
+        int value = 1;
+
+        static void increment() {
+            counter += 1;
+        }
+    }
+
+    static class Second {
+        static int counter;
+        int value = 10;
+
+        static void increment() {
+            counter += 100;
+        }
+    }
+}
@@ -0,0 +1,19 @@
+package rjvm;
+
+public class FloatingPointBits {
+    public static void main(String[] args) {
+        float nonCanonicalFloatNaN = Float.intBitsToFloat(0x7f800001);
+        tempPrint(Float.floatToRawIntBits(nonCanonicalFloatNaN));
+        tempPrint(Float.floatToIntBits(nonCanonicalFloatNaN));
+        tempPrint(Float.floatToIntBits(1.5f));
+
+        double nonCanonicalDoubleNaN = Double.longBitsToDouble(0x7ff0000000000001L);
+        tempPrint(Double.doubleToRawLongBits(nonCanonicalDoubleNaN));
+        tempPrint(Double.doubleToLongBits(nonCanonicalDoubleNaN));
+        tempPrint(Double.doubleToLongBits(1.5));
+    }
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(long value);
+}
@@ -0,0 +1,24 @@
+package rjvm;
+
+public class FloatingPointRemainder {
+    public static void main(String[] args) {
+        printRemainders(5.0, 3.0);
+        printRemainders(-5.0, 3.0);
+        printRemainders(5.0, -3.0);
+        printRemainders(5.5, 0.0);
+        printRemainders(-0.0, 3.0);
+        printRemainders(Double.POSITIVE_INFINITY, 3.0);
+        printRemainders(5.0, Double.NEGATIVE_INFINITY);
+        printRemainders(Double.NaN, 3.0);
+    }
+
+    // Prints the bits, to distinguish -0.0 from 0.0
+    private static void printRemainders(double a, double b) {
+        tempPrint(Double.doubleToLongBits(a % b));
+        tempPrint(Float.floatToIntBits((float) a % (float) b));
+    }
+
+    private static native void tempPrint(long value);
+
+    private static native void tempPrint(int value);
+}
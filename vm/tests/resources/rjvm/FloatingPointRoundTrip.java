@@ -0,0 +1,19 @@
+package rjvm;
+
+public class FloatingPointRoundTrip {
+    public static void main(String[] args) {
+        float f = -2.75f;
+        tempPrint(Float.intBitsToFloat(Float.floatToRawIntBits(f)) == f);
+        tempPrint(Float.intBitsToFloat(Float.floatToRawIntBits(f) ^ 0x80000000));
+
+        double d = 1e-300;
+        tempPrint(Double.longBitsToDouble(Double.doubleToRawLongBits(d)) == d);
+        tempPrint(Double.longBitsToDouble(Double.doubleToRawLongBits(d) + 1));
+    }
+
+    private static native void tempPrint(boolean value);
+
+    private static native void tempPrint(float value);
+
+    private static native void tempPrint(double value);
+}
@@ -0,0 +1,23 @@
+package rjvm;
+
+public class GarbageArraysOfArrays {
+    public static void main(String[] args) {
+        int[][] alive = new int[2][];
+        alive[0] = new int[] {1, 2};
+        alive[1] = new int[] {3};
+
+        // Allocate lots of arrays of arrays that become garbage immediately,
+        // so that the collector has to visit them
+        for (int i = 0; i < 5000; ++i) {
+            int[][] garbage = new int[2][];
+            garbage[0] = new int[100];
+            garbage[1] = new int[100];
+            garbage[1][99] = i;
+        }
+
+        tempPrint(alive[0][0] + alive[0][1] + alive[1][0]);
+        tempPrint(alive[1].length);
+    }
+
+    private static native void tempPrint(int value);
+}
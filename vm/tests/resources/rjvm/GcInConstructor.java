@@ -0,0 +1,11 @@
+package rjvm;
+
+public class GcInConstructor {
+    private final int value;
+
+    public GcInConstructor(int value) {
+        this.value = value;
+        // Moves the instance being constructed to the other semi-space
+        System.gc();
+    }
+}
@@ -0,0 +1,54 @@
+package rjvm;
+
+public class GcWithObjectsOnTheStack {
+    public static void main(String[] args) {
+        long checksum = 0;
+        for (int i = 0; i < 200; ++i) {
+            // While the arguments are evaluated, the uninitialized Pair (pushed by new and dup)
+            // and the first Leaf are only referenced by the operand stack, and allocate()
+            // will trigger garbage collections
+            Pair pair = new Pair(new Leaf(i), allocate(i), new Leaf(2 * i));
+            checksum += pair.sum();
+
+            // Same for the arguments of a static call
+            checksum += sum(new Leaf(i), allocate(i), new Leaf(-i));
+        }
+        tempPrint(checksum);
+    }
+
+    private static int[] allocate(int value) {
+        int[] garbage = new int[10000];
+        garbage[0] = value;
+        return garbage;
+    }
+
+    private static long sum(Leaf first, int[] array, Leaf second) {
+        return first.value + array[0] + second.value;
+    }
+
+    private static native void tempPrint(long value);
+
+    static class Leaf {
+        final int value;
+
+        Leaf(int value) {
+            this.value = value;
+        }
+    }
+
+    static class Pair {
+        final Leaf first;
+        final int[] array;
+        final Leaf second;
+
+        Pair(Leaf first, int[] array, Leaf second) {
+            this.first = first;
+            this.array = array;
+            this.second = second;
+        }
+
+        long sum() {
+            return first.value + array[0] + second.value;
+        }
+    }
+}
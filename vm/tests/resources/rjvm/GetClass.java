@@ -0,0 +1,26 @@
+package rjvm;
+
+public class GetClass {
+    public static void main(String[] args) {
+        Foo first = new Foo();
+        Foo second = new Foo();
+        Class<?> firstClass = first.getClass();
+
+        tempPrint(firstClass == second.getClass());
+        tempPrint(firstClass == new Bar().getClass());
+
+        // The cached class objects must survive a garbage collection
+        for (int i = 0; i < 10000; ++i) {
+            int[] garbage = new int[100];
+        }
+        tempPrint(firstClass == new Foo().getClass());
+    }
+
+    static class Foo {
+    }
+
+    static class Bar {
+    }
+
+    private static native void tempPrint(boolean value);
+}
@@ -0,0 +1,32 @@
+package rjvm;
+
+// Note: the class file of Target is compiled by patch_classes.py from a modified version of
+// this source, where staticMethod is an instance method and instanceMethod is static. This
+// simulates a class compiled against a different version of its dependencies.
+public class IncompatibleInvocations {
+    public static void main(String[] args) {
+        try {
+            tempPrint(Target.staticMethod());
+        } catch (IncompatibleClassChangeError e) {
+            tempPrint(10);
+        }
+
+        try {
+            tempPrint(new Target().instanceMethod());
+        } catch (IncompatibleClassChangeError e) {
+            tempPrint(20);
+        }
+    }
+
+    static class Target {
+        static int staticMethod() {
+            return 1;
+        }
+
+        int instanceMethod() {
+            return 2;
+        }
+    }
+
+    private static native void tempPrint(int value);
+}
@@ -0,0 +1,34 @@
+package rjvm;
+
+// Note: the class file of Child is compiled by patch_classes.py from a modified version of this
+// source, where Child does not declare a constructor without arguments. Constructors are not
+// inherited, so `new Child()` must fail rather than invoke the one of Parent.
+public class InheritedConstructor {
+    public static void main(String[] args) {
+        tempPrint(new Child().value);
+    }
+
+    static class Parent {
+        int value;
+
+        Parent() {
+            value = 1;
+        }
+
+        Parent(int value) {
+            this.value = value;
+        }
+    }
+
+    static class Child extends Parent {
+        Child() {
+            super(2);
+        }
+
+        Child(int value) {
+            super(value);
+        }
+    }
+
+    private static native void tempPrint(int value);
+}
@@ -0,0 +1,22 @@
+package rjvm;
+
+public class InstanceofBenchmark {
+    public static void main(String[] args) {
+        Object[] values = new Object[] {new InstanceofBenchmark(), "string"};
+        int count = 0;
+        for (int i = 0; i < 1000000; ++i) {
+            Object value = values[i & 1];
+            if (value instanceof InstanceofBenchmark) {
+                InstanceofBenchmark benchmark = (InstanceofBenchmark) value;
+                count += benchmark.one();
+            }
+        }
+        tempPrint(count);
+    }
+
+    private int one() {
+        return 1;
+    }
+
+    private static native void tempPrint(int value);
+}
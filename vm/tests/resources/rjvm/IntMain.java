@@ -0,0 +1,7 @@
+package rjvm;
+
+public class IntMain {
+    public static int main(String[] args) {
+        return 40 + args.length;
+    }
+}
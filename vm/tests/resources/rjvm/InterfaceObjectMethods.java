@@ -0,0 +1,53 @@
+package rjvm;
+
+public class InterfaceObjectMethods {
+    public static void main(String[] args) {
+        Shape custom = new Square(3);
+        // Since Shape redeclares them, toString and hashCode are compiled to invokeinterface,
+        // even though the implementation might come from Object. equals is not redeclared,
+        // so it is compiled as an invokevirtual of Object.equals
+        tempPrint(custom.toString());
+        tempPrint(custom.hashCode());
+        tempPrint(custom.equals(custom));
+        tempPrint(custom.equals(new Square(4)));
+
+        Shape inheriting = new Circle();
+        tempPrint(inheriting.equals(inheriting));
+        tempPrint(inheriting.equals(custom));
+        tempPrint(inheriting.toString().startsWith("rjvm.InterfaceObjectMethods$Circle@"));
+        tempPrint(inheriting.hashCode() == System.identityHashCode(inheriting));
+    }
+
+    interface Shape {
+        String toString();
+
+        int hashCode();
+    }
+
+    static class Square implements Shape {
+        private final int side;
+
+        Square(int side) {
+            this.side = side;
+        }
+
+        @Override
+        public String toString() {
+            return "Square(" + side + ")";
+        }
+
+        @Override
+        public int hashCode() {
+            return side * 31;
+        }
+    }
+
+    static class Circle implements Shape {
+    }
+
+    private static native void tempPrint(String value);
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(boolean value);
+}
@@ -0,0 +1,15 @@
+package rjvm;
+
+// The compiled class file is patched by patch_classes.py, so that the branch of the `if`
+// in run jumps outside of the method's code
+public class InvalidBranchTarget {
+    public static void run(int value) {
+        if (value == 0) {
+            tempPrint(1);
+        } else {
+            tempPrint(2);
+        }
+    }
+
+    private static native void tempPrint(int value);
+}
@@ -0,0 +1,12 @@
+package rjvm;
+
+public class LargeAllocation {
+    public static void main(String[] args) {
+        // Roughly 2.4 MB, i.e. a bit more than half of the 4 MB used in the tests
+        long[] array = new long[300000];
+        array[array.length - 1] = 42;
+        tempPrint(array[array.length - 1]);
+    }
+
+    private static native void tempPrint(long value);
+}
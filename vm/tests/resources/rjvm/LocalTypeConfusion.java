@@ -0,0 +1,20 @@
+package rjvm;
+
+// The compiled class file is patched by patch_classes.py to create type confusions in the
+// local variables, which javac would never generate:
+// - in nullAsInt, the iload reads the local 0, which contains null, rather than the local 1;
+// - in overwrittenLong, the astore writes null in the local 1, which is the second half
+//   of the long in the local 0, rather than in the local 2.
+public class LocalTypeConfusion {
+    public static int nullAsInt() {
+        Object object = null;
+        int value = 42;
+        return value;
+    }
+
+    public static long overwrittenLong() {
+        long value = 1L;
+        Object object = null;
+        return value;
+    }
+}
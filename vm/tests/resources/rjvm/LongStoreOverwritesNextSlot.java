@@ -0,0 +1,13 @@
+package rjvm;
+
+// Note: the class file is patched by patch_classes.py, replacing the `lstore_3` in run with
+// `lstore_1`. The long then overwrites the slots 1 and 2, so that reading `second`
+// from slot 2 should fail rather than see its stale value.
+public class LongStoreOverwritesNextSlot {
+    public static int run() {
+        long first = 0;
+        int second = 5;
+        long third = 1;
+        return second;
+    }
+}
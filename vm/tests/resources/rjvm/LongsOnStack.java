@@ -0,0 +1,29 @@
+package rjvm;
+
+public class LongsOnStack {
+    private long counter = 10;
+
+    public static void main(String[] args) {
+        long a, b;
+        a = b = 5L;
+        tempPrint(a + b);
+
+        LongsOnStack object = new LongsOnStack();
+        long previousCounter = object.counter++;
+        tempPrint(previousCounter);
+        tempPrint(object.counter);
+
+        long[] array = {20L};
+        long previousElement = array[0]++;
+        tempPrint(previousElement);
+        tempPrint(array[0]);
+
+        tempPrint(sum(1, 2, 3));
+    }
+
+    private static long sum(long a, long b, long c) {
+        return a + (b + (c + (a + b)));
+    }
+
+    private static native void tempPrint(long value);
+}
@@ -0,0 +1,15 @@
+package rjvm;
+
+public class LoopBenchmark {
+    public static void main(String[] args) {
+        int total = 0;
+        for (int i = 0; i < 1000; ++i) {
+            for (int j = 0; j < 1000; ++j) {
+                total += (i ^ j) & 1;
+            }
+        }
+        tempPrint(total);
+    }
+
+    private static native void tempPrint(int value);
+}
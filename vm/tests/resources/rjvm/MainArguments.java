@@ -0,0 +1,16 @@
+package rjvm;
+
+public class MainArguments {
+    public static void main(String[] args) {
+        int valid = 0;
+        for (int i = 0; i < args.length; ++i) {
+            if (Integer.parseInt(args[i]) == i) {
+                ++valid;
+            }
+        }
+        tempPrint(args.length);
+        tempPrint(valid);
+    }
+
+    private static native void tempPrint(int value);
+}
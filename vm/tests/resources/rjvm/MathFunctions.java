@@ -0,0 +1,22 @@
+package rjvm;
+
+public class MathFunctions {
+    public static void main(String[] args) {
+        tempPrint(Math.sqrt(2.0));
+        tempPrint(Math.sin(Math.PI / 2));
+        tempPrint(Math.cos(0));
+        tempPrint(Math.tan(Math.PI / 4));
+        tempPrint(Math.pow(2, 10));
+        tempPrint(Math.log(Math.E));
+        tempPrint(Math.abs(-3.5));
+        tempPrint(Math.floor(2.7));
+        tempPrint(Math.ceil(2.1));
+        tempPrint(Math.min(-1.0, 2.0));
+        // Comparisons with NaN are always false
+        double nan = Double.NaN;
+        tempPrint(nan < 1.0 ? 1.0 : 0.0);
+        tempPrint(nan > 1.0 ? 1.0 : 0.0);
+    }
+
+    private static native void tempPrint(double value);
+}
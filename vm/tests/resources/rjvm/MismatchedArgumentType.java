@@ -0,0 +1,13 @@
+package rjvm;
+
+// Note: the class file is patched by patch_classes.py, replacing the `ldc "text"` in main
+// with `bipush 1`. This simulates a malformed caller that passes an int where a String
+// is expected.
+public class MismatchedArgumentType {
+    public static void main(String[] args) {
+        takesString("text");
+    }
+
+    private static void takesString(String value) {
+    }
+}
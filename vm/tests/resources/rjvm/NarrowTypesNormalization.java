@@ -0,0 +1,31 @@
+package rjvm;
+
+// Note: the class file is patched by patch_classes.py. Each `iconst_1` stored in a boolean is
+// replaced by `iconst_3`, and the `i2c` in main by `nop`, to simulate bytecode that stores
+// values outside the range of the declared type.
+public class NarrowTypesNormalization {
+    private boolean flag;
+    private static boolean staticFlag;
+    private char character;
+
+    public static void main(String[] args) {
+        NarrowTypesNormalization object = new NarrowTypesNormalization();
+        object.flag = true;
+        tempPrint(object.flag);
+
+        staticFlag = true;
+        tempPrint(staticFlag);
+
+        boolean[] flags = new boolean[1];
+        flags[0] = true;
+        tempPrint(flags[0]);
+
+        int value = 65 + 65536;
+        object.character = (char) value;
+        tempPrint(object.character);
+    }
+
+    private static native void tempPrint(boolean value);
+
+    private static native void tempPrint(int value);
+}
@@ -0,0 +1,30 @@
+package rjvm;
+
+public class NativeExceptionCaught {
+    public static void main(String[] args) {
+        int[] src = new int[] {1, 2, 3};
+        int[] dest = new int[2];
+        try {
+            System.arraycopy(src, 0, dest, 0, 3);
+            tempPrint(0);
+        } catch (ArrayIndexOutOfBoundsException e) {
+            tempPrint(1);
+        }
+
+        try {
+            System.arraycopy(null, 0, dest, 0, 1);
+            tempPrint(0);
+        } catch (NullPointerException e) {
+            tempPrint(2);
+        } finally {
+            tempPrint(3);
+        }
+
+        // The failed copies must not have modified the destination
+        tempPrint(dest[0]);
+        System.arraycopy(src, 1, dest, 0, 2);
+        tempPrint(dest[0] + dest[1]);
+    }
+
+    private static native void tempPrint(int value);
+}
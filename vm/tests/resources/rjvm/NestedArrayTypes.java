@@ -0,0 +1,51 @@
+package rjvm;
+
+import java.util.Arrays;
+
+public class NestedArrayTypes {
+    public static void main(String[] args) {
+        Object strings = new String[2][];
+        tempPrint(strings instanceof String[][]);
+        tempPrint(strings instanceof Object[][]);
+        tempPrint(strings instanceof Integer[][]);
+        tempPrint(strings instanceof int[][]);
+
+        Object ints = new int[2][];
+        tempPrint(ints instanceof int[][]);
+        tempPrint(ints instanceof long[][]);
+        tempPrint(ints instanceof Object[][]);
+        tempPrint(ints instanceof Object[]);
+
+        Object cube = new int[1][][];
+        tempPrint(cube instanceof int[][][]);
+        tempPrint(cube instanceof Object[][]);
+        tempPrint(cube instanceof int[][]);
+
+        String[][] source = new String[][] {new String[] {"a"}};
+        Integer[][] destination = new Integer[1][];
+        try {
+            System.arraycopy(source, 0, destination, 0, 1);
+            tempPrint(false);
+        } catch (ArrayStoreException e) {
+            tempPrint(destination[0] == null);
+        }
+
+        Object[][] objects = new Object[1][];
+        System.arraycopy(source, 0, objects, 0, 1);
+        tempPrint(objects[0] == source[0]);
+
+        try {
+            Arrays.fill((Object[]) destination, new String[0]);
+            tempPrint(false);
+        } catch (ArrayStoreException e) {
+            tempPrint(destination[0] == null);
+        }
+    }
+
+    public static Integer[][] castToIntegers() {
+        Object strings = new String[2][];
+        return (Integer[][]) strings;
+    }
+
+    private static native void tempPrint(boolean value);
+}
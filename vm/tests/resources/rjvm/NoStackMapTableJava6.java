@@ -0,0 +1,15 @@
+package rjvm;
+
+// The compiled class file is patched by patch_classes.py: the StackMapTable attribute of the
+// methods is renamed to StackMapTablX, so that it is ignored as an unknown attribute,
+// and its version is changed to 50 (Java 6), so that the verifier does not require
+// the table.
+public class NoStackMapTableJava6 {
+    public static int sum(int count) {
+        int sum = 0;
+        for (int i = 1; i <= count; ++i) {
+            sum += i;
+        }
+        return sum;
+    }
+}
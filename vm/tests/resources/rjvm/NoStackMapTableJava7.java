@@ -0,0 +1,14 @@
+package rjvm;
+
+// The compiled class file is patched by patch_classes.py: the StackMapTable attribute of the
+// methods is renamed to StackMapTablX, so that it is ignored as an unknown attribute,
+// so that, being a Java 7 class, it is rejected by the verifier.
+public class NoStackMapTableJava7 {
+    public static int sum(int count) {
+        int sum = 0;
+        for (int i = 1; i <= count; ++i) {
+            sum += i;
+        }
+        return sum;
+    }
+}
@@ -0,0 +1,58 @@
+package rjvm;
+
+public class ObjectClone {
+    static class Point implements Cloneable {
+        int x;
+        long y;
+        String label;
+
+        Point(int x, long y, String label) {
+            this.x = x;
+            this.y = y;
+            this.label = label;
+        }
+
+        @Override
+        public Point clone() {
+            try {
+                return (Point) super.clone();
+            } catch (CloneNotSupportedException e) {
+                throw new AssertionError(e);
+            }
+        }
+    }
+
+    static class NotCloneable {
+        int value;
+
+        NotCloneable tryClone() throws CloneNotSupportedException {
+            return (NotCloneable) super.clone();
+        }
+    }
+
+    public static void main(String[] args) {
+        Point original = new Point(3, 4L, "p");
+        Point clone = original.clone();
+        tempPrint(clone != original);
+        tempPrint(clone.getClass() == Point.class);
+        tempPrint(clone.x);
+        tempPrint(clone.y);
+        tempPrint(clone.label == original.label);
+
+        clone.x = 10;
+        tempPrint(original.x);
+
+        try {
+            new NotCloneable().tryClone();
+            tempPrint(false);
+        } catch (CloneNotSupportedException e) {
+            tempPrint(true);
+        }
+    }
+
+    private static native void tempPrint(boolean value);
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(long value);
+}
@@ -0,0 +1,22 @@
+package rjvm;
+
+public class ObjectFields {
+    static class Base {
+        int id = 7;
+        static String staticField = "not included";
+    }
+
+    static class Derived extends Base {
+        long big = 1234567890123L;
+        double ratio = 0.5;
+        boolean flag = true;
+        String name = "derived";
+        Base other = null;
+    }
+
+    public static Derived create() {
+        Derived derived = new Derived();
+        derived.other = new Base();
+        return derived;
+    }
+}
@@ -0,0 +1,30 @@
+package rjvm;
+
+import java.util.Objects;
+
+public class ObjectsUtilities {
+    public static void main(String[] args) {
+        Object object = new Object();
+        tempPrint(Objects.requireNonNull(object) == object);
+        try {
+            Objects.requireNonNull(null);
+            tempPrint(false);
+        } catch (NullPointerException e) {
+            tempPrint(true);
+        }
+        try {
+            Objects.requireNonNull(null, "must not be null");
+            tempPrint(false);
+        } catch (NullPointerException e) {
+            tempPrint(e.getMessage().equals("must not be null"));
+        }
+
+        tempPrint(object.hashCode() == object.hashCode());
+        tempPrint(object.hashCode() == System.identityHashCode(object));
+        tempPrint(object.equals(object));
+        tempPrint(object.equals(new Object()));
+        tempPrint(object.equals(null));
+    }
+
+    private static native void tempPrint(boolean value);
+}
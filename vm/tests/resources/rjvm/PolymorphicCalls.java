@@ -0,0 +1,77 @@
+package rjvm;
+
+public class PolymorphicCalls {
+    public static void main(String[] args) {
+        Animal[] animals = new Animal[] {new Dog(), new Dog(), new Bird(), new Puppy(), new Bird()};
+        // The same call sites see receivers of different classes
+        for (Animal animal : animals) {
+            tempPrint(animal.legs());
+        }
+        for (Animal animal : animals) {
+            tempPrint(animal.describe());
+        }
+
+        Shape[] shapes = new Shape[] {new Square(2), new Rectangle(2, 3), new Square(3)};
+        for (Shape shape : shapes) {
+            tempPrint(shape.area());
+        }
+    }
+
+    private static native void tempPrint(int value);
+
+    abstract static class Animal {
+        abstract int legs();
+
+        int describe() {
+            return legs() * 10;
+        }
+    }
+
+    static class Dog extends Animal {
+        int legs() {
+            return 4;
+        }
+    }
+
+    static class Puppy extends Dog {
+        int describe() {
+            return 1 + super.describe();
+        }
+    }
+
+    static class Bird extends Animal {
+        int legs() {
+            return 2;
+        }
+    }
+
+    interface Shape {
+        int area();
+    }
+
+    static class Square implements Shape {
+        private final int side;
+
+        Square(int side) {
+            this.side = side;
+        }
+
+        public int area() {
+            return side * side;
+        }
+    }
+
+    static class Rectangle implements Shape {
+        private final int width;
+        private final int height;
+
+        Rectangle(int width, int height) {
+            this.width = width;
+            this.height = height;
+        }
+
+        public int area() {
+            return width * height;
+        }
+    }
+}
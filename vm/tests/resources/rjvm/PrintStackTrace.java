@@ -0,0 +1,18 @@
+package rjvm;
+
+public class PrintStackTrace {
+    public static void main(String[] args) {
+        try {
+            fail();
+        } catch (IllegalStateException e) {
+            e.printStackTrace();
+        }
+        tempPrint("done");
+    }
+
+    private static void fail() {
+        throw new IllegalStateException("something went wrong");
+    }
+
+    private static native void tempPrint(String value);
+}
@@ -0,0 +1,14 @@
+package rjvm;
+
+public class PrintingPhases {
+    public static void first() {
+        tempPrint(1);
+        tempPrint(2);
+    }
+
+    public static void second() {
+        tempPrint(3);
+    }
+
+    private static native void tempPrint(int value);
+}
@@ -0,0 +1,48 @@
+package rjvm;
+
+import java.lang.ref.SoftReference;
+import java.lang.ref.WeakReference;
+
+public class References {
+    public static void main(String[] args) throws Throwable {
+        Finalizable finalizable = new Finalizable(42);
+        // We never run finalizers, but invoking them explicitly must work
+        finalizable.finalize();
+        tempPrint(finalizable.value);
+        new Object() {
+            void callObjectFinalize() throws Throwable {
+                finalize();
+            }
+        }.callObjectFinalize();
+
+        // References are never cleared, since the referents are kept alive
+        WeakReference<Finalizable> weak = new WeakReference<Finalizable>(finalizable);
+        tempPrint(weak.get() == finalizable);
+        SoftReference<String> soft = new SoftReference<String>("soft");
+        tempPrint(soft.get());
+        tempPrint(weak.isEnqueued());
+
+        weak.clear();
+        tempPrint(weak.get() == null);
+    }
+
+    static class Finalizable {
+        int value;
+
+        Finalizable(int value) {
+            this.value = value;
+        }
+
+        @Override
+        protected void finalize() throws Throwable {
+            super.finalize();
+            value = -value;
+        }
+    }
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(boolean value);
+
+    private static native void tempPrint(String value);
+}
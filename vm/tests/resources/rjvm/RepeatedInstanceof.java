@@ -0,0 +1,16 @@
+package rjvm;
+
+public class RepeatedInstanceof {
+    public static void main(String[] args) {
+        Object[] values = new Object[] {new RepeatedInstanceof(), "string"};
+        int count = 0;
+        for (int i = 0; i < 100; ++i) {
+            if (values[i & 1] instanceof RepeatedInstanceof) {
+                ++count;
+            }
+        }
+        tempPrint(count);
+    }
+
+    private static native void tempPrint(int value);
+}
@@ -0,0 +1,29 @@
+package rjvm;
+
+// The compiled class file is patched by patch_classes.py: in the methods `extraInt` and
+// `extraObject` the first store is replaced by a nop, so that the first value
+// remains on the stack below the returned one, and max_stack is increased to 2
+public class ReturnWithExtraStackValues {
+    public static void main(String[] args) {
+        int value = extraInt();
+        tempPrint(value);
+        String string = extraObject();
+        tempPrint(string);
+    }
+
+    private static int extraInt() {
+        int below = 7;
+        int top = 42;
+        return top;
+    }
+
+    private static String extraObject() {
+        Object below = "below";
+        String top = "top";
+        return top;
+    }
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(String value);
+}
@@ -0,0 +1,15 @@
+package rjvm;
+
+public class SignedBytes {
+    public static void main(String[] args) {
+        // All these constants are loaded with bipush
+        tempPrint(-2);
+        tempPrint(-128);
+        tempPrint(127);
+
+        byte b = -100;
+        tempPrint(b);
+    }
+
+    private static native void tempPrint(int value);
+}
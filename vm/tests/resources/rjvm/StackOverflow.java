@@ -0,0 +1,28 @@
+package rjvm;
+
+public class StackOverflow {
+    public static void main(String[] args) {
+        tempPrint(sum(500));
+        try {
+            tempPrint(ping(0));
+        } catch (StackOverflowError e) {
+            tempPrint(e.getMessage());
+        }
+    }
+
+    private static int sum(int n) {
+        return n == 0 ? 0 : n + sum(n - 1);
+    }
+
+    private static int ping(int n) {
+        return pong(n + 1);
+    }
+
+    private static int pong(int n) {
+        return ping(n + 1);
+    }
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(String value);
+}
@@ -0,0 +1,18 @@
+package rjvm;
+
+public class StaticConfiguration {
+    private static int limit = 10;
+    private static String name = "default";
+    // Computed by the static initializer, before any injected value is applied
+    private static final int DOUBLE_LIMIT = limit * 2;
+
+    public static void main(String[] args) {
+        tempPrint(limit);
+        tempPrint(name);
+        tempPrint(DOUBLE_LIMIT);
+    }
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(String value);
+}
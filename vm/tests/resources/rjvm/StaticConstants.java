@@ -0,0 +1,24 @@
+// Note: this class is compiled by patch_classes.py against a version of ConstantFields where
+// the fields are not final, so that javac emits a getstatic rather than inlining them. The
+// fields of ConstantFields have a ConstantValue attribute and are never assigned in <clinit>.
+package rjvm;
+
+public class StaticConstants {
+    public static void main(String[] args) {
+        tempPrint(ConstantFields.INT);
+        tempPrint(ConstantFields.LONG);
+        tempPrint(ConstantFields.FLOAT);
+        tempPrint(ConstantFields.DOUBLE);
+        tempPrint(ConstantFields.STRING == "constant");
+    }
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(long value);
+
+    private static native void tempPrint(float value);
+
+    private static native void tempPrint(double value);
+
+    private static native void tempPrint(boolean value);
+}
@@ -0,0 +1,52 @@
+package rjvm;
+
+public class StaticFields {
+    private static int anInt;
+    private static long aLong;
+    private static float aFloat;
+    private static double aDouble;
+    private static boolean aBoolean;
+    private static char aChar;
+    private static String aString;
+    private static int[] anArray;
+    private static Object anObject;
+    private static String aNullString;
+    private static Object neverAssigned;
+
+    public static void main(String[] args) {
+        anInt = 42;
+        aLong = 1L << 40;
+        aFloat = 1.5f;
+        aDouble = 2.25;
+        aBoolean = true;
+        aChar = 'x';
+        aString = "hello";
+        anArray = new int[] {1, 2, 3};
+        // Arrays can be stored in fields of type Object
+        anObject = new int[] {4};
+        aNullString = "temporary";
+        aNullString = null;
+
+        tempPrint(anInt);
+        tempPrint(aLong);
+        tempPrint(aFloat);
+        tempPrint(aDouble);
+        tempPrint(aBoolean ? 1 : 0);
+        tempPrint(aChar);
+        tempPrint(aString);
+        tempPrint(anArray[2]);
+        tempPrint(((int[]) anObject)[0]);
+        tempPrint(aNullString == null ? 1 : 0);
+        tempPrint(neverAssigned == null ? 1 : 0);
+    }
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(long value);
+
+    private static native void tempPrint(float value);
+
+    private static native void tempPrint(double value);
+
+    private static native void tempPrint(String value);
+}
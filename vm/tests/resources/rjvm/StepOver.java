@@ -0,0 +1,13 @@
+package rjvm;
+
+public class StepOver {
+    public static int run() {
+        int a = 1;
+        int b = square(a + 2);
+        return a + b;
+    }
+
+    private static int square(int x) {
+        return x * x;
+    }
+}
@@ -0,0 +1,19 @@
+package rjvm;
+
+public class StringConstruction {
+    public static void main(String[] args) {
+        char[] chars = new char[] {'h', 'e', 'l', 'l', 'o'};
+        String fromChars = new String(chars);
+        // The string must have copied the array
+        chars[0] = 'j';
+        tempPrint(fromChars);
+        tempPrint(new String(chars, 1, 3));
+        tempPrint(new String("literal"));
+        tempPrint(fromChars.length());
+        tempPrint(fromChars.equals("hello") ? 1 : 0);
+    }
+
+    private static native void tempPrint(String value);
+
+    private static native void tempPrint(int value);
+}
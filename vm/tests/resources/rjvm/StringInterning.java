@@ -0,0 +1,35 @@
+package rjvm;
+
+public class StringInterning {
+    public static void main(String[] args) {
+        String first = "hello";
+        String second = "hello";
+        tempPrint(first == second);
+        tempPrint(first == Other.hello());
+
+        String built = new StringBuilder("hel").append("lo").toString();
+        tempPrint(built == first);
+        tempPrint(built.intern() == first);
+
+        String unique = new StringBuilder("not a ").append("literal").toString();
+        tempPrint(unique.intern() == unique);
+
+        // Strings with lone surrogates are not valid utf-16, but they are valid Java strings
+        String lone = loneSurrogate('\uD800');
+        tempPrint(lone.intern() == lone);
+        tempPrint(loneSurrogate('\uDC00').intern() == lone);
+        tempPrint(loneSurrogate('\uD800').intern() == lone);
+    }
+
+    public static String loneSurrogate(char surrogate) {
+        return new StringBuilder().append(surrogate).toString();
+    }
+
+    static class Other {
+        static String hello() {
+            return "hello";
+        }
+    }
+
+    private static native void tempPrint(boolean value);
+}
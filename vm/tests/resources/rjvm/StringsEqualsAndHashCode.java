@@ -0,0 +1,21 @@
+package rjvm;
+
+public class StringsEqualsAndHashCode {
+    public static void main(String[] args) {
+        String literal = "hello";
+        String built = new StringBuilder("hel").append("lo").toString();
+
+        tempPrint(literal == built);
+        tempPrint(literal.equals(built));
+        tempPrint(literal.hashCode() == built.hashCode());
+        tempPrint(literal.hashCode());
+
+        tempPrint(literal.equals("world"));
+        tempPrint(literal.equals(null));
+        tempPrint(literal.equals(new Object()));
+    }
+
+    private static native void tempPrint(boolean value);
+
+    private static native void tempPrint(int value);
+}
@@ -0,0 +1,40 @@
+// Note: after compilation, patch_classes.py changes the method reference used by the
+// invokespecial in Patched.describe to point to Base.describe rather than Middle.describe,
+// like older compilers would emit. The class literal in Patched exists only to add
+// a class constant for Base to its constant pool.
+package rjvm;
+
+public class SuperCalls {
+    public static void main(String[] args) {
+        tempPrint(new Derived().describe());
+        tempPrint(new Patched().describe());
+    }
+
+    static class Base {
+        String describe() {
+            return "base";
+        }
+    }
+
+    static class Middle extends Base {
+        String describe() {
+            return "middle, " + super.describe();
+        }
+    }
+
+    static class Derived extends Middle {
+        String describe() {
+            return "derived, " + super.describe();
+        }
+    }
+
+    static class Patched extends Middle {
+        static final Class<?> BASE_CLASS = Base.class;
+
+        String describe() {
+            return "patched, " + super.describe();
+        }
+    }
+
+    private static native void tempPrint(String value);
+}
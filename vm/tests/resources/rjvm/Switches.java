@@ -0,0 +1,57 @@
+package rjvm;
+
+public class Switches {
+    public static void main(String[] args) {
+        for (int i = -1; i <= 4; ++i) {
+            tempPrint(dense(i));
+        }
+        tempPrint(sparse(-1000));
+        tempPrint(sparse(7));
+        tempPrint(sparse(1000000));
+        tempPrint(sparse(8));
+        tempPrint(onChar('b'));
+        tempPrint(onChar('z'));
+    }
+
+    // Compiled to a tableswitch
+    private static int dense(int value) {
+        switch (value) {
+            case 0:
+                return 10;
+            case 1:
+                return 11;
+            case 2:
+            case 3:
+                return 23;
+            default:
+                return -1;
+        }
+    }
+
+    // Compiled to a lookupswitch
+    private static int sparse(int value) {
+        switch (value) {
+            case -1000:
+                return 1;
+            case 7:
+                return 2;
+            case 1000000:
+                return 3;
+            default:
+                return 0;
+        }
+    }
+
+    private static int onChar(char value) {
+        switch (value) {
+            case 'a':
+                return 1;
+            case 'b':
+                return 2;
+            default:
+                return 0;
+        }
+    }
+
+    private static native void tempPrint(int value);
+}
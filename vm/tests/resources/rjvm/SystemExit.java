@@ -0,0 +1,15 @@
+package rjvm;
+
+public class SystemExit {
+    public static void main(String[] args) {
+        try {
+            tempPrint(1);
+            System.exit(7);
+            tempPrint(2);
+        } finally {
+            tempPrint(3);
+        }
+    }
+
+    private static native void tempPrint(int value);
+}
@@ -0,0 +1,7 @@
+package rjvm;
+
+public class ThrowsRuntimeException {
+    public static void main(String[] args) {
+        throw new RuntimeException("boom");
+    }
+}
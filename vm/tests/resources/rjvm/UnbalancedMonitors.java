@@ -0,0 +1,42 @@
+package rjvm;
+
+// Note: the class file is patched by patch_classes.py. In exitWithoutEnter the `astore_1` is
+// replaced by `monitorexit`. In exitInWrongOrder the `astore_2` and `astore_3` are replaced
+// by `monitorenter`, and the `astore 4` by `monitorexit` followed by `nop`.
+public class UnbalancedMonitors {
+    public static void main(String[] args) {
+        Object first = new Object();
+        Object second = new Object();
+        synchronized (first) {
+            synchronized (second) {
+                tempPrint(1);
+            }
+        }
+
+        try {
+            exitWithoutEnter(first);
+            tempPrint(-1);
+        } catch (IllegalMonitorStateException e) {
+            tempPrint(2);
+        }
+
+        try {
+            exitInWrongOrder(first, second);
+            tempPrint(-1);
+        } catch (IllegalMonitorStateException e) {
+            tempPrint(3);
+        }
+    }
+
+    private static void exitWithoutEnter(Object lock) {
+        Object unused = lock;
+    }
+
+    private static void exitInWrongOrder(Object first, Object second) {
+        Object a = first;
+        Object b = second;
+        Object c = first;
+    }
+
+    private static native void tempPrint(int value);
+}
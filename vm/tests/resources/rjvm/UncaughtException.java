@@ -0,0 +1,12 @@
+package rjvm;
+
+public class UncaughtException {
+    public static void main(String[] args) {
+        fail();
+    }
+
+    private static void fail() {
+        // The stack trace printed by the CLI should point here
+        throw new IllegalStateException("something failed");
+    }
+}
@@ -0,0 +1,29 @@
+package rjvm;
+
+public class VirtualCallsBenchmark {
+    public static void main(String[] args) {
+        Counter counter = new IncrementingCounter();
+        for (int i = 0; i < 1000000; ++i) {
+            counter.increment();
+        }
+        tempPrint(counter.value());
+    }
+
+    private static native void tempPrint(int value);
+
+    abstract static class Counter {
+        protected int value;
+
+        abstract void increment();
+
+        int value() {
+            return value;
+        }
+    }
+
+    static class IncrementingCounter extends Counter {
+        void increment() {
+            ++value;
+        }
+    }
+}
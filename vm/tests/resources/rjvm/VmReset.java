@@ -0,0 +1,37 @@
+package rjvm;
+
+public class VmReset {
+    private static int counter = initialCounter();
+    private static StringBuilder log = new StringBuilder("log:");
+
+    public static void main(String[] args) {
+        counter++;
+        log.append(counter);
+        tempPrint(counter);
+        tempPrint(log.toString());
+        tempPrint(Base.initializations);
+    }
+
+    public static void runLate() {
+        tempPrint(Late.value());
+    }
+
+    private static int initialCounter() {
+        Base.initializations++;
+        return 10;
+    }
+
+    static class Base {
+        static int initializations = 0;
+    }
+
+    static class Late extends Base {
+        static int value() {
+            return initializations + 100;
+        }
+    }
+
+    private static native void tempPrint(int value);
+
+    private static native void tempPrint(String value);
+}
@@ -0,0 +1,33 @@
+package rjvm;
+
+public class WrapperParsing {
+    public static void main(String[] args) {
+        tempPrint(Short.parseShort("-1234"));
+        tempPrint(Byte.parseByte("7f", 16));
+        tempPrint(Short.valueOf((short) 300).byteValue());
+        tempPrint(Integer.valueOf(70000).shortValue());
+        tempPrint(Character.getNumericValue('7'));
+        tempPrint(Character.getNumericValue('z'));
+        tempPrint(Character.getNumericValue('%'));
+        tempPrint(parseByteOrMinusOne("128"));
+        tempPrint(parseShortOrMinusOne("12a"));
+    }
+
+    private static int parseByteOrMinusOne(String s) {
+        try {
+            return Byte.parseByte(s);
+        } catch (NumberFormatException e) {
+            return -1;
+        }
+    }
+
+    private static int parseShortOrMinusOne(String s) {
+        try {
+            return Short.parseShort(s);
+        } catch (NumberFormatException e) {
+            return -1;
+        }
+    }
+
+    private static native void tempPrint(int value);
+}
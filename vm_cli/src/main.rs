@@ -1,23 +1,30 @@
+use std::io::Write;
+
 use clap::Parser;
 
 use rjvm_vm::{
-    array::Array,
-    array_entry_type::ArrayEntryType,
     call_stack::CallStack,
-    class_and_method::ClassAndMethod,
-    exceptions::MethodCallFailed,
-    java_objects_creation::new_java_lang_string_object,
+    exceptions::{JavaException, MethodCallFailed},
     value::Value,
-    vm::{Vm, DEFAULT_MAX_MEMORY_MB_STR, ONE_MEGABYTE},
+    vm::{
+        native_stack_size_for_call_stack_depth, AllocationStrategy, MemorySizing, Vm,
+        DEFAULT_MAX_CALL_STACK_DEPTH, DEFAULT_MAX_MEMORY_MB_STR, ONE_MEGABYTE, RUNTIME_JAR_ENV_VAR,
+    },
     vm_error::VmError,
 };
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Class path. Use colon (:) as separator for entries
-    #[arg(short, long)]
-    classpath: Option<String>,
+    /// Class path. Use the platform path separator, i.e. colon (:) on Unix, between entries.
+    /// Can be given multiple times, and `-cp` is accepted as an alias like in `java`
+    #[arg(short, long, alias = "cp")]
+    classpath: Vec<String>,
+
+    /// Path of the `rt.jar` of the Java runtime. If not given, the one in the RJVM_RUNTIME_JAR
+    /// environment variable is used if it is set, otherwise it must be in the class path
+    #[arg(long)]
+    runtime: Option<String>,
 
     /// Class name to execute
     class_name: String,
@@ -26,17 +33,64 @@ struct Args {
     #[arg(short, long, default_value = DEFAULT_MAX_MEMORY_MB_STR)]
     maximum_mb_of_memory: usize,
 
+    /// Disable the garbage collector, so that all the memory can be used for objects,
+    /// but the execution fails once it is full
+    #[arg(long)]
+    no_gc: bool,
+
+    /// Maximum number of frames of the call stack, past which a StackOverflowError is thrown
+    #[arg(long, default_value_t = DEFAULT_MAX_CALL_STACK_DEPTH)]
+    max_stack_depth: usize,
+
+    /// Static method without parameters to invoke instead of `main`, given as
+    /// `name:descriptor`, for example `run:()I`. Its result is printed
+    #[arg(long, value_parser = parse_entry_method)]
+    entry_method: Option<EntryMethod>,
+
     /// Java program arguments
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     java_program_arguments: Vec<String>,
 }
 
+/// A static method to invoke instead of `main`
+#[derive(Debug, Clone, PartialEq)]
+struct EntryMethod {
+    name: String,
+    type_descriptor: String,
+}
+
+fn parse_entry_method(value: &str) -> Result<EntryMethod, String> {
+    match value.split_once(':') {
+        Some((name, type_descriptor)) if !name.is_empty() && type_descriptor.starts_with("()") => {
+            Ok(EntryMethod {
+                name: name.to_string(),
+                type_descriptor: type_descriptor.to_string(),
+            })
+        }
+        _ => Err(format!(
+            "expected name:descriptor of a method without parameters, got {value}"
+        )),
+    }
+}
+
 fn main() {
-    let args = Args::parse();
+    let args = Args::parse_from(rewrite_java_style_flags(std::env::args()));
     env_logger::init_from_env(
         env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, "info"),
     );
 
-    let result = run(args);
+    // The vm executes each java frame recursively, so it needs a native stack large
+    // enough for the maximum depth of the call stack
+    let stack_size = native_stack_size_for_call_stack_depth(args.max_stack_depth);
+    let result = std::thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(move || run(args))
+        .map_err(|err| format!("cannot create the thread executing the vm: {err}"))
+        .and_then(|thread| {
+            thread
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        });
     match result {
         Ok(exit_code) => std::process::exit(exit_code),
         Err(err) => {
@@ -46,78 +100,361 @@ fn main() {
     }
 }
 
+/// Rewrites `-cp`, which is not a valid short flag for clap, into `--cp`. Only the
+/// flags before the class name are rewritten, since the rest belong to the Java program.
+fn rewrite_java_style_flags(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut args = args.into_iter();
+    let mut result: Vec<String> = args.next().into_iter().collect();
+    let mut expecting_value = false;
+    for arg in args.by_ref() {
+        let is_flag = !expecting_value && arg.starts_with('-');
+        let is_class_name = !expecting_value && !is_flag;
+        expecting_value = is_flag
+            && matches!(
+                arg.as_str(),
+                "-c" | "--classpath"
+                    | "-cp"
+                    | "-m"
+                    | "--maximum-mb-of-memory"
+                    | "--max-stack-depth"
+                    | "--entry-method"
+                    | "--runtime"
+            );
+        if arg == "-cp" && is_flag {
+            result.push("--cp".to_string());
+        } else {
+            result.push(arg);
+        }
+        if is_class_name {
+            break;
+        }
+    }
+    result.extend(args);
+    result
+}
+
 fn append_classpath(vm: &mut Vm, args: &Args) -> Result<(), String> {
-    if let Some(classpath) = &args.classpath {
-        vm.append_class_path(classpath)
-            .map_err(|err| err.to_string())?;
+    for classpath in &args.classpath {
+        for entry in std::env::split_paths(classpath) {
+            let entry = entry
+                .to_str()
+                .ok_or_else(|| format!("invalid classpath entry: {}", entry.display()))?;
+            vm.append_class_path(entry).map_err(|err| err.to_string())?;
+        }
     }
     Ok(())
 }
 
-fn resolve_class_and_main_method<'a>(
-    vm: &mut Vm<'a>,
-    args: &Args,
-) -> Result<(&'a mut CallStack<'a>, ClassAndMethod<'a>), String> {
-    let call_stack = vm.allocate_call_stack();
-    let main_method = vm
-        .resolve_class_method(
-            call_stack,
-            &args.class_name,
-            "main",
-            "([Ljava/lang/String;)V",
-        )
-        .map_err(|v| match v {
-            MethodCallFailed::InternalError(VmError::ClassNotFoundException(name)) => {
-                format!("class not found: {name}")
-            }
-            MethodCallFailed::InternalError(VmError::MethodNotFoundException(..)) => {
-                "class does not contain a valid <main> method".to_string()
-            }
-            _ => format!("unexpected error: {:?}", v),
-        })?;
-    Ok((call_stack, main_method))
+fn run(args: Args) -> Result<i32, String> {
+    run_with_output(args, &mut std::io::stdout())
 }
 
-fn run(args: Args) -> Result<i32, String> {
-    let mut vm = Vm::new(args.maximum_mb_of_memory * ONE_MEGABYTE);
+/// Like [run], but the result of the entry method is written to the given output
+fn run_with_output(args: Args, output: &mut impl Write) -> Result<i32, String> {
+    if args.entry_method.is_some() && !args.java_program_arguments.is_empty() {
+        return Err("the entry method does not accept program arguments".to_string());
+    }
+    let allocation_strategy = if args.no_gc {
+        AllocationStrategy::BumpOnly
+    } else {
+        AllocationStrategy::SemiSpaceCopying
+    };
+    let max_memory = args
+        .maximum_mb_of_memory
+        .checked_mul(ONE_MEGABYTE)
+        .ok_or_else(|| "the maximum memory is too large".to_string())?;
+    let mut vm = Vm::with_allocation_strategy(max_memory, MemorySizing::Total, allocation_strategy)
+        .map_err(|err| err.to_string())?
+        .with_max_call_stack_depth(args.max_stack_depth);
+    vm = match &args.runtime {
+        Some(runtime) => vm.with_runtime(runtime),
+        None if std::env::var_os(RUNTIME_JAR_ENV_VAR).is_some() => vm.with_bundled_runtime(),
+        None => Ok(vm),
+    }
+    .map_err(|err| err.to_string())?;
     append_classpath(&mut vm, &args)?;
 
-    let (call_stack, main_method) = resolve_class_and_main_method(&mut vm, &args)?;
-
-    let main_args = allocate_java_args(&mut vm, call_stack, &args.java_program_arguments)
-        .map_err(|err| format!("{err:?}"))?;
-    let main_result = vm
-        .invoke(call_stack, main_method, None, vec![main_args])
-        .map_err(|v| format!("execution error: {:?}", v))?;
-
-    match main_result {
-        None => Ok(0),
-        Some(v) => Err(format!(
-            "<main> method should be void, but returned the value: {v:?}",
-        )),
+    let call_stack = vm.allocate_call_stack();
+    let result = match &args.entry_method {
+        Some(entry_method) => run_entry_method(&mut vm, call_stack, &args, entry_method),
+        None => vm
+            .run_main(call_stack, &args.class_name, &args.java_program_arguments)
+            .map(|exit_code| (exit_code, None)),
+    };
+    let (exit_code, printed_result) = result.map_err(|err| match err {
+        MethodCallFailed::InternalError(VmError::ClassNotFoundException(name)) => {
+            format!("class not found: {name}")
+        }
+        MethodCallFailed::InternalError(VmError::MethodNotFoundException(
+            class_name,
+            method_name,
+            _,
+        )) if class_name == args.class_name && method_name == "main" => {
+            "class does not contain a valid <main> method".to_string()
+        }
+        MethodCallFailed::InternalError(VmError::MethodNotFoundException(
+            class_name,
+            method_name,
+            type_descriptor,
+        )) if class_name == args.class_name
+            && args
+                .entry_method
+                .as_ref()
+                .is_some_and(|entry_method| entry_method.name == method_name) =>
+        {
+            format!("class does not contain the entry method {method_name}:{type_descriptor}")
+        }
+        MethodCallFailed::InternalError(VmError::MethodIsNotStatic(
+            _,
+            method_name,
+            type_descriptor,
+        )) => {
+            format!("the entry method {method_name}:{type_descriptor} is not static")
+        }
+        MethodCallFailed::ExceptionThrown(exception) => format_uncaught_exception(&vm, &exception),
+        _ => format!("execution error: {:?}", err),
+    })?;
+    if let Some(printed_result) = printed_result {
+        writeln!(output, "{printed_result}").map_err(|err| err.to_string())?;
     }
+    Ok(exit_code)
 }
 
-fn allocate_java_args<'a>(
+/// Invokes the given static method, and returns the exit code along with its formatted
+/// result, if it is not void. The exit code is zero, unless the program calls `System.exit`.
+fn run_entry_method<'a>(
     vm: &mut Vm<'a>,
     call_stack: &mut CallStack<'a>,
-    command_line_args: &[String],
-) -> Result<Value<'a>, MethodCallFailed<'a>> {
-    let class_id_java_lang_string = vm.get_or_resolve_class(call_stack, "java/lang/String")?.id;
-
-    let strings: Result<Vec<Value<'a>>, MethodCallFailed<'a>> = command_line_args
-        .iter()
-        .map(|s| new_java_lang_string_object(vm, call_stack, s).map(Value::Object))
-        .collect();
-
-    let strings = strings?;
-    let array = vm.new_array(
-        ArrayEntryType::Object(class_id_java_lang_string),
-        strings.len(),
-    );
+    args: &Args,
+    entry_method: &EntryMethod,
+) -> Result<(i32, Option<String>), MethodCallFailed<'a>> {
+    let result = match vm.invoke_static_by_name(
+        call_stack,
+        &args.class_name,
+        &entry_method.name,
+        &entry_method.type_descriptor,
+        vec![],
+    ) {
+        Ok(result) => result,
+        Err(MethodCallFailed::Exit(exit_code)) => return Ok((exit_code, None)),
+        Err(err) => return Err(err),
+    };
+    let formatted = result.map(|value| format_result(vm, &entry_method.type_descriptor, &value));
+    Ok((0, formatted))
+}
+
+/// Formats a value returned by the entry method like Java prints it. Since booleans and
+/// chars are represented as ints, we use the return type to format them properly.
+fn format_result<'a>(vm: &Vm<'a>, type_descriptor: &str, value: &Value<'a>) -> String {
+    let return_type = type_descriptor
+        .rsplit_once(')')
+        .map(|(_, return_type)| return_type);
+    match (return_type, value) {
+        (Some("Z"), Value::Int(int)) => (*int != 0).to_string(),
+        (Some("C"), Value::Int(int)) => char::from_u32(*int as u16 as u32)
+            .unwrap_or(char::REPLACEMENT_CHARACTER)
+            .to_string(),
+        _ => vm.format_value(value),
+    }
+}
+
+/// Formats an exception that was not caught by the program like the JVM does, i.e.
+/// with its class, message, and stack trace
+fn format_uncaught_exception<'a>(vm: &Vm<'a>, exception: &JavaException<'a>) -> String {
+    match exception.format_stack_trace(vm) {
+        Ok(lines) => format!("Exception in thread \"main\" {}", lines.join("\n")),
+        Err(err) => format!("execution error: {err:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use rjvm_vm::vm::{native_stack_size_for_call_stack_depth, DEFAULT_MAX_CALL_STACK_DEPTH};
+
+    use crate::{rewrite_java_style_flags, run, run_with_output, Args};
+
+    fn args_for(class_name: &str, java_program_arguments: Vec<String>) -> Args {
+        let vm_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../vm");
+        Args {
+            classpath: vec![format!("{vm_dir}/tests/resources")],
+            runtime: Some(format!("{vm_dir}/rt.jar")),
+            class_name: class_name.to_string(),
+            maximum_mb_of_memory: 10,
+            no_gc: false,
+            max_stack_depth: DEFAULT_MAX_CALL_STACK_DEPTH,
+            entry_method: None,
+            java_program_arguments,
+        }
+    }
+
+    #[test]
+    fn void_main_exits_with_zero() {
+        let args = args_for("rjvm/SimpleMain", vec![]);
+        assert_eq!(Ok(0), run(args));
+    }
+
+    #[test]
+    fn uncaught_exception_prints_the_stack_trace() {
+        let args = args_for("rjvm/UncaughtException", vec![]);
+        let error = run(args).expect_err("main should throw an exception");
+        let lines: Vec<&str> = error.lines().collect();
+        assert_eq!(
+            "Exception in thread \"main\" java.lang.IllegalStateException: something failed",
+            lines[0]
+        );
+        assert!(lines.contains(&"\tat rjvm.UncaughtException.fail(UncaughtException.java:10)"));
+        assert_eq!(
+            Some(&"\tat rjvm.UncaughtException.main(UncaughtException.java:5)"),
+            lines.last()
+        );
+    }
+
+    #[test]
+    fn uncaught_exception_prints_type_and_message() {
+        let args = args_for("rjvm/ThrowsRuntimeException", vec![]);
+        let error = run(args).expect_err("main should throw an exception");
+        assert_eq!(
+            Some("Exception in thread \"main\" java.lang.RuntimeException: boom"),
+            error.lines().next()
+        );
+    }
+
+    #[test]
+    fn int_main_return_value_is_the_exit_code() {
+        let args = args_for("rjvm/IntMain", vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(Ok(42), run(args));
+    }
+
+    #[test]
+    fn classpath_can_be_given_multiple_times_with_cp() {
+        let vm_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../vm");
+        let command_line = [
+            "rjvm_vm_cli".to_string(),
+            "-cp".to_string(),
+            format!("{vm_dir}/rt.jar"),
+            "-cp".to_string(),
+            format!("{vm_dir}/tests/resources"),
+            "rjvm/IntMain".to_string(),
+            "-cp".to_string(),
+        ];
+        let args = Args::try_parse_from(rewrite_java_style_flags(command_line))
+            .expect("should be able to parse the arguments");
+        assert_eq!(2, args.classpath.len());
+        assert_eq!(vec!["-cp".to_string()], args.java_program_arguments);
+        assert_eq!(Ok(41), run(args));
+    }
+
+    #[test]
+    fn missing_runtime_is_reported() {
+        let mut args = args_for("rjvm/SimpleMain", vec![]);
+        args.runtime = Some("/does/not/exist/rt.jar".to_string());
+        let error = run(args).expect_err("should not find the runtime");
+        assert!(error.starts_with("cannot find the Java runtime at /does/not/exist/rt.jar"));
+    }
+
+    #[test]
+    fn system_exit_status_is_the_exit_code() {
+        let args = args_for("rjvm/SystemExit", vec![]);
+        assert_eq!(Ok(7), run(args));
+    }
+
+    fn run_entry_method(entry_method: &str) -> (Result<i32, String>, String) {
+        let vm_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../vm");
+        let command_line = [
+            "rjvm_vm_cli".to_string(),
+            "--runtime".to_string(),
+            format!("{vm_dir}/rt.jar"),
+            "-cp".to_string(),
+            format!("{vm_dir}/tests/resources"),
+            "--entry-method".to_string(),
+            entry_method.to_string(),
+            "rjvm/EntryMethod".to_string(),
+        ];
+        let args = Args::try_parse_from(rewrite_java_style_flags(command_line))
+            .expect("should be able to parse the arguments");
+        let mut output = Vec::new();
+        let result = run_with_output(args, &mut output);
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn entry_method_result_is_printed() {
+        assert_eq!((Ok(0), "42\n".to_string()), run_entry_method("run:()I"));
+        assert_eq!(
+            (Ok(0), "hello from greet\n".to_string()),
+            run_entry_method("greet:()Ljava/lang/String;")
+        );
+        assert_eq!((Ok(0), "".to_string()), run_entry_method("nothing:()V"));
+        assert_eq!(
+            (Ok(0), "true\n".to_string()),
+            run_entry_method("isReady:()Z")
+        );
+        assert_eq!((Ok(0), "r\n".to_string()), run_entry_method("initial:()C"));
+    }
+
+    #[test]
+    fn entry_method_must_be_static() {
+        let (result, _) = run_entry_method("notStatic:()I");
+        assert_eq!(
+            Err("the entry method notStatic:()I is not static".to_string()),
+            result
+        );
+    }
+
+    #[test]
+    fn entry_method_must_exist() {
+        let (result, _) = run_entry_method("missing:()I");
+        assert_eq!(
+            Err("class does not contain the entry method missing:()I".to_string()),
+            result
+        );
+    }
+
+    #[test]
+    fn entry_method_must_not_have_parameters() {
+        let vm_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../vm");
+        let result = Args::try_parse_from([
+            "rjvm_vm_cli",
+            "--classpath",
+            vm_dir,
+            "--entry-method",
+            "run:(I)I",
+            "rjvm/EntryMethod",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn max_stack_depth_can_be_configured() {
+        let vm_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../vm");
+        let args = Args::parse_from(rewrite_java_style_flags(
+            [
+                "rjvm_vm_cli",
+                "--runtime",
+                &format!("{vm_dir}/rt.jar"),
+                "-cp",
+                &format!("{vm_dir}/tests/resources"),
+                "--max-stack-depth",
+                "50",
+                "rjvm/StackOverflow",
+            ]
+            .map(String::from),
+        ));
+        assert_eq!(50, args.max_stack_depth);
 
-    for (index, string) in strings.into_iter().enumerate() {
-        array.set_element(index, string)?;
+        let result = std::thread::Builder::new()
+            .stack_size(native_stack_size_for_call_stack_depth(50))
+            .spawn(move || run(args))
+            .expect("should be able to spawn the thread")
+            .join()
+            .expect("the vm should not panic");
+        let message = result.expect_err("sum should overflow the stack");
+        assert!(
+            message.starts_with(
+                "Exception in thread \"main\" java.lang.StackOverflowError: call stack depth exceeded 50"
+            ),
+            "{message}"
+        );
     }
-    Ok(Value::Object(array))
 }